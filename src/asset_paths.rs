@@ -1,3 +1,25 @@
 
 pub const FONT_MONO_LIGHT: &str = "fonts/RedHatMono-Light.ttf";
-pub const FONT_MONO_BOLD: &str = "fonts/RedHatMono-Bold.ttf";
\ No newline at end of file
+pub const FONT_MONO_BOLD: &str = "fonts/RedHatMono-Bold.ttf";
+
+pub const SHAPE_ROCKET: &str = "shapes/rocket.shape.ron";
+pub const SHAPE_ROCKET_EXHAUST: &str = "shapes/rocket_exhaust.shape.ron";
+pub const SHAPE_ALIEN_UFO: &str = "shapes/alien_ufo.shape.ron";
+pub const SHAPE_BULLET: &str = "shapes/bullet.shape.ron";
+pub const SHAPE_BULLET_PULSE: &str = "shapes/bullet_pulse.shape.ron";
+pub const SHAPE_BULLET_SLUG: &str = "shapes/bullet_slug.shape.ron";
+pub const SHAPE_MINE: &str = "shapes/mine.shape.ron";
+pub const SHAPE_MISSILE: &str = "shapes/missile.shape.ron";
+pub const SHAPE_MOTHERSHIP_HULL: &str = "shapes/mothership_hull.shape.ron";
+pub const SHAPE_BOSS_TURRET: &str = "shapes/boss_turret.shape.ron";
+pub const SHAPE_BOSS_SHIELD: &str = "shapes/boss_shield.shape.ron";
+pub const SHAPE_BOSS_CORE: &str = "shapes/boss_core.shape.ron";
+pub const SHAPE_ASTEROID_A: &str = "shapes/asteroid_a.shape.ron";
+pub const SHAPE_ASTEROID_B: &str = "shapes/asteroid_b.shape.ron";
+pub const SHAPE_ASTEROID_C: &str = "shapes/asteroid_c.shape.ron";
+pub const SHAPE_ASTEROID_D: &str = "shapes/asteroid_d.shape.ron";
+pub const SHAPE_ASTEROID_E: &str = "shapes/asteroid_e.shape.ron";
+
+pub const LEVEL_CHALLENGE: &str = "levels/challenge.level.ron";
+
+pub const SCRIPT_WAVES: &str = "scripts/waves.rhai";
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::render::camera::OrthographicProjection;
+use crate::game::manager::GameCleanup;
+
+// Camera effects
+//
+// Gameplay systems push "trauma" (and optional zoom punches) into `CameraEffects`. Trauma decays
+// over time and drives a noise-based shake of the camera. Only the camera moves, so the world
+// coordinates used by gameplay are unaffected.
+//
+// See: "Math for Game Programmers: Juicing Your Cameras With Math" (GDC 2016)
+
+const TRAUMA_DECAY_PER_SEC: f32 = 1.2;
+const ZOOM_PUNCH_DECAY_PER_SEC: f32 = 0.6;
+const SHAKE_MAX_OFFSET: f32 = 4.0; // world units
+const SHAKE_MAX_ROLL: f32 = 0.03; // rads
+const SHAKE_FREQUENCY: f32 = 25.0; // noise samples per second
+const ZOOM_PUNCH_MAX: f32 = 0.25;
+
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CameraEffects::default());
+        app.add_systems(Update, camera_effects_system);
+        app.add_systems(GameCleanup, camera_effects_reset_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraEffects {
+    /// Accessibility setting. When disabled the camera never shakes or zooms.
+    pub shake_enabled: bool,
+    /// (0.0, 1.0)
+    trauma: f32,
+    /// (0.0, ZOOM_PUNCH_MAX)
+    zoom_punch: f32,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self { shake_enabled: true, trauma: 0., zoom_punch: 0. }
+    }
+}
+
+impl CameraEffects {
+    /// Adds trauma in the range (0.0, 1.0). Intensity of the shake is the square of the accumulated trauma.
+    pub fn add_trauma(&mut self, amount: f32) {
+        if self.shake_enabled {
+            self.trauma = (self.trauma + amount).clamp(0., 1.);
+        }
+    }
+
+    /// Briefly zooms the camera in. `amount` is the fraction of the view to zoom by.
+    pub fn punch_zoom(&mut self, amount: f32) {
+        if self.shake_enabled {
+            self.zoom_punch = (self.zoom_punch + amount).clamp(0., ZOOM_PUNCH_MAX);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.trauma = 0.;
+        self.zoom_punch = 0.;
+    }
+}
+
+// Systems
+
+fn camera_effects_system(
+    time: Res<Time>,
+    mut effects: ResMut<CameraEffects>,
+    mut query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>
+) {
+    if !effects.shake_enabled {
+        effects.clear();
+    }
+
+    let t_secs = time.delta_seconds();
+    effects.trauma = (effects.trauma - TRAUMA_DECAY_PER_SEC * t_secs).max(0.);
+    effects.zoom_punch = (effects.zoom_punch - ZOOM_PUNCH_DECAY_PER_SEC * t_secs).max(0.);
+
+    let shake = effects.trauma * effects.trauma;
+    let x = time.elapsed_seconds() * SHAKE_FREQUENCY;
+    let offset = Vec2::new(noise_1d(1, x), noise_1d(2, x)) * SHAKE_MAX_OFFSET * shake;
+    let roll = noise_1d(3, x) * SHAKE_MAX_ROLL * shake;
+    let scale = 1.0 - effects.zoom_punch;
+
+    // NOTE: The camera rests at the origin
+    for (mut transform, mut projection) in query.iter_mut() {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+        transform.rotation = Quat::from_rotation_z(roll);
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
+
+fn camera_effects_reset_system(mut effects: ResMut<CameraEffects>) {
+    effects.clear();
+}
+
+// Noise
+
+/// Smooth 1D value noise in the range (-1.0, 1.0)
+fn noise_1d(seed: u32, x: f32) -> f32 {
+    let i = x.floor();
+    let f = x - i;
+    let a = hash_to_unit(seed, i as i32);
+    let b = hash_to_unit(seed, i as i32 + 1);
+    let t = f * f * (3.0 - 2.0 * f); // smoothstep
+    a + (b - a) * t
+}
+
+fn hash_to_unit(seed: u32, n: i32) -> f32 {
+    let mut h = (n as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    (h as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
@@ -1,23 +1,38 @@
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
 use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Themed, ThemeColor};
 use super::FrameStage;
 use super::assets::GameAssets;
-use super::hit::{HitEvent, distinct_hit_events};
-use super::manager::GameCleanup;
+use super::asteroid::{Asteroid, AsteroidCollidable};
+use super::hit::{BlastHitEvent, HitEvent, blast_hit_entities, distinct_hit_events};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::{GameCleanup, WorldBoundaries};
 use super::movable::{Movable, MovableTorusConstraint};
-use super::collidable::{Collidable, Collider};
-use super::explosion::{ExplosionShapeId, SpawnExplosion, spawn_explosion};
-use super::bullet::{BulletController, BulletCollidable, BulletFireResult, BulletSpawn, spawn_bullet, BulletSource};
+use super::collidable::Collidable;
+use super::explosion::{ExplosionCollidable, SpawnExplosion, spawn_explosion};
+use super::bullet::{BulletController, BulletCollidable, BulletFireResult, BulletShape, BulletSpawn, spawn_bullet, BulletSource};
 use super::player::PlayerRocket;
-use super::svg::simple_svg_to_path;
+use super::particle::{ParticleEmitter, ParticleKind};
+use super::mine::MineLayer;
+use super::missile::{MissileSpawn, spawn_missile};
+use super::shape::{ShapeCollider, VectorShape};
+use super::util::*;
 
 // Player's Rocket
 
 const ALIEN_BULLET_SPEED: f32 = 125.0;
 const ALIEN_BULLET_MAX_AGE_SECS: f32 = 2.0;
-const ALIEN_FIRE_RATE: f32 = 0.5; // per second
+const ALIEN_MISSILE_LAUNCH_SPEED: f32 = 20.0;
+const ALIEN_MISSILE_FUEL_SECS: f32 = 4.0;
 const ALIEN_Z: f32 = 10.0;
+const ALIEN_ENGINE_PARTICLE_RATE: f32 = 20.0; // per second
+const ALIEN_MINE_DROP_SECS: f32 = 6.0;
+const ALIEN_AVOID_RANGE: f32 = 40.0;
+const ALIEN_AVOID_STRENGTH: f32 = 80.0;
+const ALIEN_CRUISE_CORRECTION: f32 = 1.5; // per second
 
 pub struct AlienPlugin;
 
@@ -30,6 +45,14 @@ impl Plugin for AlienPlugin {
                 alien_bullet_system
                     .after(FrameStage::Movement),
 
+                alien_avoidance_system
+                    .after(FrameStage::Input)
+                    .before(FrameStage::Movement),
+
+                alien_collision_system
+                    .in_set(FrameStage::Collision)
+                    .after(FrameStage::Movement),
+
                 alien_hit_system
                     .in_set(FrameStage::CollisionEffect)
                     .after(FrameStage::Collision),
@@ -43,30 +66,36 @@ impl Plugin for AlienPlugin {
 // Events
 
 #[derive(Event)]
-pub struct AlienUfoDestroyedEvent;
+pub struct AlienUfoDestroyedEvent {
+    pub variant: AlienUfoVariant,
+    pub destroyed_by: AlienUfoDestroyedBy,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlienUfoDestroyedBy {
+    /// Shot down (or blown up) by the player
+    PlayerWeapon,
+    /// Rammed by the player's rocket
+    PlayerRocket,
+    /// Flew into an asteroid
+    Asteroid,
+    /// Caught in a mine blast
+    Blast,
+}
 
 // Setup
 
 pub struct AlienAssets {
-    alien_ufo_dimension: (f32, f32), // (w, h) of the rocket shape
+    alien_ufo_dimension: Vec2, // (w, h) of the ufo shape
+    alien_ufo_collider: ShapeCollider,
     alien_ufo_shape: Path,
 }
 
-pub fn create_alien_assets() -> AlienAssets {
-    // See: https://yqnn.github.io/svg-path-editor/
-    let alien_ufo_dimension = (10.0, 6.0);
-    let alien_ufo_path = "
-        M 5 0
-        C 2 -2 -2 -2 -5 0
-        L -5 1
-        C -2 3 2 3 5 1 L 5 0
-        M 3 -1
-        Q 0 -5 -3 -1
-    ";
-
+pub fn create_alien_assets(alien_ufo: &VectorShape) -> AlienAssets {
     AlienAssets {
-        alien_ufo_dimension,
-        alien_ufo_shape: simple_svg_to_path(alien_ufo_path),
+        alien_ufo_dimension: alien_ufo.size,
+        alien_ufo_collider: alien_ufo.collider,
+        alien_ufo_shape: Path(alien_ufo.path.0.clone()),
     }
 }
 
@@ -82,15 +111,77 @@ fn alien_teardown_system(mut commands: Commands, query: Query<Entity, With<Alien
 
 // Entity
 
-#[derive(Component, Default)]
-pub struct AlienUfo;
+#[derive(Component)]
+pub struct AlienUfo {
+    variant: AlienUfoVariant,
+    /// Shots are fired up to this many radians either side of the target
+    pub aim_error: f32,
+}
+
+impl AlienUfo {
+    pub fn new(variant: AlienUfoVariant) -> Self {
+        Self { variant, aim_error: 0.0 }
+    }
+
+    pub fn variant(&self) -> AlienUfoVariant {
+        self.variant
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum AlienUfoVariant {
+    /// Slow firing, lays mines
+    Large,
+    /// Fires homing missiles
+    Small,
+}
+
+impl AlienUfoVariant {
+    pub const VALUES: [Self; 2] = [ Self::Large, Self::Small ];
+
+    fn scale(self) -> f32 {
+        match self {
+            AlienUfoVariant::Large => 1.0,
+            AlienUfoVariant::Small => 0.6,
+        }
+    }
+
+    fn weapon(self) -> AlienWeapon {
+        match self {
+            AlienUfoVariant::Large => AlienWeapon::Bullet,
+            AlienUfoVariant::Small => AlienWeapon::HomingMissile,
+        }
+    }
+
+    /// shots per second
+    pub fn fire_rate(self) -> f32 {
+        match self {
+            AlienUfoVariant::Large => 0.5,
+            AlienUfoVariant::Small => 0.25,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum AlienWeapon {
+    Bullet,
+    HomingMissile,
+}
+
+/// Steers the UFO around asteroids in its path, then back onto its original course
+#[derive(Component)]
+struct AsteroidAvoidance {
+    cruise_velocity: Vec2,
+}
 
 // Spawning
 
 #[derive(Clone)]
 pub struct AlienSpawn {
+    pub variant: AlienUfoVariant,
     pub position: Vec2,
     pub velocity: Vec2,
+    pub avoid_asteroids: bool,
 }
 
 const LINE_WIDTH: f32 = 0.2;
@@ -103,48 +194,61 @@ pub fn spawn_alien_ufo(
      // Spawn stationary, in the middle of the screen
     let position = spawn.position;
     let velocity = spawn.velocity;
-    let (width, height) = assets.alien_ufo_dimension;
+    let variant = spawn.variant;
+    let scale = variant.scale();
+    let height = assets.alien_ufo_dimension.y * scale;
 
-    // Rocket
-    let alien_color = Color::rgba(1., 1., 1., 1.);
-    let alien_stroke = Stroke::new(alien_color, LINE_WIDTH);
+    // Ufo
+    // NOTE: Colour is applied by the theme
+    let alien_stroke = Stroke::new(Color::WHITE, LINE_WIDTH / scale);
 
     // Transform
-    let transform = Transform::from_translation(Vec3::new(position.x, position.y, ALIEN_Z));
+    let transform = Transform::from_translation(Vec3::new(position.x, position.y, ALIEN_Z))
+        .with_scale(Vec3::splat(scale));
     
     // Collision detection
-    let arm = Vec2::new(width / 2., 0.);
     let radius = height / 2.;
-    let collider = Collider::capsule(position, arm, radius);
+    let collider = assets.alien_ufo_collider.to_collider(position, scale);
 
     // Bullet control
-    let mut bullet_controller = BulletController::new(ALIEN_FIRE_RATE);
+    let mut bullet_controller = BulletController::new(variant.fire_rate());
     bullet_controller.try_set_firing_state(true);
 
-    commands
-        .spawn((
-            AlienUfo,
-            Movable {
-                position,
-                velocity,
-                acceleration: None,
-                heading_angle: 0.0,
-                rotational_velocity: 0.,
-                rotational_acceleration: None,
-            },
-            MovableTorusConstraint { radius },
-            bullet_controller,
-            // Collision detection
-            BulletCollidable { source: BulletSource::PlayerRocket },
-            Collidable { collider },
-            // Rendering
-            ShapeBundle {
-                path: Path(assets.alien_ufo_shape.0.clone()),
-                transform,
-                ..default()
-            },
-            alien_stroke
-        ));
+    // Engine glow, drifting down from beneath the hull whichever way the UFO is flying
+    let engine_particles = ParticleEmitter::new(ALIEN_ENGINE_PARTICLE_RATE, ParticleKind::Dot, ThemeColor::Alien)
+        .with_offset(Vec2::new(0.0, -height / 4.))
+        .with_direction(-std::f32::consts::FRAC_PI_2, 0.6)
+        .with_speed(5.0, 10.0)
+        .with_lifetime(0.2, 0.5);
+
+    let mut ufo = commands.spawn((
+        AlienUfo::new(variant),
+        Movable::new(position, velocity),
+        MovableTorusConstraint { radius },
+        bullet_controller,
+        engine_particles,
+    ));
+    if variant == AlienUfoVariant::Large {
+        ufo.insert(MineLayer::new(ALIEN_MINE_DROP_SECS));
+    }
+    if spawn.avoid_asteroids {
+        ufo.insert(AsteroidAvoidance { cruise_velocity: velocity });
+    }
+    ufo.insert((
+        // Collision detection
+        AsteroidCollidable,
+        BulletCollidable { source: BulletSource::PlayerRocket },
+        ExplosionCollidable,
+        Collidable { collider },
+        // Rendering
+        ShapeBundle {
+            path: Path(assets.alien_ufo_shape.0.clone()),
+            transform,
+            ..default()
+        },
+        alien_stroke,
+        Themed(ThemeColor::Alien),
+    ));
 }
 
 // Bullet system
@@ -153,7 +257,7 @@ fn alien_bullet_system(
     time: Res<Time>,
     assets: Res<GameAssets>,
     mut commands: Commands,
-    mut ufo_query: Query<(&Movable, &mut BulletController), With<AlienUfo>>,
+    mut ufo_query: Query<(Entity, &AlienUfo, &Movable, &mut BulletController)>,
     player_rocket_query: Query<&Movable, With<PlayerRocket>>
 ) {
     // Find a target to fire at
@@ -162,18 +266,39 @@ fn alien_bullet_system(
         None => return,
     };
 
-    for (source, mut controller) in ufo_query.iter_mut() {
-        if controller.update(&time) == BulletFireResult::FireBullet {
-            let firing_normal = calculate_firing_normal(source, target);
-            let translation = controller.spawn_translation.unwrap_or_default();
-            let velocity = firing_normal * ALIEN_BULLET_SPEED;
-            spawn_bullet(&mut commands, &assets.bullet, BulletSpawn {
-                source: BulletSource::AlienUfo,
-                position: source.position + translation,
-                velocity: source.velocity + velocity,
-                heading_angle: Vec2::X.angle_between(firing_normal),
-                despawn_after_secs: ALIEN_BULLET_MAX_AGE_SECS,
-            });
+    let mut rng = rand::thread_rng();
+    for (entity, ufo, source, mut controller) in ufo_query.iter_mut() {
+        if controller.update(&time) != BulletFireResult::FireBullet {
+            continue;
+        }
+        let aim_error = (rng.random_f32() * 2. - 1.) * ufo.aim_error;
+        let firing_normal = Vec2::from_angle(aim_error).rotate(calculate_firing_normal(source, target));
+        let translation = controller.spawn_translation.unwrap_or_default();
+        match ufo.variant.weapon() {
+            AlienWeapon::Bullet => {
+                let velocity = firing_normal * ALIEN_BULLET_SPEED;
+                spawn_bullet(&mut commands, &assets.bullet, BulletSpawn {
+                    source: BulletSource::AlienUfo,
+                    owner: entity,
+                    shape: BulletShape::Standard,
+                    scale: 1.0,
+                    damage: 1,
+                    pierce: 0,
+                    position: source.position + translation,
+                    velocity: source.velocity + velocity,
+                    heading_angle: Vec2::X.angle_between(firing_normal),
+                    despawn_after_secs: ALIEN_BULLET_MAX_AGE_SECS,
+                });
+            },
+            AlienWeapon::HomingMissile => {
+                let velocity = firing_normal * ALIEN_MISSILE_LAUNCH_SPEED;
+                spawn_missile(&mut commands, &assets.missile, MissileSpawn {
+                    position: source.position + translation,
+                    velocity: source.velocity + velocity,
+                    heading_angle: Vec2::X.angle_between(firing_normal),
+                    fuel_secs: ALIEN_MISSILE_FUEL_SECS,
+                });
+            },
         }
     }
 }
@@ -183,34 +308,108 @@ fn calculate_firing_normal(source: &Movable, target: &Movable) -> Vec2 {
     (target.position - source.position).normalize()
 }
 
+// Asteroid avoidance
+
+fn alien_avoidance_system(
+    world_boundaries: Res<WorldBoundaries>,
+    mut ufos: Query<(&mut Movable, &AsteroidAvoidance), With<AlienUfo>>,
+    asteroids: Query<&Movable, (With<Asteroid>, Without<AlienUfo>)>,
+) {
+    for (mut movable, avoidance) in ufos.iter_mut() {
+        let heading = movable.velocity.normalize_or_zero();
+        let side = heading.perp();
+        let mut acceleration = Vec2::ZERO;
+        // Push sideways away from asteroids ahead, harder the closer they are
+        for asteroid in asteroids.iter() {
+            let delta = world_boundaries.torus_delta(movable.position, asteroid.position);
+            let distance = delta.length();
+            if distance > ALIEN_AVOID_RANGE || delta.dot(heading) < 0. {
+                continue;
+            }
+            let away = if delta.dot(side) > 0. { -side } else { side };
+            acceleration += away * ALIEN_AVOID_STRENGTH * (1. - distance / ALIEN_AVOID_RANGE);
+        }
+        // Drift back towards the original course
+        acceleration += (avoidance.cruise_velocity - movable.velocity) * ALIEN_CRUISE_CORRECTION;
+        let force = acceleration * movable.mass;
+        movable.add_force(force);
+    }
+}
+
+// Collision detection
+
+fn alien_collision_system(
+    ufos: Query<(Entity, &Collidable), With<AlienUfo>>,
+    rockets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<PlayerRocket>>,
+    mut hit_events: EventWriter<HitEvent>
+) {
+    // NOTE: Asteroid collisions are handled by the asteroid (see `AsteroidCollidable`)
+    for (ufo, ufo_collidable) in ufos.iter() {
+        for (rocket, rocket_collidable, invulnerable) in rockets.iter() {
+            if invulnerable.is_invulnerable() {
+                continue;
+            }
+            if ufo_collidable.test_collision_with(rocket_collidable) {
+                hit_events.send(HitEvent(ufo));
+                hit_events.send(HitEvent(rocket));
+            }
+        }
+    }
+}
+
 // Destruction system
 
 static PLAYER_ALIEN_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 3.0;
+static ALIEN_UFO_DESTROYED_TRAUMA: f32 = 0.5;
+static ALIEN_UFO_DESTROYED_ZOOM_PUNCH: f32 = 0.05;
+
+/// Things which destroy the UFO by flying into it
+type AlienUfoRammer = Or<(With<Asteroid>, With<PlayerRocket>)>;
 
 fn alien_hit_system(
     mut commands: Commands,
     mut hit_events: EventReader<HitEvent>,
+    mut blast_hit_events: EventReader<BlastHitEvent>,
     mut alien_destroyed: EventWriter<AlienUfoDestroyedEvent>,
-    assets: Res<GameAssets>,
-    query: Query<&Movable, With<AlienUfo>>
+    mut camera_effects: ResMut<CameraEffects>,
+    query: Query<(&AlienUfo, &Movable, &Path, &Collidable)>,
+    others: Query<(&Collidable, Option<&Asteroid>), AlienUfoRammer>,
 ) {
-    for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
-        if let Ok(movable) = query.get(entity) {
+    let events = distinct_hit_events(&mut hit_events).map(|e| e.0).collect::<Vec<_>>();
+    let blasted = blast_hit_entities(&mut blast_hit_events);
+    for &entity in events.iter() {
+        if let Ok((ufo, movable, path, collidable)) = query.get(entity) {
+            // Work out who was responsible, from whatever else was hit by touching the UFO
+            let mut destroyed_by = AlienUfoDestroyedBy::PlayerWeapon;
+            for (other, asteroid) in events.iter().filter_map(|&e| others.get(e).ok()) {
+                if other.test_collision_with(collidable) {
+                    destroyed_by = match asteroid {
+                        Some(_) => AlienUfoDestroyedBy::Asteroid,
+                        None => AlienUfoDestroyedBy::PlayerRocket,
+                    };
+                }
+            }
+            if blasted.contains(&entity) {
+                destroyed_by = AlienUfoDestroyedBy::Blast;
+            }
             let mut rng = rand::thread_rng();
             // Despawn the entity
             commands.entity(entity).despawn_recursive();
             // Start the explosion
-            spawn_explosion(&mut commands, &mut rng, &assets.explosion, SpawnExplosion {
-                shape_id: ExplosionShapeId::UfoDebris,
-                shape_scale: 1.0,
+            spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+                shape: path,
+                shape_scale: ufo.variant.scale(),
                 position: movable.position,
                 velocity: movable.velocity,
                 heading_angle: movable.heading_angle,
                 rotational_velocity: std::f32::consts::PI,
                 despawn_after_secs: PLAYER_ALIEN_EXPLOSION_DESPAWN_AFTER_SECS,
+                shockwave_radius: None,
             });
+            camera_effects.add_trauma(ALIEN_UFO_DESTROYED_TRAUMA);
+            camera_effects.punch_zoom(ALIEN_UFO_DESTROYED_ZOOM_PUNCH);
             // Send events
-            alien_destroyed.send(AlienUfoDestroyedEvent);
+            alien_destroyed.send(AlienUfoDestroyedEvent { variant: ufo.variant, destroyed_by });
         }
     }
 }
\ No newline at end of file
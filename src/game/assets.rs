@@ -1,31 +1,143 @@
 use bevy::prelude::*;
+use bevy::asset::{HandleId, LoadState};
 
-use super::explosion;
-use super::asteroid;
+use crate::asset_paths;
+use super::asteroid::{self, AsteroidShapeId};
 use super::player;
 use super::bullet;
 use super::alien;
+use super::mine;
+use super::missile;
+use super::boss;
+use super::shape::{VectorShape, VectorShapeLoader};
 
 pub struct AssetsPlugin;
 
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
         // Assets
-        app.insert_resource(GameAssets {
-            explosion: explosion::create_explosion_assets(),
-            asteroid: asteroid::create_asteroid_assets(),
-            rocket: player::create_roket_assets(),
-            alien: alien::create_alien_assets(),
-            bullet: bullet::create_bullet_assets(),
-        });
+        app.add_asset::<VectorShape>();
+        app.init_asset_loader::<VectorShapeLoader>();
+        app.add_systems(Startup, load_shapes_system);
+        app.add_systems(Update, game_assets_update_system);
     }
 }
 
+/// Handles to the shape files used to build `GameAssets`
+#[derive(Clone)]
+pub struct GameShapeHandles {
+    pub asteroid: Vec<(AsteroidShapeId, Handle<VectorShape>)>,
+    pub rocket: Handle<VectorShape>,
+    pub rocket_exhaust: Handle<VectorShape>,
+    pub alien_ufo: Handle<VectorShape>,
+    pub bullet: Handle<VectorShape>,
+    pub bullet_pulse: Handle<VectorShape>,
+    pub bullet_slug: Handle<VectorShape>,
+    pub mine: Handle<VectorShape>,
+    pub missile: Handle<VectorShape>,
+    pub mothership_hull: Handle<VectorShape>,
+    pub boss_turret: Handle<VectorShape>,
+    pub boss_shield: Handle<VectorShape>,
+    pub boss_core: Handle<VectorShape>,
+}
+
+impl GameShapeHandles {
+    fn ids(&self) -> impl Iterator<Item=HandleId> + '_ {
+        self.asteroid.iter().map(|(_, h)| h)
+            .chain([&self.rocket, &self.rocket_exhaust, &self.alien_ufo, &self.bullet, &self.mine, &self.missile])
+            .chain([&self.bullet_pulse, &self.bullet_slug])
+            .chain([&self.mothership_hull, &self.boss_turret, &self.boss_shield, &self.boss_core])
+            .map(|h| h.id())
+    }
+}
+
+/// Shapes which are still being loaded. Replaced by `GameAssets` once loading completes.
+#[derive(Resource)]
+struct LoadingGameShapes(GameShapeHandles);
+
+/// Inserted instead of `GameAssets` if any shape file fails to load (the game can't start without them)
+#[derive(Resource)]
+pub struct GameAssetsFailed;
+
+/// NOTE: Only available once all shape files have loaded
 #[derive(Resource)]
 pub struct GameAssets {
-    pub explosion: explosion::ExplosionAssets,
+    /// NOTE: Held so the shape files stay loaded
+    #[allow(dead_code)]
+    pub shapes: GameShapeHandles,
     pub asteroid: asteroid::AsteroidAssets,
     pub rocket: player::RocketAssets,
     pub alien: alien::AlienAssets,
     pub bullet: bullet::BulletAssets,
-}
\ No newline at end of file
+    pub mine: mine::MineAssets,
+    pub missile: missile::MissileAssets,
+    pub boss: boss::BossAssets,
+}
+
+fn load_shapes_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let asteroid_paths = [
+        (AsteroidShapeId::A, asset_paths::SHAPE_ASTEROID_A),
+        (AsteroidShapeId::B, asset_paths::SHAPE_ASTEROID_B),
+        (AsteroidShapeId::C, asset_paths::SHAPE_ASTEROID_C),
+        (AsteroidShapeId::D, asset_paths::SHAPE_ASTEROID_D),
+        (AsteroidShapeId::E, asset_paths::SHAPE_ASTEROID_E),
+    ];
+    commands.insert_resource(LoadingGameShapes(GameShapeHandles {
+        asteroid: asteroid_paths.into_iter().map(|(id, path)| (id, asset_server.load(path))).collect(),
+        rocket: asset_server.load(asset_paths::SHAPE_ROCKET),
+        rocket_exhaust: asset_server.load(asset_paths::SHAPE_ROCKET_EXHAUST),
+        alien_ufo: asset_server.load(asset_paths::SHAPE_ALIEN_UFO),
+        bullet: asset_server.load(asset_paths::SHAPE_BULLET),
+        bullet_pulse: asset_server.load(asset_paths::SHAPE_BULLET_PULSE),
+        bullet_slug: asset_server.load(asset_paths::SHAPE_BULLET_SLUG),
+        mine: asset_server.load(asset_paths::SHAPE_MINE),
+        missile: asset_server.load(asset_paths::SHAPE_MISSILE),
+        mothership_hull: asset_server.load(asset_paths::SHAPE_MOTHERSHIP_HULL),
+        boss_turret: asset_server.load(asset_paths::SHAPE_BOSS_TURRET),
+        boss_shield: asset_server.load(asset_paths::SHAPE_BOSS_SHIELD),
+        boss_core: asset_server.load(asset_paths::SHAPE_BOSS_CORE),
+    }));
+}
+
+fn game_assets_update_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    shapes: Res<Assets<VectorShape>>,
+    loading: Option<Res<LoadingGameShapes>>,
+) {
+    // Build the game assets once loading completes
+    let Some(loading) = loading else { return };
+    let handles = &loading.0;
+
+    match asset_server.get_group_load_state(handles.ids()) {
+        LoadState::Loaded => {},
+        LoadState::Failed => {
+            for id in handles.ids().filter(|&id| asset_server.get_load_state(id) == LoadState::Failed) {
+                let path = asset_server.get_handle_path(id).map(|p| p.path().display().to_string());
+                error!("Failed to load shape {}", path.as_deref().unwrap_or("(unknown)"));
+            }
+            commands.insert_resource(GameAssetsFailed);
+            commands.remove_resource::<LoadingGameShapes>();
+            return;
+        },
+        _ => return,
+    }
+
+    let shape = |handle: &Handle<VectorShape>| shapes.get(handle).unwrap();
+    commands.insert_resource(GameAssets {
+        shapes: handles.clone(),
+        asteroid: asteroid::create_asteroid_assets(handles.asteroid.iter().map(|(id, h)| (*id, shape(h)))),
+        rocket: player::create_roket_assets(shape(&handles.rocket), shape(&handles.rocket_exhaust)),
+        alien: alien::create_alien_assets(shape(&handles.alien_ufo)),
+        bullet: bullet::create_bullet_assets(shape(&handles.bullet), shape(&handles.bullet_pulse), shape(&handles.bullet_slug)),
+        mine: mine::create_mine_assets(shape(&handles.mine)),
+        missile: missile::create_missile_assets(shape(&handles.missile)),
+        boss: boss::create_boss_assets(
+            shape(&handles.mothership_hull),
+            shape(&handles.boss_turret),
+            shape(&handles.boss_shield),
+            shape(&handles.boss_core),
+        ),
+    });
+    commands.remove_resource::<LoadingGameShapes>();
+}
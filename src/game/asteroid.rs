@@ -2,17 +2,22 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_prototype_lyon::prelude::*;
 use rand::thread_rng;
+use serde::{Deserialize, Serialize};
 use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Themed, ThemeColor};
 use super::FrameStage;
-use super::assets::GameAssets;
-use super::bullet::{BulletCollidable, BulletSource};
-use super::hit::{HitEvent, distinct_hit_events};
+use super::alien::AlienUfo;
+use super::bullet::{BulletCollidable, BulletDeflector, BulletSource};
+use super::hit::{BlastHitEvent, DamageEvent, HitEvent, blast_hit_entities, damage_by_entity, distinct_hit_events};
 use super::invulnerable::{Invulnerable, TestInvulnerable};
 use super::manager::GameCleanup;
 use super::movable::{Movable, MovableTorusConstraint};
-use super::collidable::{Collidable, Collider};
-use super::explosion::{ExplosionShapeId, SpawnExplosion, spawn_explosion};
-use super::svg::simple_svg_to_path;
+use super::collidable::Collidable;
+use super::explosion::{ExplosionCollidable, SpawnExplosion, spawn_explosion};
+use super::particle::{ParticleBurst, ParticleKind, ParticlePool};
+use super::shape::{ShapeCollider, VectorShape};
+use super::util::*;
 
 pub struct AsteroidPlugin;
 
@@ -31,6 +36,10 @@ impl Plugin for AsteroidPlugin {
                 .after(FrameStage::Collision)
                 .run_if(in_state(AppState::Game))
         );
+        app.add_systems(Update,
+            explosive_asteroid_pulse_system
+                .run_if(in_state(AppState::Game))
+        );
         app.add_systems(GameCleanup, destroy_asteroids_system);
     }
 }
@@ -40,35 +49,39 @@ impl Plugin for AsteroidPlugin {
 #[derive(Clone, Event)]
 pub struct AsteroidDestroyedEvent {
     pub size: AsteroidSize,
+    pub material: AsteroidMaterial,
     pub position: Vec2,
     pub velocity: Vec2,
+    /// Shot or rammed by the player, rather than caught in a mine blast or hit by a UFO (which are worth no points)
+    pub by_player: bool,
 }
 
 // Setup
 
 pub struct AsteroidAssets {
-    asteroid_shapes: HashMap<AsteroidShapeId, (f32, Path)>,
+    asteroid_shapes: HashMap<AsteroidShapeId, AsteroidShape>,
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+struct AsteroidShape {
+    diameter: f32,
+    collider: ShapeCollider,
+    path: Path,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 pub enum AsteroidShapeId { A, B, C, D, E }
 
 impl AsteroidShapeId {
     pub const VALUES: [Self; 5] = [ Self::A, Self::B, Self::C, Self::D, Self::E ];
 }
 
-pub fn create_asteroid_assets() -> AsteroidAssets {
-    let asteroid_shapes = vec![
-        // diameter, path
-        // See: https://yqnn.github.io/svg-path-editor/
-        (AsteroidShapeId::A, 10.0, "M -2 -5 L -5 -2 L -5 0 L -2 0 L -5 2 L -2 5 L 3 4 L 2 2 L 5 0 L 4 -2 L 1 -5 Z"),
-        (AsteroidShapeId::B, 10.0, "M -5 -3 L -5 2 L -3 5 L 2 5 L 5 3 L 4 1 L 6 -2 L 4 -5 L 1 -3 L -2 -6 Z"),
-        (AsteroidShapeId::C, 10.0, "M 4 -3 L 0 -5 L -3 -5 L -2 -2 L -5 -2 L -5 0 L -2 5 L 1 2 L 2 4 L 5 1 L 1 -1 L 5 -2 L 5 -3 Z"),
-        (AsteroidShapeId::D, 10.0, "M 0 -5 L -4 -4 L -5 -1 L -3 0 L -4 2 L -2 4 L 1 4 L 2 5 L 4 4 L 5 1 L 2 0 L 5 -1 L 5 -3 L 4 -5 L 1 -4 L 0 -5"),
-        (AsteroidShapeId::E, 10.0, "M 5 0 L 4 -3 L 2 -4 L 1 -3 L 0 -3 L -2 -4 L -4 -3 L -5 0 L -3 1 L -3 3 L -1 3 L 0 5 L 2 3 L 4 3 L 4 1 L 5 0"),
-    ];
-    let asteroid_shapes = asteroid_shapes.into_iter()
-        .map(|(id, dim, svg)| (id, (dim, simple_svg_to_path(svg))))
+pub fn create_asteroid_assets<'a>(shapes: impl Iterator<Item=(AsteroidShapeId, &'a VectorShape)>) -> AsteroidAssets {
+    let asteroid_shapes = shapes
+        .map(|(id, shape)| (id, AsteroidShape {
+            diameter: shape.size.max_element(),
+            collider: shape.collider,
+            path: Path(shape.path.0.clone()),
+        }))
         .collect();
 
     AsteroidAssets { asteroid_shapes }
@@ -91,7 +104,7 @@ static ASTEROID_SMALL_SCALE: f32 = 1.0;
 static ASTEROID_MEDIUM_SCALE: f32 = 2.0;
 static ASTEROID_LARGE_SCALE: f32 = 3.0;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub enum AsteroidSize {
     Small, Medium, Large
 }
@@ -100,9 +113,114 @@ impl AsteroidSize {
     pub const VALUES: [Self; 3] = [ Self::Large, Self::Medium, Self::Small ];
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+pub enum AsteroidMaterial {
+    #[default]
+    Rock,
+    /// Blasts everything nearby when destroyed
+    Explosive,
+    /// Takes several hits, and deflects some bullets
+    Metallic,
+    /// Shatters into more fragments than rock
+    Ice,
+}
+
+impl AsteroidMaterial {
+    pub const VALUES: [Self; 4] = [ Self::Rock, Self::Explosive, Self::Metallic, Self::Ice ];
+
+    fn hit_points(self) -> u32 {
+        match self {
+            AsteroidMaterial::Metallic => 3,
+            _ => 1,
+        }
+    }
+
+    fn bullet_deflect_chance(self) -> f32 {
+        match self {
+            AsteroidMaterial::Metallic => 0.3,
+            _ => 0.0,
+        }
+    }
+
+    fn mass_scale(self) -> f32 {
+        match self {
+            AsteroidMaterial::Metallic => 2.0,
+            AsteroidMaterial::Ice => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    fn line_width_scale(self) -> f32 {
+        match self {
+            AsteroidMaterial::Metallic => 2.0,
+            AsteroidMaterial::Ice => 0.6,
+            _ => 1.0,
+        }
+    }
+
+    fn alpha(self) -> f32 {
+        match self {
+            AsteroidMaterial::Ice => 0.6,
+            _ => 1.0,
+        }
+    }
+
+    /// The number of smaller asteroids this breaks into
+    pub fn fragment_count(self) -> usize {
+        match self {
+            AsteroidMaterial::Ice => 3,
+            _ => 2,
+        }
+    }
+
+    /// The material of the smaller asteroids this breaks into
+    pub fn fragment_material(self) -> AsteroidMaterial {
+        match self {
+            // Avoid endless chain reactions
+            AsteroidMaterial::Explosive => AsteroidMaterial::Rock,
+            material => material,
+        }
+    }
+}
+
+/// The chance of an asteroid being made of each material. Anything left over is rock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsteroidMaterialChances {
+    pub explosive: f32,
+    pub metallic: f32,
+    pub ice: f32,
+}
+
+impl AsteroidMaterialChances {
+    pub fn pick(&self, rng: &mut impl RngUtil) -> AsteroidMaterial {
+        let roll = rng.random_f32();
+        let chances = [
+            (AsteroidMaterial::Explosive, self.explosive),
+            (AsteroidMaterial::Metallic, self.metallic),
+            (AsteroidMaterial::Ice, self.ice),
+        ];
+        let mut total = 0.0;
+        for (material, chance) in chances {
+            total += chance;
+            if roll < total {
+                return material;
+            }
+        }
+        AsteroidMaterial::Rock
+    }
+}
+
 #[derive(Component)]
 pub struct Asteroid {
     size: AsteroidSize,
+    material: AsteroidMaterial,
+    hit_points: u32,
+}
+
+impl Asteroid {
+    pub fn new(size: AsteroidSize, material: AsteroidMaterial) -> Self {
+        Self { size, material, hit_points: material.hit_points() }
+    }
 }
 
 /// Marker component which indicates that an entity should be considered for asteroid collisions
@@ -121,13 +239,44 @@ fn asteroid_scale(size: AsteroidSize) -> f32 {
     }
 }
 
+/// Mass scales with area
+fn asteroid_mass(size: AsteroidSize) -> f32 {
+    asteroid_scale(size).powi(2)
+}
+
+fn asteroid_trauma(size: AsteroidSize) -> f32 {
+    match size {
+        AsteroidSize::Small => 0.1,
+        AsteroidSize::Medium => 0.2,
+        AsteroidSize::Large => 0.35,
+    }
+}
+
+const EXPLOSIVE_ASTEROID_BLAST_RADIUS_SCALE: f32 = 12.0;
+const EXPLOSIVE_ASTEROID_TRAUMA: f32 = 0.4;
+
+fn explosive_asteroid_blast_radius(size: AsteroidSize) -> f32 {
+    asteroid_scale(size) * EXPLOSIVE_ASTEROID_BLAST_RADIUS_SCALE
+}
+
+fn asteroid_dust_count(size: AsteroidSize) -> u32 {
+    match size {
+        AsteroidSize::Small => 8,
+        AsteroidSize::Medium => 16,
+        AsteroidSize::Large => 24,
+    }
+}
+
 #[derive(Clone)]
 pub struct AsteroidSpawn {
     pub size: AsteroidSize,
+    pub material: AsteroidMaterial,
     pub shape: AsteroidShapeId,
     pub position: Vec2,
     pub velocity: Vec2,
     pub rotation: f32,
+    /// The direction the shape initially faces (rads)
+    pub heading_angle: f32,
     pub invulnerable: Option<Timer>,
 }
 
@@ -135,57 +284,67 @@ pub fn spawn_asteroid(
     commands: &mut Commands,
     assets: &AsteroidAssets,
     spawn: AsteroidSpawn
-) {
+) -> Entity {
     let position = spawn.position;
     let velocity = spawn.velocity;
     let rotation = spawn.rotation;
+    let heading_angle = spawn.heading_angle;
 
     // Mesh
-    let (diameter, shape) = &assets.asteroid_shapes[&spawn.shape];
+    let shape = &assets.asteroid_shapes[&spawn.shape];
     let scale = asteroid_scale(spawn.size);
 
-    let color = Color::rgba(0.6, 0.6, 0.6, 1.);
-    let stroke = Stroke::new(color, LINE_WIDTH / scale);
+    // NOTE: Colour is applied by the theme
+    let material = spawn.material;
+    let stroke = Stroke::new(
+        Color::WHITE.with_a(material.alpha()),
+        LINE_WIDTH * material.line_width_scale() / scale
+    );
     let transform = Transform::default()
         .with_translation(Vec3::new(position.x, position.y, ASTEROID_Z))
+        .with_rotation(Quat::from_rotation_z(heading_angle))
         .with_scale(Vec3::splat(scale));
 
     // Collision detection
-    let radius = scale * diameter / 2.;
-    let collider = Collider::circle(position.into(), radius);
+    let radius = scale * shape.diameter / 2.;
+    let collider = shape.collider.to_collider(position, scale);
 
     let entity = commands
         .spawn((
-            Asteroid {
-                size: spawn.size,
-            },
-            Movable {
-                position,
-                velocity,
-                acceleration: None,
-                heading_angle: 0.,
-                rotational_velocity: rotation * std::f32::consts::TAU,
-                rotational_acceleration: None,
-            },
+            Asteroid::new(spawn.size, material),
+            Movable::new(position, velocity)
+                .with_heading_angle(heading_angle)
+                .with_rotational_velocity(rotation * std::f32::consts::TAU)
+                .with_mass(asteroid_mass(spawn.size) * material.mass_scale()),
             MovableTorusConstraint { radius },
             // Render
             ShapeBundle {
-                path: Path(shape.0.clone()),
+                path: Path(shape.path.0.clone()),
                 transform,
                 ..default()
             },
             stroke,
+            Themed(ThemeColor::Asteroid),
             // Collision detection
             Collidable { collider },
-            BulletCollidable { source: BulletSource::PlayerRocket }
+            BulletCollidable { source: BulletSource::PlayerRocket },
+            ExplosionCollidable,
         ))
         .id();
 
+    if material == AsteroidMaterial::Metallic {
+        commands
+            .entity(entity)
+            .insert(BulletDeflector { chance: material.bullet_deflect_chance() });
+    }
+
     if let Some(timer) = spawn.invulnerable {
         commands
             .entity(entity)
             .insert(Invulnerable::new(timer));
     }
+
+    entity
 }
 
 // Collision detection
@@ -217,31 +376,95 @@ const ASTEROID_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 0.8;
 fn asteroid_hit_system(
     mut commands: Commands,
     mut hit_events: EventReader<HitEvent>,
+    // NOTE: Grouped, as they only qualify the hits
+    (mut damage_events, mut blast_hit_events, ufos): (EventReader<DamageEvent>, EventReader<BlastHitEvent>, Query<&Collidable, With<AlienUfo>>),
     mut asteroid_destroyed: EventWriter<AsteroidDestroyedEvent>,
-    assets: Res<GameAssets>,
-    query: Query<(&Asteroid, &Movable)>
+    mut particles: ResMut<ParticlePool>,
+    mut camera_effects: ResMut<CameraEffects>,
+    mut query: Query<(&mut Asteroid, &Movable, &Path, &Collidable)>
 ) {
-    for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
-        if let Ok((asteroid, movable)) = query.get(entity) {
+    let events = distinct_hit_events(&mut hit_events).map(|e| e.0).collect::<Vec<_>>();
+    let damage = damage_by_entity(&mut damage_events);
+    let blasted = blast_hit_entities(&mut blast_hit_events);
+    for &entity in events.iter() {
+        if let Ok((mut asteroid, movable, path, collidable)) = query.get_mut(entity) {
             let mut rng = thread_rng();
+            // Tough asteroids shrug off some hits
+            asteroid.hit_points = asteroid.hit_points.saturating_sub(damage.get(&entity).copied().unwrap_or(1));
+            if asteroid.hit_points > 0 {
+                particles.burst(&mut rng, ParticleBurst {
+                    position: movable.position,
+                    velocity: movable.velocity,
+                    count: 6,
+                    speed: (20.0, 50.0),
+                    lifetime_secs: (0.1, 0.3),
+                    kind: ParticleKind::Line,
+                    color: ThemeColor::Asteroid,
+                });
+                continue;
+            }
             // Despawn the entity
             commands.entity(entity).despawn();
             // Start the explosion
-            spawn_explosion(&mut commands, &mut rng, &assets.explosion, SpawnExplosion {
-                shape_id: ExplosionShapeId::AsteroidDebris,
+            spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+                shape: path,
                 shape_scale: asteroid_scale(asteroid.size),
                 position: movable.position,
                 velocity: movable.velocity,
                 heading_angle: movable.heading_angle,
                 rotational_velocity: movable.rotational_velocity,
                 despawn_after_secs: ASTEROID_EXPLOSION_DESPAWN_AFTER_SECS,
+                shockwave_radius: (asteroid.material == AsteroidMaterial::Explosive)
+                    .then(|| explosive_asteroid_blast_radius(asteroid.size)),
+            });
+            // Dust
+            particles.burst(&mut rng, ParticleBurst {
+                position: movable.position,
+                velocity: movable.velocity,
+                count: asteroid_dust_count(asteroid.size),
+                speed: (5.0, 30.0),
+                lifetime_secs: (0.4, 1.2),
+                kind: ParticleKind::Dot,
+                color: ThemeColor::Asteroid,
             });
+            camera_effects.add_trauma(asteroid_trauma(asteroid.size));
+            // Explosive asteroids take their neighbours with them
+            if asteroid.material == AsteroidMaterial::Explosive {
+                let radius = explosive_asteroid_blast_radius(asteroid.size);
+                particles.burst(&mut rng, ParticleBurst {
+                    position: movable.position,
+                    velocity: movable.velocity,
+                    count: 30,
+                    speed: (radius, radius * 3.),
+                    lifetime_secs: (0.2, 0.4),
+                    kind: ParticleKind::Line,
+                    color: ThemeColor::Asteroid,
+                });
+                camera_effects.add_trauma(EXPLOSIVE_ASTEROID_TRAUMA);
+            }
+            // Work out who was responsible, from whatever else was hit by touching the asteroid
+            let hit_by_ufo = events.iter()
+                .filter_map(|&e| ufos.get(e).ok())
+                .any(|ufo| ufo.test_collision_with(collidable));
             // Send events
             asteroid_destroyed.send(AsteroidDestroyedEvent {
                 size: asteroid.size,
+                material: asteroid.material,
                 position: movable.position,
-                velocity: movable.velocity
+                velocity: movable.velocity,
+                by_player: !blasted.contains(&entity) && !hit_by_ufo,
             });
         }
     }
 }
+
+// Explosive asteroids glow
+
+fn explosive_asteroid_pulse_system(time: Res<Time>, mut query: Query<(&Asteroid, &mut Stroke)>) {
+    let t_secs = time.elapsed_seconds();
+    for (asteroid, mut stroke) in query.iter_mut() {
+        if asteroid.material == AsteroidMaterial::Explosive {
+            stroke.color.set_a(0.6 + 0.4 * (t_secs * std::f32::consts::TAU).sin().abs());
+        }
+    }
+}
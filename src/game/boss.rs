@@ -0,0 +1,489 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
+use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::assets::GameAssets;
+use super::bullet::{BulletController, BulletCollidable, BulletFireResult, BulletSource, BulletShape, BulletSpawn, spawn_bullet};
+use super::collidable::Collidable;
+use super::explosion::{SpawnExplosion, spawn_explosion};
+use super::hit::{DamageEvent, HitEvent, damage_by_entity, distinct_hit_events};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::{GameCleanup, WorldBoundaries};
+use super::movable::{Movable, MovableTorusConstraint};
+use super::particle::{ParticleBurst, ParticleKind, ParticlePool};
+use super::player::PlayerRocket;
+use super::shape::{ShapeCollider, VectorShape};
+use super::util::*;
+
+// Mothership boss
+//
+// The mothership is a root entity (the hull) with several child segments. Each segment has its own
+// collider and health. Turrets fire aimed shots, shield generators protect the core, and once exposed
+// the core fires rings of bullets. Destroying the core destroys the mothership in a series of explosions.
+
+const MOTHERSHIP_Z: f32 = 12.0;
+const MOTHERSHIP_SPEED: f32 = 10.0;
+const MOTHERSHIP_BULLET_SPEED: f32 = 90.0;
+const MOTHERSHIP_BULLET_MAX_AGE_SECS: f32 = 2.5;
+const MOTHERSHIP_CORE_RING_BULLETS: u32 = 10;
+const MOTHERSHIP_DEATH_STAGE_SECS: f32 = 0.3;
+const MOTHERSHIP_DEATH_STAGE_TRAUMA: f32 = 0.3;
+const MOTHERSHIP_DESTROYED_TRAUMA: f32 = 1.0;
+const MOTHERSHIP_DESTROYED_ZOOM_PUNCH: f32 = 0.15;
+const MOTHERSHIP_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 2.0;
+const SEGMENT_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 1.0;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BossSegmentDestroyedEvent>();
+        app.add_event::<BossDestroyedEvent>();
+        app.add_systems(
+            Update,
+            (
+                boss_segment_collider_system
+                    .after(FrameStage::Movement)
+                    .before(FrameStage::Collision),
+
+                boss_collision_system
+                    .in_set(FrameStage::Collision)
+                    .after(FrameStage::Movement),
+
+                boss_segment_hit_system
+                    .in_set(FrameStage::CollisionEffect)
+                    .after(FrameStage::Collision),
+
+                boss_firing_system
+                    .after(FrameStage::Movement),
+
+                boss_death_system
+                    .after(boss_segment_hit_system),
+
+                boss_health_system
+                    .after(boss_segment_hit_system),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, boss_teardown_system);
+    }
+}
+
+// Events
+
+#[derive(Event)]
+pub struct BossSegmentDestroyedEvent {
+    pub kind: BossSegmentKind,
+}
+
+/// Sent once the mothership has finished exploding
+#[derive(Event)]
+pub struct BossDestroyedEvent;
+
+// Setup
+
+struct BossShape {
+    collider: ShapeCollider,
+    path: Path,
+}
+
+impl BossShape {
+    fn new(shape: &VectorShape) -> Self {
+        Self { collider: shape.collider, path: Path(shape.path.0.clone()) }
+    }
+}
+
+pub struct BossAssets {
+    hull: BossShape,
+    hull_size: Vec2,
+    turret: BossShape,
+    shield: BossShape,
+    core: BossShape,
+}
+
+pub fn create_boss_assets(hull: &VectorShape, turret: &VectorShape, shield: &VectorShape, core: &VectorShape) -> BossAssets {
+    BossAssets {
+        hull: BossShape::new(hull),
+        hull_size: hull.size,
+        turret: BossShape::new(turret),
+        shield: BossShape::new(shield),
+        core: BossShape::new(core),
+    }
+}
+
+// Teardown
+
+fn boss_teardown_system(mut commands: Commands, query: Query<Entity, With<Mothership>>) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+}
+
+// Entities
+
+#[derive(Component)]
+pub struct Mothership {
+    max_health: u32,
+    /// Remaining health of all segments (0.0 - 1.0)
+    pub health_fraction: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossSegmentKind {
+    Turret,
+    ShieldGenerator,
+    Core,
+}
+
+impl BossSegmentKind {
+    fn health(self) -> u32 {
+        match self {
+            BossSegmentKind::Turret => 5,
+            BossSegmentKind::ShieldGenerator => 8,
+            BossSegmentKind::Core => 20,
+        }
+    }
+
+    /// shots per second
+    fn fire_rate(self) -> Option<f32> {
+        match self {
+            BossSegmentKind::Turret => Some(0.8),
+            BossSegmentKind::ShieldGenerator => None,
+            BossSegmentKind::Core => Some(0.3),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BossSegment {
+    kind: BossSegmentKind,
+    health: u32,
+    /// Position relative to the centre of the mothership
+    offset: Vec2,
+}
+
+/// Added to the mothership once its core is destroyed
+#[derive(Component)]
+struct MothershipDying {
+    timer: Timer,
+}
+
+// Spawning
+
+/// (kind, offset) of each segment
+const MOTHERSHIP_LAYOUT: [(BossSegmentKind, Vec2); 5] = [
+    (BossSegmentKind::Turret, Vec2::new(-20.0, -5.0)),
+    (BossSegmentKind::Turret, Vec2::new(20.0, -5.0)),
+    (BossSegmentKind::ShieldGenerator, Vec2::new(-9.0, 3.0)),
+    (BossSegmentKind::ShieldGenerator, Vec2::new(9.0, 3.0)),
+    (BossSegmentKind::Core, Vec2::new(0.0, -1.0)),
+];
+
+#[derive(Clone)]
+pub struct MothershipSpawn {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+const LINE_WIDTH: f32 = 0.3;
+
+pub fn spawn_mothership(
+    commands: &mut Commands,
+    assets: &BossAssets,
+    spawn: MothershipSpawn
+) {
+    let position = spawn.position;
+    let radius = assets.hull_size.x / 2.;
+    let max_health = MOTHERSHIP_LAYOUT.iter().map(|(kind, _)| kind.health()).sum();
+
+    commands
+        .spawn((
+            Mothership { max_health, health_fraction: 1.0 },
+            Movable::new(position, spawn.velocity),
+            MovableTorusConstraint { radius },
+            // Collision detection (the hull can't be damaged, but will destroy the rocket)
+            Collidable { collider: assets.hull.collider.to_collider(position, 1.0) },
+            // Rendering
+            ShapeBundle {
+                path: Path(assets.hull.path.0.clone()),
+                transform: Transform::from_xyz(position.x, position.y, MOTHERSHIP_Z),
+                ..default()
+            },
+            // NOTE: Colour is applied by the theme
+            Stroke::new(Color::WHITE, LINE_WIDTH),
+            Themed(ThemeColor::Alien),
+        ))
+        .with_children(|child_commands| {
+            for (kind, offset) in MOTHERSHIP_LAYOUT {
+                let shape = match kind {
+                    BossSegmentKind::Turret => &assets.turret,
+                    BossSegmentKind::ShieldGenerator => &assets.shield,
+                    BossSegmentKind::Core => &assets.core,
+                };
+                let mut segment = child_commands.spawn((
+                    BossSegment { kind, health: kind.health(), offset },
+                    // Collision detection
+                    BulletCollidable { source: BulletSource::PlayerRocket },
+                    Collidable { collider: shape.collider.to_collider(position + offset, 1.0) },
+                    // Rendering
+                    ShapeBundle {
+                        path: Path(shape.path.0.clone()),
+                        transform: Transform::from_xyz(offset.x, offset.y, 1.0),
+                        ..default()
+                    },
+                    Stroke::new(Color::WHITE, LINE_WIDTH),
+                    Themed(ThemeColor::Alien),
+                ));
+                if let Some(fire_rate) = kind.fire_rate() {
+                    segment.insert(BulletController::new(fire_rate));
+                }
+            }
+        });
+}
+
+pub fn random_mothership_spawn(rng: &mut impl RngUtil, world_boundaries: &WorldBoundaries) -> MothershipSpawn {
+    // Enter from one side, in the top half of the screen
+    let from_left = rng.random_bool();
+    let x = if from_left { world_boundaries.left - 30.0 } else { world_boundaries.right + 30.0 };
+    let y = world_boundaries.top * 0.5;
+    let x_speed = if from_left { MOTHERSHIP_SPEED } else { -MOTHERSHIP_SPEED };
+    MothershipSpawn { position: Vec2::new(x, y), velocity: Vec2::new(x_speed, 0.) }
+}
+
+// Segments follow the mothership
+
+fn boss_segment_collider_system(
+    motherships: Query<&Movable, With<Mothership>>,
+    mut segments: Query<(&BossSegment, &Parent, &mut Collidable)>
+) {
+    for (segment, parent, mut collidable) in segments.iter_mut() {
+        if let Ok(movable) = motherships.get(parent.get()) {
+            collidable.collider.set_position(movable.position + segment.offset);
+        }
+    }
+}
+
+// Collision detection
+
+type BossPart = Or<(With<Mothership>, With<BossSegment>)>;
+
+fn boss_collision_system(
+    boss_parts: Query<&Collidable, BossPart>,
+    rockets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<PlayerRocket>>,
+    mut hit_events: EventWriter<HitEvent>
+) {
+    for (rocket, rocket_collidable, invulnerable) in rockets.iter() {
+        if invulnerable.is_invulnerable() {
+            continue;
+        }
+        if boss_parts.iter().any(|part| part.test_collision_with(rocket_collidable)) {
+            hit_events.send(HitEvent(rocket));
+        }
+    }
+}
+
+// Firing
+
+fn boss_firing_system(
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    mut commands: Commands,
+    motherships: Query<&Movable, (With<Mothership>, Without<MothershipDying>)>,
+    mut segments: Query<(Entity, &BossSegment, &Parent, &mut BulletController)>,
+    rockets: Query<&Movable, With<PlayerRocket>>,
+) {
+    let target = rockets.get_single().ok();
+    let shields_up = segments.iter().any(|(_, s, _, _)| s.kind == BossSegmentKind::ShieldGenerator);
+
+    for (entity, segment, parent, mut controller) in segments.iter_mut() {
+        let Ok(mothership) = motherships.get(parent.get()) else { continue };
+        // The core only fires once exposed
+        let firing = match segment.kind {
+            BossSegmentKind::Turret => target.is_some(),
+            BossSegmentKind::Core => !shields_up,
+            BossSegmentKind::ShieldGenerator => false,
+        };
+        controller.try_set_firing_state(firing);
+        if controller.update(&time) != BulletFireResult::FireBullet {
+            continue;
+        }
+
+        let position = mothership.position + segment.offset;
+        let directions = match (segment.kind, target) {
+            // Aimed shot
+            (BossSegmentKind::Turret, Some(target)) => vec![(target.position - position).normalize_or_zero()],
+            // Ring of bullets
+            (BossSegmentKind::Core, _) => (0..MOTHERSHIP_CORE_RING_BULLETS)
+                .map(|i| Vec2::from_angle(i as f32 / MOTHERSHIP_CORE_RING_BULLETS as f32 * std::f32::consts::TAU))
+                .collect(),
+            _ => vec![],
+        };
+        for direction in directions {
+            spawn_bullet(&mut commands, &assets.bullet, BulletSpawn {
+                source: BulletSource::AlienUfo,
+                owner: entity,
+                shape: BulletShape::Standard,
+                scale: 1.0,
+                damage: 1,
+                pierce: 0,
+                position,
+                velocity: mothership.velocity + direction * MOTHERSHIP_BULLET_SPEED,
+                heading_angle: Vec2::X.angle_between(direction),
+                despawn_after_secs: MOTHERSHIP_BULLET_MAX_AGE_SECS,
+            });
+        }
+    }
+}
+
+// Hit handling system
+
+fn boss_segment_hit_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<HitEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut segment_destroyed: EventWriter<BossSegmentDestroyedEvent>,
+    mut particles: ResMut<ParticlePool>,
+    motherships: Query<(&Movable, Option<&MothershipDying>), With<Mothership>>,
+    mut segments: Query<(&mut BossSegment, &Parent, &Path)>,
+) {
+    let mut rng = thread_rng();
+    let damage = damage_by_entity(&mut damage_events);
+    for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
+        let shields_up = segments.iter().any(|(s, _, _)| s.kind == BossSegmentKind::ShieldGenerator && s.health > 0);
+        let Ok((mut segment, parent, path)) = segments.get_mut(entity) else { continue };
+        let Ok((mothership, dying)) = motherships.get(parent.get()) else { continue };
+        if dying.is_some() || segment.health == 0 {
+            continue;
+        }
+        let position = mothership.position + segment.offset;
+
+        // The core is protected while any shield generator remains
+        if segment.kind == BossSegmentKind::Core && shields_up {
+            particles.burst(&mut rng, ParticleBurst {
+                position,
+                velocity: mothership.velocity,
+                count: 4,
+                speed: (20.0, 40.0),
+                lifetime_secs: (0.1, 0.2),
+                kind: ParticleKind::Line,
+                color: ThemeColor::Alien,
+            });
+            continue;
+        }
+
+        segment.health = segment.health.saturating_sub(damage.get(&entity).copied().unwrap_or(1));
+        if segment.health > 0 {
+            continue;
+        }
+
+        // Segment destroyed
+        commands.entity(entity).despawn();
+        spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+            shape: path,
+            shape_scale: 1.0,
+            position,
+            velocity: mothership.velocity,
+            heading_angle: 0.,
+            rotational_velocity: 0.,
+            despawn_after_secs: SEGMENT_EXPLOSION_DESPAWN_AFTER_SECS,
+            shockwave_radius: None,
+        });
+        segment_destroyed.send(BossSegmentDestroyedEvent { kind: segment.kind });
+        if segment.kind == BossSegmentKind::Core {
+            commands.entity(parent.get()).insert(MothershipDying {
+                timer: Timer::from_seconds(MOTHERSHIP_DEATH_STAGE_SECS, TimerMode::Repeating),
+            });
+        }
+    }
+}
+
+// Multi-stage destruction
+
+fn boss_death_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut boss_destroyed: EventWriter<BossDestroyedEvent>,
+    mut particles: ResMut<ParticlePool>,
+    mut camera_effects: ResMut<CameraEffects>,
+    mut motherships: Query<(Entity, &mut MothershipDying, &Movable, &Path, &Children)>,
+    segments: Query<(&BossSegment, &Path)>,
+) {
+    let mut rng = thread_rng();
+    for (entity, mut dying, movable, path, children) in motherships.iter_mut() {
+        if !dying.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        // Blow up the next remaining segment...
+        let next_segment = children.iter().find_map(|&child| segments.get(child).ok().map(|s| (child, s)));
+        if let Some((child, (segment, segment_path))) = next_segment {
+            commands.entity(child).despawn();
+            spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+                shape: segment_path,
+                shape_scale: 1.0,
+                position: movable.position + segment.offset,
+                velocity: movable.velocity,
+                heading_angle: 0.,
+                rotational_velocity: 0.,
+                despawn_after_secs: SEGMENT_EXPLOSION_DESPAWN_AFTER_SECS,
+                shockwave_radius: None,
+            });
+            particles.burst(&mut rng, ParticleBurst {
+                position: movable.position + segment.offset,
+                velocity: movable.velocity,
+                count: 20,
+                speed: (10.0, 50.0),
+                lifetime_secs: (0.3, 0.8),
+                kind: ParticleKind::Line,
+                color: ThemeColor::Alien,
+            });
+            camera_effects.add_trauma(MOTHERSHIP_DEATH_STAGE_TRAUMA);
+            continue;
+        }
+
+        // ...then the hull itself
+        commands.entity(entity).despawn_recursive();
+        spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+            shape: path,
+            shape_scale: 1.0,
+            position: movable.position,
+            velocity: movable.velocity,
+            heading_angle: 0.,
+            rotational_velocity: std::f32::consts::FRAC_PI_4,
+            despawn_after_secs: MOTHERSHIP_EXPLOSION_DESPAWN_AFTER_SECS,
+            shockwave_radius: None,
+        });
+        particles.burst(&mut rng, ParticleBurst {
+            position: movable.position,
+            velocity: movable.velocity,
+            count: 60,
+            speed: (20.0, 80.0),
+            lifetime_secs: (0.5, 1.2),
+            kind: ParticleKind::Line,
+            color: ThemeColor::Alien,
+        });
+        camera_effects.add_trauma(MOTHERSHIP_DESTROYED_TRAUMA);
+        camera_effects.punch_zoom(MOTHERSHIP_DESTROYED_ZOOM_PUNCH);
+        boss_destroyed.send(BossDestroyedEvent);
+    }
+}
+
+// Health
+
+fn boss_health_system(
+    mut motherships: Query<(&mut Mothership, &Children)>,
+    segments: Query<&BossSegment>,
+) {
+    for (mut mothership, children) in motherships.iter_mut() {
+        let health: u32 = children.iter()
+            .filter_map(|&child| segments.get(child).ok())
+            .map(|s| s.health)
+            .sum();
+        mothership.health_fraction = health as f32 / mothership.max_health as f32;
+    }
+}
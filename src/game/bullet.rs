@@ -1,13 +1,18 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
 use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
 use super::FrameStage;
-use super::hit::{HitEvent, distinct_hit_events};
+use super::hit::{DamageEvent, HitEvent, distinct_hit_events};
 use super::manager::GameCleanup;
 use super::movable::{Movable, MovableTorusConstraint};
-use super::collidable::{Collidable, Collider};
+use super::collidable::Collidable;
 use super::invulnerable::{Invulnerable, TestInvulnerable};
-use super::svg::simple_svg_to_path;
+use super::particle::{ParticleBurst, ParticleKind, ParticlePool};
+use super::shape::{ShapeCollider, VectorShape};
+use super::util::*;
 
 // Bullets
 
@@ -17,6 +22,11 @@ pub struct BulletPlugin;
 
 impl Plugin for BulletPlugin {
     fn build(&self, app: &mut App) {
+        app.add_systems(Update,
+            bullet_owner_count_system
+                .in_set(FrameStage::Start)
+                .run_if(in_state(AppState::Game))
+        );
         app.add_systems(Update,
             bullet_collision_system
                 .in_set(FrameStage::Collision)
@@ -36,18 +46,42 @@ impl Plugin for BulletPlugin {
 // Setup
 
 pub struct BulletAssets {
+    standard: BulletShapeAssets,
+    pulse: BulletShapeAssets,
+    slug: BulletShapeAssets,
+}
+
+struct BulletShapeAssets {
     bullet_dimension: f32, // h of the bullet shape
+    bullet_collider: ShapeCollider,
     bullet_shape: Path,
 }
 
-pub fn create_bullet_assets() -> BulletAssets {
-    // See: https://yqnn.github.io/svg-path-editor/
-    let bullet_dimension = 2.0;
-    let bullet_path = "M 1 0 L -1 0";
+impl BulletShapeAssets {
+    fn new(bullet: &VectorShape) -> Self {
+        Self {
+            bullet_dimension: bullet.size.y,
+            bullet_collider: bullet.collider,
+            bullet_shape: Path(bullet.path.0.clone()),
+        }
+    }
+}
+
+impl BulletAssets {
+    fn shape(&self, shape: BulletShape) -> &BulletShapeAssets {
+        match shape {
+            BulletShape::Standard => &self.standard,
+            BulletShape::Pulse => &self.pulse,
+            BulletShape::Slug => &self.slug,
+        }
+    }
+}
 
+pub fn create_bullet_assets(standard: &VectorShape, pulse: &VectorShape, slug: &VectorShape) -> BulletAssets {
     BulletAssets {
-        bullet_dimension,
-        bullet_shape: simple_svg_to_path(bullet_path),
+        standard: BulletShapeAssets::new(standard),
+        pulse: BulletShapeAssets::new(pulse),
+        slug: BulletShapeAssets::new(slug),
     }
 }
 
@@ -69,9 +103,25 @@ pub enum BulletSource {
     AlienUfo
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BulletShape {
+    Standard,
+    Pulse,
+    Slug,
+}
+
 #[derive(Component)]
 pub struct Bullet {
     source: BulletSource,
+    /// The entity which fired this bullet
+    owner: Entity,
+    damage: u32,
+    /// The number of things this bullet can pass through before it is destroyed
+    pierce: u32,
+    /// Entities already hit (piercing bullets only hit each entity once)
+    hit: Vec<Entity>,
+    /// Hit something with no pierce left, so is destroyed along with it
+    spent: bool,
     despawn_timer: Timer,
 }
 
@@ -81,10 +131,22 @@ pub struct BulletCollidable {
     pub source: BulletSource
 }
 
+/// Bullets which hit this entity have a chance of bouncing off rather than hitting it
+#[derive(Component)]
+pub struct BulletDeflector {
+    /// Chance of deflecting each bullet (0.0 - 1.0)
+    pub chance: f32,
+}
+
 // Spawning
 
 pub struct BulletSpawn {
     pub source: BulletSource,
+    pub owner: Entity,
+    pub shape: BulletShape,
+    pub scale: f32,
+    pub damage: u32,
+    pub pierce: u32,
     pub position: Vec2,
     pub velocity: Vec2,
     pub heading_angle: f32,
@@ -98,32 +160,33 @@ pub fn spawn_bullet(
     assets: &BulletAssets,
     spawn: BulletSpawn
 ) {
-    let bullet_color = Color::rgba(0.8, 0.8, 0.8, 1.0);
-    let bullet_stroke = Stroke::new(bullet_color, LINE_WIDTH);
+    // NOTE: Colour is applied by the theme
+    let bullet_stroke = Stroke::new(Color::WHITE, LINE_WIDTH / spawn.scale);
 
     // Transform
     let transform = Transform::default()
         .with_translation(Vec3::new(spawn.position.x, spawn.position.y, BULLET_Z))
-        .with_rotation(Quat::from_rotation_z(spawn.heading_angle));
+        .with_rotation(Quat::from_rotation_z(spawn.heading_angle))
+        .with_scale(Vec3::splat(spawn.scale));
 
     // collision detection
-    let radius = assets.bullet_dimension / 2.;
-    let collider = Collider::circle(spawn.position.into(), radius);
+    let assets = assets.shape(spawn.shape);
+    let radius = spawn.scale * assets.bullet_dimension / 2.;
+    let collider = assets.bullet_collider.to_collider(spawn.position, spawn.scale);
 
     commands
         .spawn((
             Bullet {
                 source: spawn.source,
+                owner: spawn.owner,
+                damage: spawn.damage,
+                pierce: spawn.pierce,
+                hit: Vec::new(),
+                spent: false,
                 despawn_timer: Timer::from_seconds(spawn.despawn_after_secs, TimerMode::Once),
             },
-            Movable {
-                position: spawn.position,
-                velocity: spawn.velocity,
-                acceleration: None,
-                heading_angle: spawn.heading_angle,
-                rotational_velocity: 0.,
-                rotational_acceleration: None,
-            },
+            Movable::new(spawn.position, spawn.velocity)
+                .with_heading_angle(spawn.heading_angle),
             MovableTorusConstraint { radius },
             // Rendering
             ShapeBundle {
@@ -132,6 +195,7 @@ pub fn spawn_bullet(
                 ..default()
             },
             bullet_stroke,
+            Themed(ThemeColor::Bullet),
             // Collision detection
             Collidable { collider },
         ));
@@ -141,17 +205,31 @@ fn bullet_despawn_system(
     time: Res<Time>,
     mut commands: Commands,
     mut hit_events: EventReader<HitEvent>,
-    mut query: Query<(Entity, &mut Bullet)>
+    mut particles: ResMut<ParticlePool>,
+    mut query: Query<(Entity, &mut Bullet, &Movable)>
 ) {
-    // Despawn bullets which have hit something
+    // Despawn bullets which have hit something, with a shower of sparks
+    let mut rng = thread_rng();
     for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
-        if let Ok(_) = query.get(entity) {
-            commands.entity(entity).despawn();
+        if let Ok((_, bullet, movable)) = query.get(entity) {
+            // Piercing bullets carry on through (see `bullet_collision_system`)
+            if bullet.spent {
+                commands.entity(entity).despawn();
+            }
+            particles.burst(&mut rng, ParticleBurst {
+                position: movable.position,
+                velocity: movable.velocity * 0.1,
+                count: 6,
+                speed: (20.0, 60.0),
+                lifetime_secs: (0.1, 0.3),
+                kind: ParticleKind::Line,
+                color: ThemeColor::Bullet,
+            });
         }
     }
 
     // Despawn bullets which have expired
-    for (entity, mut bullet) in query.iter_mut() {
+    for (entity, mut bullet, _) in query.iter_mut() {
         bullet.despawn_timer.tick(time.delta());
         if bullet.despawn_timer.finished() {
             commands.entity(entity).despawn();
@@ -161,10 +239,12 @@ fn bullet_despawn_system(
 
 // Fire control
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum BulletFireResult {
     None,
     FireBullet,
+    /// Fire a charged shot, with the given charge (0.0 - 1.0)
+    FireChargedBullet(f32),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -172,12 +252,25 @@ enum BulletControllerState {
     None,
     Firing,
     Cooldown,
+    /// Fire is being held, building up charge
+    Charging,
+    /// Fire was released after charging, and the shot is ready to go
+    Released,
 }
 
+/// Holding fire for less than this is treated as a normal shot
+const BULLET_CHARGE_TAP_SECS: f32 = 0.2;
+
 #[derive(Component)]
 pub struct BulletController {
     timer: Timer,
     state: BulletControllerState,
+    /// Time to reach full charge (only set in charge mode)
+    charge_timer: Option<Timer>,
+    /// Refuse to fire while this many of our bullets are still alive
+    pub max_live_bullets: Option<u32>,
+    /// Bullets fired by this entity which are still alive (see `bullet_owner_count_system`)
+    pub live_bullets: u32,
     pub fire_count: i32,
     pub spawn_translation: Option<Vec2>,
 }
@@ -188,6 +281,9 @@ impl BulletController {
             state: BulletControllerState::None,
             fire_count: 0,
             timer: Timer::from_seconds(1.0 / fire_rate, TimerMode::Repeating),
+            charge_timer: None,
+            max_live_bullets: None,
+            live_bullets: 0,
             spawn_translation: None,
         }
     }
@@ -197,7 +293,47 @@ impl BulletController {
         self
     }
 
+    /// Holding fire charges up a single shot, which fires on release
+    pub fn with_charge(mut self, full_charge_secs: f32) -> Self {
+        self.charge_timer = Some(Timer::from_seconds(full_charge_secs, TimerMode::Once));
+        self
+    }
+
+    fn at_bullet_limit(&self) -> bool {
+        self.max_live_bullets.is_some_and(|max| self.live_bullets >= max)
+    }
+
+    /// How many more bullets can be fired before reaching the limit (if there is one)
+    pub fn bullet_allowance(&self) -> Option<u32> {
+        self.max_live_bullets.map(|max| max.saturating_sub(self.live_bullets))
+    }
+
+    /// Changes the fire rate, keeping the current firing state
+    pub fn set_fire_rate(&mut self, fire_rate: f32) {
+        self.timer.set_duration(std::time::Duration::from_secs_f32(1.0 / fire_rate));
+    }
+
+    /// The current charge (0.0 - 1.0), ignoring quick taps
+    pub fn charge(&self) -> f32 {
+        match (&self.charge_timer, self.state) {
+            (Some(timer), BulletControllerState::Charging) if timer.elapsed_secs() >= BULLET_CHARGE_TAP_SECS => timer.percent(),
+            _ => 0.,
+        }
+    }
+
     pub fn try_set_firing_state(&mut self, firing: bool) {
+        if let Some(charge_timer) = self.charge_timer.as_mut() {
+            if firing && self.state == BulletControllerState::None {
+                // Start charging
+                self.state = BulletControllerState::Charging;
+                charge_timer.reset();
+            }
+            else if !firing && self.state == BulletControllerState::Charging {
+                // Let go
+                self.state = BulletControllerState::Released;
+            }
+            return;
+        }
         if firing && self.state == BulletControllerState::None {
             // Start firing
             self.state = BulletControllerState::Firing;
@@ -212,13 +348,17 @@ impl BulletController {
 
     pub fn update(&mut self, time: &Time) -> BulletFireResult {
         self.timer.tick(time.delta());
+        if let Some(charge_timer) = self.charge_timer.as_mut() {
+            charge_timer.tick(time.delta());
+        }
         match self.state {
             BulletControllerState::None => BulletFireResult::None,
             BulletControllerState::Firing => {
                 // Fire immediately for the first bullet, and therafter
                 // on the cadence set by the timer
+                // (A shot held back by the bullet limit is skipped, except the first)
                 let should_fire = self.fire_count == 0 || self.timer.just_finished();
-                if should_fire {
+                if should_fire && !self.at_bullet_limit() {
                     self.fire_count += 1;
                     BulletFireResult::FireBullet
                 }
@@ -235,31 +375,90 @@ impl BulletController {
                 }
                 BulletFireResult::None
             },
+            BulletControllerState::Charging => BulletFireResult::None,
+            // Hold the shot until one of our bullets is gone
+            BulletControllerState::Released if self.at_bullet_limit() => BulletFireResult::None,
+            BulletControllerState::Released => {
+                // Fire once, then cool down as usual
+                self.state = BulletControllerState::Cooldown;
+                self.fire_count += 1;
+                self.timer.reset();
+                match &self.charge_timer {
+                    Some(timer) if timer.elapsed_secs() >= BULLET_CHARGE_TAP_SECS => BulletFireResult::FireChargedBullet(timer.percent()),
+                    _ => BulletFireResult::FireBullet,
+                }
+            },
         }
     }
 }
 
+/// Counts the live bullets fired by each entity with a `BulletController`
+fn bullet_owner_count_system(
+    bullets: Query<&Bullet>,
+    mut controllers: Query<(Entity, &mut BulletController)>
+) {
+    let mut live: HashMap<Entity, u32> = HashMap::new();
+    for bullet in bullets.iter() {
+        *live.entry(bullet.owner).or_default() += 1;
+    }
+    for (entity, mut controller) in controllers.iter_mut() {
+        controller.live_bullets = live.get(&entity).copied().unwrap_or(0);
+    }
+}
+
 // Collision detection
 
+type BulletTarget<'a> = (Entity, &'a BulletCollidable, &'a Collidable, Option<&'a Invulnerable>, Option<&'a BulletDeflector>);
+
 fn  bullet_collision_system(
-    bullets: Query<(Entity, &Bullet, &Collidable)>,
-    collidables: Query<(Entity, &BulletCollidable, &Collidable, Option<&Invulnerable>)>,
-    mut hit_events: EventWriter<HitEvent>
+    mut bullets: Query<(Entity, &mut Bullet, &Collidable, &mut Movable)>,
+    collidables: Query<BulletTarget>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
 )
 {
-    for (b_entity, b_bullet, b_collidable) in bullets.iter() {
-        for (o_entity, o_bullet_collidable, o_collidable, invulnerable) in collidables.iter() {
+    let mut rng = thread_rng();
+    for (b_entity, mut b_bullet, b_collidable, mut b_movable) in bullets.iter_mut() {
+        if b_bullet.spent {
+            continue;
+        }
+        for (o_entity, o_bullet_collidable, o_collidable, invulnerable, deflector) in collidables.iter() {
             if invulnerable.is_invulnerable() {
                 continue;
             }
-            if b_bullet.source != o_bullet_collidable.source {
+            if b_bullet.source != o_bullet_collidable.source || b_bullet.hit.contains(&o_entity) {
                 continue;
             }
-            if b_collidable.test_collision_with(&o_collidable) {
-                // Collision!
-                hit_events.send(HitEvent(b_entity));
-                hit_events.send(HitEvent(o_entity));
+            if !b_collidable.test_collision_with(&o_collidable) {
+                continue;
+            }
+            if let Some(deflector) = deflector {
+                // Bullets which are already heading away have been deflected
+                let normal = (b_movable.position - o_collidable.collider.position()).normalize_or_zero();
+                let incoming = b_movable.velocity.dot(normal);
+                if incoming >= 0. {
+                    continue;
+                }
+                if rng.random_f32() < deflector.chance {
+                    // Bounce off the surface
+                    b_movable.velocity -= 2. * incoming * normal;
+                    b_movable.heading_angle = Vec2::X.angle_between(b_movable.velocity);
+                    continue;
+                }
+            }
+            // Collision!
+            b_bullet.hit.push(o_entity);
+            hit_events.send(HitEvent(b_entity));
+            hit_events.send(HitEvent(o_entity));
+            if b_bullet.damage > 1 {
+                damage_events.send(DamageEvent { entity: o_entity, amount: b_bullet.damage });
+            }
+            // Each target uses up one pierce, even if several are hit in the same frame
+            if b_bullet.pierce == 0 {
+                b_bullet.spent = true;
+                break;
             }
+            b_bullet.pierce -= 1;
         }
     }
 }
\ No newline at end of file
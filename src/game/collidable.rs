@@ -30,6 +30,10 @@ impl Collidable {
         sepax2d::sat_overlap(self.shape(), other.shape())
     }
 
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        sepax2d::contains_point(self.shape(), point.into())
+    }
+
     fn shape(&self) -> &dyn sepax2d::Shape {
         match self.collider {
             Collider::Circle(ref circle) => circle,
@@ -45,6 +49,7 @@ fn collidable_update_system(mut query: Query<(&Movable, &mut Collidable)>) {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum Collider {
     Circle(sepax2d::circle::Circle),
     Capsule(sepax2d::capsule::Capsule),
@@ -61,7 +66,15 @@ impl Collider {
         Collider::Capsule(capsule)
     }
 
-    fn set_position(&mut self, position: Vec2) {
+    pub fn position(&self) -> Vec2 {
+        let pos = match self {
+            Collider::Circle(circle) => circle.position,
+            Collider::Capsule(capsule) => capsule.position,
+        };
+        pos.into()
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
         let pos = match self {
             Collider::Circle(ref mut circle) => &mut circle.position,
             Collider::Capsule(ref mut capsule) => &mut capsule.position,
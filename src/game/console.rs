@@ -0,0 +1,447 @@
+use bevy::prelude::*;
+use bevy::ecs::system::CommandQueue;
+use bevy::input::InputSystem;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::AppState;
+use crate::theme::{Theme, ThemeColor, ThemedText};
+use super::alien::{AlienSpawn, AlienUfo, AlienUfoVariant, spawn_alien_ufo};
+use super::assets::GameAssets;
+use super::asteroid::{Asteroid, AsteroidMaterial, AsteroidShapeId, AsteroidSize, AsteroidSpawn, spawn_asteroid};
+use super::boss::Mothership;
+use super::bullet::Bullet;
+use super::invulnerable::Invulnerable;
+use super::manager::{GameCleanup, GameManager, GameRng, WorldBoundaries};
+use super::player::PlayerRocket;
+
+// Developer console (`dev-tools` feature only)
+//
+// [`] toggles a text console over the game. While it is open it swallows keyboard input, and
+// submitted lines are parsed into a `ConsoleCommand` and run against the world.
+
+const CONSOLE_LOG_LINES: usize = 10;
+const CONSOLE_UFO_SPEED: f32 = 35.0;
+
+/// Everything tab-completion knows about
+const CONSOLE_COMPLETIONS: [&str; 9] = [
+    "spawn asteroid",
+    "spawn ufo",
+    "lives",
+    "god",
+    "timescale",
+    "wave",
+    "seed",
+    "dump entities",
+    "help",
+];
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>();
+        app.add_systems(
+            PreUpdate,
+            console_input_system
+                .after(InputSystem)
+                .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(
+            Update,
+            (
+                console_execute_system,
+                console_god_mode_system,
+                console_view_system,
+            )
+            .chain()
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(OnEnter(AppState::Game), console_time_scale_system);
+        app.add_systems(OnExit(AppState::Game), console_close_system);
+        app.add_systems(GameCleanup, console_teardown_system);
+    }
+}
+
+// Console state
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    log: Vec<String>,
+    history: Vec<String>,
+    /// Position in `history` while browsing it with the arrow keys
+    history_index: Option<usize>,
+    /// Submitted lines, waiting to be run
+    pending: Vec<String>,
+    god_mode: bool,
+    /// Set by `timescale`, and only applied while the game is running
+    time_scale: Option<f32>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        info!("console: {}", line);
+        self.log.push(line);
+    }
+
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        let line = line.trim();
+        self.history_index = None;
+        if line.is_empty() {
+            return;
+        }
+        self.print(format!("> {}", line));
+        self.history.push(line.to_string());
+        self.pending.push(line.to_string());
+    }
+
+    fn browse_history(&mut self, step: i32) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.history_index = match (self.history_index, step < 0) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i < last => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        self.input = self.history_index
+            .map(|i| self.history[i].clone())
+            .unwrap_or_default();
+    }
+
+    fn complete(&mut self) {
+        let candidates: Vec<&str> = CONSOLE_COMPLETIONS.iter()
+            .copied()
+            .filter(|c| c.starts_with(self.input.as_str()))
+            .collect();
+        match candidates.as_slice() {
+            [] => {},
+            [only] => self.input = format!("{} ", only),
+            _ => {
+                // Fill in as much as all the candidates agree on, and list them
+                let prefix = common_prefix(&candidates);
+                if prefix.len() > self.input.len() {
+                    self.input = prefix.to_string();
+                }
+                else {
+                    self.print(candidates.join("  "));
+                }
+            },
+        }
+    }
+}
+
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let first = words[0];
+    let len = words.iter()
+        .map(|w| first.bytes().zip(w.bytes()).take_while(|(a, b)| a == b).count())
+        .min()
+        .unwrap_or(0);
+    &first[..len]
+}
+
+// Commands
+
+enum ConsoleCommand {
+    SpawnAsteroid { size: AsteroidSize, position: Option<Vec2> },
+    SpawnUfo { variant: AlienUfoVariant },
+    Lives(u32),
+    God,
+    TimeScale(f32),
+    Wave(u32),
+    Seed(u64),
+    DumpEntities,
+    Help,
+}
+
+fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["spawn", "asteroid", args @ ..] => {
+            let size = match args.first().copied().unwrap_or("large") {
+                "small" => AsteroidSize::Small,
+                "medium" => AsteroidSize::Medium,
+                "large" => AsteroidSize::Large,
+                other => return Err(format!("unknown asteroid size '{}'", other)),
+            };
+            let position = match args.get(1..) {
+                Some([x, y]) => Some(Vec2::new(parse_arg(x)?, parse_arg(y)?)),
+                Some([]) | None => None,
+                Some(_) => return Err("usage: spawn asteroid [size] [x y]".to_string()),
+            };
+            Ok(ConsoleCommand::SpawnAsteroid { size, position })
+        },
+        ["spawn", "ufo", args @ ..] => {
+            let variant = match args.first().copied().unwrap_or("large") {
+                "large" => AlienUfoVariant::Large,
+                "small" => AlienUfoVariant::Small,
+                other => return Err(format!("unknown ufo variant '{}'", other)),
+            };
+            Ok(ConsoleCommand::SpawnUfo { variant })
+        },
+        ["lives", n] => Ok(ConsoleCommand::Lives(parse_arg(n)?)),
+        ["god"] => Ok(ConsoleCommand::God),
+        ["timescale", scale] => {
+            let scale: f32 = parse_arg(scale)?;
+            if !scale.is_finite() || scale < 0. {
+                return Err("timescale must be a non-negative number".to_string());
+            }
+            Ok(ConsoleCommand::TimeScale(scale))
+        },
+        ["wave", n] => Ok(ConsoleCommand::Wave(parse_arg(n)?)),
+        ["seed", seed] => Ok(ConsoleCommand::Seed(parse_arg(seed)?)),
+        ["dump", "entities"] => Ok(ConsoleCommand::DumpEntities),
+        ["help"] => Ok(ConsoleCommand::Help),
+        _ => Err(format!("unknown command '{}' (try 'help')", line)),
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse().map_err(|_| format!("invalid argument '{}'", arg))
+}
+
+fn run_command(world: &mut World, command: ConsoleCommand) -> String {
+    match command {
+        ConsoleCommand::SpawnAsteroid { size, position } => {
+            let position = position.unwrap_or(Vec2::ZERO);
+            world.resource_scope(|world, assets: Mut<GameAssets>| {
+                let mut queue = CommandQueue::default();
+                spawn_asteroid(&mut Commands::new(&mut queue, world), &assets.asteroid, AsteroidSpawn {
+                    size,
+                    material: AsteroidMaterial::Rock,
+                    shape: AsteroidShapeId::A,
+                    position,
+                    velocity: Vec2::ZERO,
+                    rotation: 0.0,
+                    heading_angle: 0.,
+                    invulnerable: None,
+                });
+                queue.apply(world);
+            });
+            format!("spawned {:?} asteroid at {}", size, position)
+        },
+        ConsoleCommand::SpawnUfo { variant } => {
+            // Fly in from the left
+            let left = world.resource::<WorldBoundaries>().left;
+            let avoid_asteroids = world.resource::<GameManager>().alien_avoids_asteroids();
+            world.resource_scope(|world, assets: Mut<GameAssets>| {
+                let mut queue = CommandQueue::default();
+                spawn_alien_ufo(&mut Commands::new(&mut queue, world), &assets.alien, AlienSpawn {
+                    variant,
+                    position: Vec2::new(left - 10.0, 0.0),
+                    velocity: Vec2::new(CONSOLE_UFO_SPEED, 0.0),
+                    avoid_asteroids,
+                });
+                queue.apply(world);
+            });
+            format!("spawned {:?} ufo", variant)
+        },
+        ConsoleCommand::Lives(lives) => {
+            world.resource_mut::<GameManager>().player_lives_remaining = lives;
+            format!("lives set to {}", lives)
+        },
+        ConsoleCommand::God => {
+            let mut console = world.resource_mut::<Console>();
+            console.god_mode = !console.god_mode;
+            format!("god mode {}", if console.god_mode { "on" } else { "off" })
+        },
+        ConsoleCommand::TimeScale(scale) => {
+            world.resource_mut::<Console>().time_scale = Some(scale);
+            world.resource_mut::<Time>().set_relative_speed(scale);
+            format!("timescale set to {}", scale)
+        },
+        ConsoleCommand::Wave(wave) => {
+            world.resource_mut::<GameManager>().jump_to_wave(wave);
+            format!("jumped to wave {}", wave)
+        },
+        ConsoleCommand::Seed(seed) => {
+            // NOTE: Only what spawns, and where (UFO aim, debris and particles stay random)
+            world.resource_mut::<GameRng>().0 = StdRng::seed_from_u64(seed);
+            format!("seeded spawns with {} (UFO aim and effects are still random)", seed)
+        },
+        ConsoleCommand::DumpEntities => {
+            let total = world.entities().len();
+            let rockets = world.query_filtered::<(), With<PlayerRocket>>().iter(world).count();
+            let asteroids = world.query_filtered::<(), With<Asteroid>>().iter(world).count();
+            let ufos = world.query_filtered::<(), With<AlienUfo>>().iter(world).count();
+            let bullets = world.query_filtered::<(), With<Bullet>>().iter(world).count();
+            let motherships = world.query_filtered::<(), With<Mothership>>().iter(world).count();
+            format!(
+                "entities: {} (rockets: {}, asteroids: {}, ufos: {}, bullets: {}, motherships: {})",
+                total, rockets, asteroids, ufos, bullets, motherships
+            )
+        },
+        ConsoleCommand::Help => format!("commands: {} ('seed' only seeds spawns)", CONSOLE_COMPLETIONS.join(", ")),
+    }
+}
+
+// Systems
+
+fn console_input_system(
+    mut console: ResMut<Console>,
+    mut kb: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+) {
+    if kb.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        kb.reset_all();
+        characters.clear();
+        return;
+    }
+    if !console.open {
+        characters.clear();
+        return;
+    }
+
+    for ev in characters.iter() {
+        if !ev.char.is_control() && ev.char != '`' {
+            console.input.push(ev.char);
+        }
+    }
+    if kb.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if kb.just_pressed(KeyCode::Return) {
+        console.submit();
+    }
+    if kb.just_pressed(KeyCode::Tab) {
+        console.complete();
+    }
+    if kb.just_pressed(KeyCode::Up) {
+        console.browse_history(-1);
+    }
+    if kb.just_pressed(KeyCode::Down) {
+        console.browse_history(1);
+    }
+    if kb.just_pressed(KeyCode::Escape) {
+        console.open = false;
+    }
+
+    // Keep typing from steering the rocket
+    kb.reset_all();
+}
+
+fn console_execute_system(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in pending {
+        let output = match parse_command(&line) {
+            Ok(command) => run_command(world, command),
+            Err(error) => error,
+        };
+        world.resource_mut::<Console>().print(output);
+    }
+}
+
+fn console_god_mode_system(
+    mut commands: Commands,
+    console: Res<Console>,
+    mut was_god_mode: Local<bool>,
+    rockets: Query<(Entity, Option<&Invulnerable>), With<PlayerRocket>>,
+) {
+    let toggled = console.god_mode != *was_god_mode;
+    *was_god_mode = console.god_mode;
+    for (entity, invulnerable) in rockets.iter() {
+        if console.god_mode && (toggled || !invulnerable.is_some_and(Invulnerable::is_indefinite)) {
+            // Also covers newly spawned rockets
+            commands.entity(entity).insert(Invulnerable::indefinite());
+        }
+        else if !console.god_mode && toggled {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+// View
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn console_view_system(
+    mut commands: Commands,
+    console: Res<Console>,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    root: Query<Entity, With<ConsoleRoot>>,
+    mut text: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    match (console.open, root.get_single().ok()) {
+        (true, None) => spawn_console_view(&mut commands, &asset_server, &theme, &console),
+        (false, Some(entity)) => commands.entity(entity).despawn_recursive(),
+        _ => {
+            if let Ok(mut text) = text.get_single_mut() {
+                write_console(&mut text.sections[0].value, &console);
+            }
+        },
+    }
+}
+
+fn spawn_console_view(commands: &mut Commands, asset_server: &AssetServer, theme: &Theme, console: &Console) {
+    let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
+    let mut contents = String::new();
+    write_console(&mut contents, console);
+    commands
+        .spawn((
+            ConsoleRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ConsoleText,
+                TextBundle::from_section(
+                    contents,
+                    TextStyle {
+                        font: font_light,
+                        font_size: 20.0,
+                        color: theme.text,
+                    },
+                ),
+                ThemedText(vec![ThemeColor::Text]),
+            ));
+        });
+}
+
+fn write_console(output: &mut String, console: &Console) {
+    use std::fmt::Write;
+    output.clear();
+    let start = console.log.len().saturating_sub(CONSOLE_LOG_LINES);
+    for line in &console.log[start..] {
+        writeln!(output, "{}", line).unwrap();
+    }
+    write!(output, "> {}_", console.input).unwrap();
+}
+
+fn console_time_scale_system(console: Res<Console>, mut time: ResMut<Time>) {
+    time.set_relative_speed(console.time_scale.unwrap_or(1.0));
+}
+
+fn console_close_system(mut console: ResMut<Console>, mut time: ResMut<Time>) {
+    console.open = false;
+    // NOTE: Menus (and the pause screen) always run at normal speed
+    time.set_relative_speed(1.0);
+}
+
+fn console_teardown_system(mut console: ResMut<Console>) {
+    console.time_scale = None;
+}
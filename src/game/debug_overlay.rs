@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use crate::AppState;
+use super::FrameStage;
+use super::collidable::{Collidable, Collider};
+use super::hit::{HitEvent, distinct_hit_events};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::GameCleanup;
+use super::movable::{Movable, MovableTorusConstraint};
+
+// Debug overlay
+//
+// [F3] toggles an overlay which draws what the simulation actually sees: collider outlines
+// (which can differ quite a bit from the drawn shapes), velocity and acceleration arrows and
+// torus wrap-around radii. Invulnerable entities and anything hit recently are highlighted.
+// Each kind of outline is drawn by its own layer entity, whose path is rebuilt every frame.
+
+const OVERLAY_Z: f32 = 50.0;
+const OVERLAY_LINE_WIDTH: f32 = 0.3;
+const OVERLAY_CIRCLE_SEGMENTS: usize = 16;
+/// Velocity arrows show where the entity will be after this long
+const OVERLAY_VELOCITY_ARROW_SECS: f32 = 0.5;
+/// Acceleration arrows show the change in velocity after this long
+const OVERLAY_ACCELERATION_ARROW_SECS: f32 = 0.25;
+const OVERLAY_ARROW_HEAD_SIZE: f32 = 1.5;
+/// Hits are only reported for a single frame, so keep them on screen a little longer
+const OVERLAY_HIT_HIGHLIGHT_SECS: f32 = 0.3;
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>();
+        app.add_systems(
+            Update,
+            (
+                debug_overlay_keyboard_system,
+
+                debug_overlay_hit_system
+                    .after(FrameStage::Collision),
+
+                debug_overlay_draw_system
+                    .after(FrameStage::CollisionEffect)
+                    .after(debug_overlay_hit_system),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, debug_overlay_teardown_system);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    recent_hits: Vec<(Collider, Timer)>,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum DebugOverlayLayer {
+    Colliders,
+    Invulnerable,
+    Hits,
+    Velocity,
+    Acceleration,
+    TorusRadius,
+}
+
+impl DebugOverlayLayer {
+    const VALUES: [Self; 6] = [ Self::Colliders, Self::Invulnerable, Self::Hits, Self::Velocity, Self::Acceleration, Self::TorusRadius ];
+
+    fn color(self) -> Color {
+        match self {
+            DebugOverlayLayer::Colliders => Color::GREEN,
+            DebugOverlayLayer::Invulnerable => Color::CYAN,
+            DebugOverlayLayer::Hits => Color::RED,
+            DebugOverlayLayer::Velocity => Color::YELLOW,
+            DebugOverlayLayer::Acceleration => Color::ORANGE,
+            DebugOverlayLayer::TorusRadius => Color::rgba(1.0, 1.0, 1.0, 0.25),
+        }
+    }
+}
+
+// Teardown
+
+fn debug_overlay_teardown_system(
+    mut commands: Commands,
+    mut overlay: ResMut<DebugOverlay>,
+    query: Query<Entity, With<DebugOverlayLayer>>
+) {
+    // NOTE: The layers are gone, so start the next game with the overlay off
+    overlay.enabled = false;
+    overlay.recent_hits.clear();
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Systems
+
+fn debug_overlay_keyboard_system(
+    mut commands: Commands,
+    kb: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    layers: Query<Entity, With<DebugOverlayLayer>>
+) {
+    if !kb.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.enabled = !overlay.enabled;
+    overlay.recent_hits.clear();
+    if !overlay.enabled {
+        for entity in layers.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for layer in DebugOverlayLayer::VALUES {
+        commands.spawn((
+            layer,
+            ShapeBundle {
+                transform: Transform::from_xyz(0., 0., OVERLAY_Z),
+                ..default()
+            },
+            Stroke::new(layer.color(), OVERLAY_LINE_WIDTH),
+        ));
+    }
+}
+
+fn debug_overlay_hit_system(
+    time: Res<Time>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut hit_events: EventReader<HitEvent>,
+    collidables: Query<&Collidable>,
+) {
+    if !overlay.enabled {
+        hit_events.clear();
+        return;
+    }
+    overlay.recent_hits.retain_mut(|(_, timer)| !timer.tick(time.delta()).finished());
+    for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
+        if let Ok(collidable) = collidables.get(entity) {
+            overlay.recent_hits.push((collidable.collider, Timer::from_seconds(OVERLAY_HIT_HIGHLIGHT_SECS, TimerMode::Once)));
+        }
+    }
+}
+
+type OverlayTarget<'a> = (Option<&'a Collidable>, Option<&'a Movable>, Option<&'a MovableTorusConstraint>, Option<&'a Invulnerable>);
+type OverlayTargetFilter = Or<(With<Collidable>, With<Movable>)>;
+
+fn debug_overlay_draw_system(
+    overlay: Res<DebugOverlay>,
+    targets: Query<OverlayTarget, OverlayTargetFilter>,
+    mut layers: Query<(&DebugOverlayLayer, &mut Path)>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let mut builders: [PathBuilder; DebugOverlayLayer::VALUES.len()] = std::array::from_fn(|_| PathBuilder::new());
+    for (collidable, movable, torus, invulnerable) in targets.iter() {
+        if let Some(collidable) = collidable {
+            let layer = if invulnerable.is_invulnerable() { DebugOverlayLayer::Invulnerable } else { DebugOverlayLayer::Colliders };
+            add_collider(&mut builders[layer as usize], &collidable.collider);
+        }
+        if let Some(movable) = movable {
+            let position = movable.position;
+            add_arrow(&mut builders[DebugOverlayLayer::Velocity as usize], position, position + movable.velocity * OVERLAY_VELOCITY_ARROW_SECS);
+            add_arrow(&mut builders[DebugOverlayLayer::Acceleration as usize], position, position + movable.acceleration() * OVERLAY_ACCELERATION_ARROW_SECS);
+            if let Some(torus) = torus {
+                add_circle(&mut builders[DebugOverlayLayer::TorusRadius as usize], position, torus.radius);
+            }
+        }
+    }
+    for (collider, _) in overlay.recent_hits.iter() {
+        add_collider(&mut builders[DebugOverlayLayer::Hits as usize], collider);
+    }
+
+    for (&layer, mut path) in layers.iter_mut() {
+        *path = std::mem::replace(&mut builders[layer as usize], PathBuilder::new()).build();
+    }
+}
+
+// Outlines
+// NOTE: Also used by the level editor
+
+pub fn add_collider(builder: &mut PathBuilder, collider: &Collider) {
+    match collider {
+        Collider::Circle(circle) => add_circle(builder, circle.position.into(), circle.radius),
+        Collider::Capsule(capsule) => {
+            // Two end caps, joined along either side
+            let position: Vec2 = capsule.position.into();
+            let arm: Vec2 = capsule.arm().into();
+            let side = arm.perp().normalize_or_zero() * capsule.radius;
+            add_circle(builder, position - arm, capsule.radius);
+            add_circle(builder, position + arm, capsule.radius);
+            builder.move_to(position - arm + side);
+            builder.line_to(position + arm + side);
+            builder.move_to(position - arm - side);
+            builder.line_to(position + arm - side);
+        },
+    }
+}
+
+pub fn add_circle(builder: &mut PathBuilder, center: Vec2, radius: f32) {
+    builder.move_to(center + Vec2::new(radius, 0.));
+    for i in 1..OVERLAY_CIRCLE_SEGMENTS {
+        let angle = i as f32 / OVERLAY_CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+        builder.line_to(center + Vec2::from_angle(angle) * radius);
+    }
+    builder.close();
+}
+
+pub fn add_arrow(builder: &mut PathBuilder, from: Vec2, to: Vec2) {
+    let direction = (to - from).normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let back = -direction * OVERLAY_ARROW_HEAD_SIZE;
+    builder.move_to(from);
+    builder.line_to(to);
+    builder.move_to(to + back + back.perp() * 0.5);
+    builder.line_to(to);
+    builder.line_to(to + back - back.perp() * 0.5);
+}
@@ -1,10 +1,13 @@
 use bevy::prelude::*;
-use bevy::utils::HashMap;
 use bevy_prototype_lyon::prelude::*;
 use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::collidable::{Collidable, Collider};
+use super::hit::HitEvent;
+use super::invulnerable::{Invulnerable, TestInvulnerable};
 use super::manager::GameCleanup;
 use super::movable::Movable;
-use super::svg::simple_svg_to_path;
 use super::util::*;
 
 // Explosions
@@ -16,63 +19,117 @@ pub struct ExplosionPlugin;
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, 
-            explosion_system
+            (
+                explosion_system,
+                shockwave_system
+                    .after(FrameStage::Movement),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(Update,
+            shockwave_collision_system
+                .in_set(FrameStage::Collision)
+                .after(FrameStage::Movement)
+                .after(shockwave_system)
                 .run_if(in_state(AppState::Game))
         );
         app.add_systems(GameCleanup, destroy_explosions_system);
     }
 }
 
-// Setup
+// Debris generation
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub enum ExplosionShapeId {
-    RocketDebris,
-    AsteroidDebris,
-    UfoDebris,
-}
+const EXPLOSION_SEGMENTS_PER_PART: usize = 3;
 
 pub struct ExplosionPart {
     direction: Vec2,
     shape: Path,
 }
 
-pub struct ExplosionAssets {
-    explosion_parts: HashMap<ExplosionShapeId, Vec<ExplosionPart>>,
+/// Breaks the given shape up into debris.
+/// Each sub-path is split into groups of (at most) `EXPLOSION_SEGMENTS_PER_PART` connected segments,
+/// and each group is sent flying away from the centre of the shape towards its own centroid.
+pub fn create_explosion_parts(shape: &Path) -> Vec<ExplosionPart> {
+    use tess::path::PathEvent;
+
+    let mut groups: Vec<Vec<PathEvent>> = Vec::new();
+    let mut current: Vec<PathEvent> = Vec::new();
+    for event in shape.0.iter() {
+        match event {
+            PathEvent::Begin { .. } => {},
+            PathEvent::End { last, first, close } => {
+                // Closing a sub-path draws a line back to the start
+                if close && last != first {
+                    current.push(PathEvent::Line { from: last, to: first });
+                }
+                if !current.is_empty() {
+                    groups.push(std::mem::take(&mut current));
+                }
+            },
+            segment => {
+                current.push(segment);
+                if current.len() == EXPLOSION_SEGMENTS_PER_PART {
+                    groups.push(std::mem::take(&mut current));
+                }
+            },
+        }
+    }
+
+    let centroids = groups.iter().map(|g| segments_centroid(g)).collect::<Vec<_>>();
+    let centre = centroids.iter().copied().sum::<Vec2>() / centroids.len().max(1) as f32;
+
+    groups.iter()
+        .zip(centroids)
+        .map(|(group, centroid)| ExplosionPart {
+            direction: (centroid - centre).normalize_or_zero(),
+            shape: segments_to_path(group),
+        })
+        .collect()
 }
 
-pub fn create_explosion_assets() -> ExplosionAssets {
-    fn unit(x: f32, y: f32) -> Vec2 {
-        Vec2::new(x, y).normalize()
+fn segments_centroid(segments: &[tess::path::PathEvent]) -> Vec2 {
+    use tess::path::PathEvent;
+    let mut sum = Vec2::ZERO;
+    for segment in segments {
+        sum += match *segment {
+            PathEvent::Line { from, to } |
+            PathEvent::Quadratic { from, to, .. } |
+            PathEvent::Cubic { from, to, .. } => Vec2::new(from.x + to.x, from.y + to.y) / 2.0,
+            _ => unreachable!(),
+        };
     }
-    use ExplosionShapeId::*;
-    // See: https://yqnn.github.io/svg-path-editor/
-    let explosion_part_directions_and_shapes = vec![
-        // id, diameter, path
-        // See: https://yqnn.github.io/svg-path-editor/
-        (RocketDebris, unit( 1., -1.), "M 3 0 L 1.6 -0.8 M 3 0 L -2 2 M -1 1.6 L -0.9 0.6"),
-        (RocketDebris, unit(-1.,  1.), "M -2 -2 L 1.6 -0.5 M -1 -1.6 L -1 0.6"),
-        // Asteroid Debris
-        (AsteroidDebris, unit(-1.,  1.), "M 1 -5 L -2 -5 L -5 -2 L -5 0 L -3 0"),
-        (AsteroidDebris, unit( 4.,  2.), "M 2 2 L 5 0 L 4 -2 L 1 -5"),
-        (AsteroidDebris, unit(-1., -2.), "M -2 0 L -5 2 L -2 5 L 3 4 L 2 2"),
-        // Ufo Debris
-        (UfoDebris, unit(0.,  1.), "M 5 0 C 2 -2 -2 -2 -5 0 L -5 1 M 3 -1 Q 0 -5 -3 -1"),
-        (UfoDebris, unit(0., -1.), "M -5 1 C -2 3 2 3 5 1 L 5 0"),
-    ];
-    let mut explosion_parts = HashMap::new();
-    for (explosion_id, direction, svg) in explosion_part_directions_and_shapes.into_iter() {
-        let shape = simple_svg_to_path(svg);
-        explosion_parts
-            .entry(explosion_id).or_insert(Vec::new())
-            .push(ExplosionPart { direction, shape });
+    sum / segments.len() as f32
+}
+
+fn segments_to_path(segments: &[tess::path::PathEvent]) -> Path {
+    use tess::path::PathEvent;
+    let mut builder = tess::path::Path::builder();
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            PathEvent::Line { from, to } => {
+                if i == 0 { builder.begin(from); }
+                builder.line_to(to);
+            },
+            PathEvent::Quadratic { from, ctrl, to } => {
+                if i == 0 { builder.begin(from); }
+                builder.quadratic_bezier_to(ctrl, to);
+            },
+            PathEvent::Cubic { from, ctrl1, ctrl2, to } => {
+                if i == 0 { builder.begin(from); }
+                builder.cubic_bezier_to(ctrl1, ctrl2, to);
+            },
+            _ => unreachable!(),
+        }
     }
-    ExplosionAssets { explosion_parts }
+    builder.end(false);
+    Path(builder.build())
 }
 
 // Teardown
 
-fn destroy_explosions_system(mut commands: Commands, query: Query<Entity, With<Explosion>>) {
+type ExplosionEntity = Or<(With<Explosion>, With<Shockwave>)>;
+
+fn destroy_explosions_system(mut commands: Commands, query: Query<Entity, ExplosionEntity>) {
     for entity in query.iter() {
         commands
             .entity(entity)
@@ -91,17 +148,31 @@ pub struct Explosion {
 #[derive(Component)]
 pub struct ExplosionCollidable;
 
+/// An expanding ring which hits every `ExplosionCollidable` entity it passes over
+#[derive(Component)]
+pub struct Shockwave {
+    position: Vec2,
+    radius: f32,
+    max_radius: f32,
+    timer: Timer,
+    /// Entities which have already been hit (or should never be)
+    hit: Vec<Entity>,
+}
+
 // Spawning
 
 #[derive(Clone)]
-pub struct SpawnExplosion {
-    pub shape_id: ExplosionShapeId,
+pub struct SpawnExplosion<'a> {
+    /// The shape to break up into debris
+    pub shape: &'a Path,
     pub shape_scale: f32,
     pub position: Vec2,
     pub velocity: Vec2,
     pub heading_angle: f32,
     pub rotational_velocity: f32,
     pub despawn_after_secs: f32,
+    /// If set, the explosion also sends out a shockwave of this radius
+    pub shockwave_radius: Option<f32>,
 }
 
 const LINE_WIDTH: f32 = 0.2;
@@ -111,14 +182,13 @@ const EXPLOSION_PART_MAX_ADD_SPEED: f32 = 25.0;
 pub fn spawn_explosion(
     commands: &mut Commands,
     rng: &mut rand::rngs::ThreadRng,
-    assets: &ExplosionAssets,
     spawn: SpawnExplosion
 ) {
-    let explosion_color = Color::rgba(0.8, 0.8, 0.8, 1.0);
-    let explosion_stroke = Stroke::new(explosion_color, LINE_WIDTH / spawn.shape_scale);
+    // NOTE: Colour is applied by the theme
+    let explosion_stroke = Stroke::new(Color::WHITE, LINE_WIDTH / spawn.shape_scale);
     let explosion_part_speed = EXPLOSION_PART_MIN_ADD_SPEED + rng.random_f32() * (EXPLOSION_PART_MAX_ADD_SPEED - EXPLOSION_PART_MIN_ADD_SPEED);
 
-    let parts = assets.explosion_parts.get(&spawn.shape_id).unwrap();
+    let parts = create_explosion_parts(spawn.shape);
     for part in parts.iter() {
         let rotation = Vec2::from_angle(spawn.heading_angle);
         let position = spawn.position;
@@ -133,23 +203,60 @@ pub fn spawn_explosion(
                 Explosion {
                     despawn_timer: Timer::from_seconds(spawn.despawn_after_secs, TimerMode::Once),
                 },
-                Movable {
-                    position,
-                    velocity,
-                    acceleration: None,
-                    heading_angle: spawn.heading_angle,
-                    rotational_velocity: spawn.rotational_velocity,
-                    rotational_acceleration: None,
-                },
+                Movable::new(position, velocity)
+                    .with_heading_angle(spawn.heading_angle)
+                    .with_rotational_velocity(spawn.rotational_velocity),
                 // Rendering
                 ShapeBundle {
                     transform,
                     path: Path(part.shape.0.clone()),
                     ..default()
                 },
-                explosion_stroke
+                explosion_stroke,
+                Themed(ThemeColor::Explosion),
             ));
     }
+
+    if let Some(max_radius) = spawn.shockwave_radius {
+        spawn_shockwave(commands, SpawnShockwave { position: spawn.position, max_radius, ignore: None });
+    }
+}
+
+#[derive(Clone)]
+pub struct SpawnShockwave {
+    pub position: Vec2,
+    pub max_radius: f32,
+    /// An entity which is never hit by this shockwave (e.g. whoever set it off)
+    pub ignore: Option<Entity>,
+}
+
+const SHOCKWAVE_SPEED: f32 = 150.0;
+const SHOCKWAVE_LINE_WIDTH: f32 = 0.5;
+
+pub fn spawn_shockwave(commands: &mut Commands, spawn: SpawnShockwave) {
+    let position = spawn.position;
+    commands.spawn((
+        Shockwave {
+            position,
+            radius: 0.,
+            max_radius: spawn.max_radius,
+            timer: Timer::from_seconds(spawn.max_radius / SHOCKWAVE_SPEED, TimerMode::Once),
+            hit: spawn.ignore.into_iter().collect(),
+        },
+        // Rendering
+        ShapeBundle {
+            path: shockwave_path(0.),
+            transform: Transform::from_xyz(position.x, position.y, EXPLOSION_Z),
+            ..default()
+        },
+        // NOTE: Colour is applied by the theme
+        Stroke::new(Color::WHITE, SHOCKWAVE_LINE_WIDTH),
+        Themed(ThemeColor::Explosion),
+    ));
+}
+
+fn shockwave_path(radius: f32) -> Path {
+    GeometryBuilder::build_as(&shapes::Circle { radius, center: Vec2::ZERO })
 }
 
 fn explosion_system(
@@ -169,4 +276,45 @@ fn explosion_system(
         let percent_left = explosion.despawn_timer.percent_left(); // 1.0 -> 0.0
         stroke.color.set_a(percent_left);
     }
-}
\ No newline at end of file
+}
+
+// Shockwaves
+
+fn shockwave_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Shockwave, &mut Path, &mut Stroke)>
+) {
+    for (entity, mut shockwave, mut path, mut stroke) in query.iter_mut() {
+        shockwave.timer.tick(time.delta());
+        if shockwave.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Expand quickly at first, then slow down, fading as it goes
+        let t = shockwave.timer.percent();
+        shockwave.radius = shockwave.max_radius * (1. - (1. - t).powi(2));
+        *path = shockwave_path(shockwave.radius);
+        stroke.color.set_a(1. - t);
+    }
+}
+
+fn shockwave_collision_system(
+    mut shockwaves: Query<&mut Shockwave>,
+    targets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<ExplosionCollidable>>,
+    mut hit_events: EventWriter<HitEvent>,
+) {
+    for mut shockwave in shockwaves.iter_mut() {
+        let ring = Collidable { collider: Collider::circle(shockwave.position, shockwave.radius) };
+        for (entity, collidable, invulnerable) in targets.iter() {
+            if invulnerable.is_invulnerable() || shockwave.hit.contains(&entity) {
+                continue;
+            }
+            if ring.test_collision_with(collidable) {
+                // Each entity is only hit once by each shockwave
+                shockwave.hit.push(entity);
+                hit_events.send(HitEvent(entity));
+            }
+        }
+    }
+}
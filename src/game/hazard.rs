@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
+use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::asteroid::Asteroid;
+use super::bullet::Bullet;
+use super::collidable::{Collidable, Collider};
+use super::hit::HitEvent;
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::{GameCleanup, WorldBoundaries};
+use super::movable::{Drag, Movable, MovableGlobalState, MovableTorusConstraint};
+use super::particle::{ParticleBurst, ParticleKind, ParticlePool};
+use super::player::PlayerRocket;
+
+// Environmental hazards
+//
+// Gravity wells pull the rocket, bullets and asteroids towards them, and destroy anything which
+// touches their core. Nebulae slow down everything inside them.
+// Both act through `Movable::add_force`, and measure distances across the wrap-around edges of the world.
+
+const HAZARD_Z: f32 = 3.0;
+const LINE_WIDTH: f32 = 0.3;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                gravity_well_force_system
+                    .after(FrameStage::Input)
+                    .before(FrameStage::Movement),
+
+                nebula_force_system
+                    .after(FrameStage::Input)
+                    .before(FrameStage::Movement),
+            )
+            .run_if(hazard_forces_active)
+        );
+        app.add_systems(
+            Update,
+            gravity_well_core_system
+                .in_set(FrameStage::Collision)
+                .after(FrameStage::Movement)
+                .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, hazard_teardown_system);
+    }
+}
+
+/// Forces also apply while the level editor previews a layout
+fn hazard_forces_active(state: Res<State<AppState>>, movable_state: Res<MovableGlobalState>) -> bool {
+    match state.get() {
+        AppState::Game => true,
+        AppState::Editor => movable_state.enabled,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum HazardSpawn {
+    GravityWell {
+        position: Vec2,
+        /// Acceleration at a distance of 1 unit (falls off with the square of the distance)
+        strength: f32,
+        core_radius: f32,
+        /// No force is applied beyond this distance
+        range: f32,
+    },
+    Nebula {
+        position: Vec2,
+        radius: f32,
+        drag: Drag,
+    },
+}
+
+impl HazardSpawn {
+    pub fn position(&self) -> Vec2 {
+        match *self {
+            HazardSpawn::GravityWell { position, .. } | HazardSpawn::Nebula { position, .. } => position,
+        }
+    }
+
+    /// Returns true if this hazard would immediately destroy something at the given position
+    pub fn is_deadly_at(&self, position: Vec2, clearance: f32) -> bool {
+        match self {
+            HazardSpawn::GravityWell { position: p, core_radius, .. } => p.distance(position) < core_radius + clearance,
+            HazardSpawn::Nebula { .. } => false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GravityWell {
+    position: Vec2,
+    strength: f32,
+    core_radius: f32,
+    range: f32,
+}
+
+#[derive(Component)]
+pub struct Nebula {
+    position: Vec2,
+    radius: f32,
+    drag: Drag,
+}
+
+/// Marker for the root entity of a hazard
+#[derive(Component)]
+struct Hazard;
+
+// Forces
+
+type GravityAffected = Or<(With<PlayerRocket>, With<Bullet>, With<Asteroid>)>;
+
+fn gravity_well_force_system(
+    world_boundaries: Res<WorldBoundaries>,
+    wells: Query<&GravityWell>,
+    mut query: Query<&mut Movable, GravityAffected>
+) {
+    for well in wells.iter() {
+        for mut movable in query.iter_mut() {
+            let delta = world_boundaries.torus_delta(movable.position, well.position);
+            let distance = delta.length();
+            if distance > well.range || distance == 0. {
+                continue;
+            }
+            // Inverse-square falloff, clamped inside the core to avoid huge forces
+            let distance = distance.max(well.core_radius);
+            let acceleration = well.strength / (distance * distance);
+            let force = delta.normalize() * acceleration * movable.mass;
+            movable.add_force(force);
+        }
+    }
+}
+
+fn nebula_force_system(
+    time: Res<Time>,
+    world_boundaries: Res<WorldBoundaries>,
+    nebulae: Query<&Nebula>,
+    mut query: Query<&mut Movable, With<MovableTorusConstraint>>
+) {
+    let t_secs = time.delta_seconds();
+    if t_secs <= 0. {
+        return;
+    }
+    for nebula in nebulae.iter() {
+        for mut movable in query.iter_mut() {
+            let delta = world_boundaries.torus_delta(movable.position, nebula.position);
+            if delta.length() > nebula.radius {
+                continue;
+            }
+            // Convert the drag into a force opposing the velocity
+            let speed = movable.velocity.length();
+            let deceleration = (speed - nebula.drag.apply(speed, t_secs)) / t_secs;
+            let force = -movable.velocity.normalize_or_zero() * deceleration * movable.mass;
+            movable.add_force(force);
+        }
+    }
+}
+
+// Gravity well cores
+
+type GravitySwallowed = Or<(With<Bullet>, With<Asteroid>)>;
+
+fn gravity_well_core_system(
+    mut commands: Commands,
+    mut hit_events: EventWriter<HitEvent>,
+    mut particles: ResMut<ParticlePool>,
+    wells: Query<&Collidable, With<GravityWell>>,
+    rockets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<PlayerRocket>>,
+    swallowed: Query<(Entity, &Collidable, &Movable, Option<&Asteroid>), GravitySwallowed>,
+) {
+    let mut rng = thread_rng();
+    for core in wells.iter() {
+        // The rocket is destroyed as usual
+        for (entity, collidable, invulnerable) in rockets.iter() {
+            if invulnerable.is_invulnerable() {
+                continue;
+            }
+            if core.test_collision_with(collidable) {
+                hit_events.send(HitEvent(entity));
+            }
+        }
+        // Bullets and asteroids are swallowed whole (no points, no fragments)
+        for (entity, collidable, movable, asteroid) in swallowed.iter() {
+            if core.test_collision_with(collidable) {
+                commands.entity(entity).despawn_recursive();
+                particles.burst(&mut rng, ParticleBurst {
+                    position: movable.position,
+                    velocity: Vec2::ZERO,
+                    count: 8,
+                    speed: (5.0, 15.0),
+                    lifetime_secs: (0.2, 0.5),
+                    kind: ParticleKind::Dot,
+                    color: if asteroid.is_some() { ThemeColor::Asteroid } else { ThemeColor::Bullet },
+                });
+            }
+        }
+    }
+}
+
+// Spawning
+
+const GRAVITY_WELL_RING_SCALES: [(f32, f32); 2] = [(2.0, 0.3), (4.0, 0.15)]; // (radius scale, alpha)
+const NEBULA_RING_SCALES: [(f32, f32); 3] = [(1.0, 0.35), (0.7, 0.2), (0.4, 0.1)]; // (radius scale, alpha)
+
+pub fn spawn_hazard(commands: &mut Commands, spawn: &HazardSpawn) -> Entity {
+    match *spawn {
+        HazardSpawn::GravityWell { position, strength, core_radius, range } => {
+            let rings = GRAVITY_WELL_RING_SCALES.map(|(scale, alpha)| (core_radius * scale, alpha));
+            commands
+                .spawn((
+                    Hazard,
+                    GravityWell { position, strength, core_radius, range },
+                    Collidable { collider: Collider::circle(position, core_radius) },
+                    hazard_ring_bundle(position.extend(HAZARD_Z), core_radius, 1.0),
+                    Themed(ThemeColor::Hazard),
+                ))
+                .with_children(|child_commands| spawn_hazard_rings(child_commands, &rings))
+                .id()
+        },
+        HazardSpawn::Nebula { position, radius, drag } => {
+            let rings = NEBULA_RING_SCALES.map(|(scale, alpha)| (radius * scale, alpha));
+            commands
+                .spawn((
+                    Hazard,
+                    Nebula { position, radius, drag },
+                    SpatialBundle::from_transform(Transform::from_xyz(position.x, position.y, HAZARD_Z)),
+                ))
+                .with_children(|child_commands| spawn_hazard_rings(child_commands, &rings))
+                .id()
+        },
+    }
+}
+
+fn spawn_hazard_rings(child_commands: &mut ChildBuilder, rings: &[(f32, f32)]) {
+    for &(radius, alpha) in rings {
+        child_commands.spawn((
+            hazard_ring_bundle(Vec3::ZERO, radius, alpha),
+            Themed(ThemeColor::Hazard),
+        ));
+    }
+}
+
+fn hazard_ring_bundle(translation: Vec3, radius: f32, alpha: f32) -> (ShapeBundle, Stroke) {
+    let circle = shapes::Circle { radius, center: Vec2::ZERO };
+    (
+        ShapeBundle {
+            path: GeometryBuilder::build_as(&circle),
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        // NOTE: Colour is applied by the theme
+        Stroke::new(Color::WHITE.with_a(alpha), LINE_WIDTH),
+    )
+}
+
+// Teardown
+
+fn hazard_teardown_system(mut commands: Commands, query: Query<Entity, With<Hazard>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
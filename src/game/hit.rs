@@ -11,14 +11,43 @@ pub struct HitPlugin;
 impl Plugin for HitPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<HitEvent>();
+        app.add_event::<DamageEvent>();
+        app.add_event::<BlastHitEvent>();
     }
 }
 
 #[derive(Event)]
 pub struct HitEvent(pub Entity);
 
+/// Sent alongside a `HitEvent` when the hit does more than a single point of damage.
+/// Only entities which can take more than one hit need to listen for these.
+#[derive(Event)]
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub amount: u32,
+}
+
+/// Sent alongside a `HitEvent` when the hit came from a mine blast, rather than from the player.
+/// Nothing destroyed this way is worth any points.
+#[derive(Event)]
+pub struct BlastHitEvent(pub Entity);
+
 // Helpers
 
 pub fn distinct_hit_events<'a>(events: &'a mut bevy::prelude::EventReader<super::hit::HitEvent>) -> impl Iterator<Item=&'a super::hit::HitEvent> {
     super::util::distinct_by(events.iter(), |e| e.0)
-}
\ No newline at end of file
+}
+
+/// The entities hit by this frame's `BlastHitEvent`s
+pub fn blast_hit_entities(events: &mut bevy::prelude::EventReader<BlastHitEvent>) -> bevy::utils::HashSet<Entity> {
+    events.iter().map(|e| e.0).collect()
+}
+
+/// The total damage done to each entity by this frame's `DamageEvent`s
+pub fn damage_by_entity(events: &mut bevy::prelude::EventReader<DamageEvent>) -> bevy::utils::HashMap<Entity, u32> {
+    let mut damage = bevy::utils::HashMap::new();
+    for event in events.iter() {
+        *damage.entry(event.entity).or_insert(0) += event.amount;
+    }
+    damage
+}
@@ -2,7 +2,11 @@ use bevy::prelude::*;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, DiagnosticsStore};
 
 use crate::AppState;
+use crate::theme::{Theme, ThemeColor, ThemedBackground, ThemedText};
+use super::boss::Mothership;
 use super::manager::GameManager;
+use super::script::ScriptStatus;
+use super::weapon::WeaponRack;
 
 // Plugin
 
@@ -20,6 +24,8 @@ impl Plugin for HeadsUpDisplayPlugin {
             (
                 status_text_update_system,
                 debug_text_update_system,
+                boss_health_bar_update_system,
+                script_error_text_update_system,
             )
             .run_if(in_state(AppState::Game))
         );
@@ -41,7 +47,16 @@ struct StatusText;
 #[derive(Component)]
 struct DebugText;
 
-fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct ScriptErrorText;
+
+#[derive(Component)]
+struct BossHealthBar;
+
+#[derive(Component)]
+struct BossHealthBarFill;
+
+fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
     
     let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
 
@@ -52,26 +67,63 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                 TextStyle {
                     font: font_light.clone(),
                     font_size: 30.0,
-                    color: Color::WHITE,
+                    color: theme.text,
                 },
             ),
             TextSection::from_style(TextStyle {
                 font: font_light.clone(),
                 font_size: 30.0,
-                color: Color::GOLD,
+                color: theme.text_highlight,
             }),
             TextSection::new(
                 " LIVES: ",
                 TextStyle {
                     font: font_light.clone(),
                     font_size: 30.0,
-                    color: Color::WHITE,
+                    color: theme.text,
                 },
             ),
             TextSection::from_style(TextStyle {
                 font: font_light.clone(),
                 font_size: 30.0,
-                color: Color::GOLD,
+                color: theme.text_highlight,
+            }),
+            TextSection::new(
+                " BOMBS: ",
+                TextStyle {
+                    font: font_light.clone(),
+                    font_size: 30.0,
+                    color: theme.text,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: font_light.clone(),
+                font_size: 30.0,
+                color: theme.text_highlight,
+            }),
+            TextSection::new(
+                " WEAPON: ",
+                TextStyle {
+                    font: font_light.clone(),
+                    font_size: 30.0,
+                    color: theme.text,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: font_light.clone(),
+                font_size: 30.0,
+                color: theme.text_highlight,
+            }),
+            // NOTE: Only shown in timed modes
+            TextSection::from_style(TextStyle {
+                font: font_light.clone(),
+                font_size: 30.0,
+                color: theme.text,
+            }),
+            TextSection::from_style(TextStyle {
+                font: font_light.clone(),
+                font_size: 30.0,
+                color: theme.text_highlight,
             }),
         ])
         .with_style(Style {
@@ -85,7 +137,14 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         StatusText,
         HudPart,
-        status_text_bundle
+        status_text_bundle,
+        ThemedText(vec![
+            ThemeColor::Text, ThemeColor::TextHighlight,
+            ThemeColor::Text, ThemeColor::TextHighlight,
+            ThemeColor::Text, ThemeColor::TextHighlight,
+            ThemeColor::Text, ThemeColor::TextHighlight,
+            ThemeColor::Text, ThemeColor::TextHighlight,
+        ]),
     ));
 
     let debug_text_bundle =
@@ -95,7 +154,7 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                 TextStyle {
                     font: font_light.clone(),
                     font_size: 15.0,
-                    color: Color::WHITE,
+                    color: theme.text,
                 },
             ),
         ])
@@ -111,7 +170,97 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
         DebugText,
         HudPart,
         debug_text_bundle,
+        ThemedText(vec![ThemeColor::Text]),
+    ));
+
+    // NOTE: Text is set by `script_error_text_update_system`
+    let script_error_text_bundle =
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_light.clone(),
+                font_size: 15.0,
+                color: theme.alien,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(15.0),
+            left: Val::Px(15.0),
+            max_width: Val::Percent(60.0),
+            ..default()
+        });
+
+    commands.spawn((
+        ScriptErrorText,
+        HudPart,
+        script_error_text_bundle,
+        ThemedText(vec![ThemeColor::Alien]),
     ));
+
+    // Boss health bar (hidden until a boss appears)
+    let boss_health_bar_bundle = NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            top: Val::Px(15.0),
+            left: Val::Percent(30.0),
+            width: Val::Percent(40.0),
+            ..default()
+        },
+        visibility: Visibility::Hidden,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            BossHealthBar,
+            HudPart,
+            boss_health_bar_bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "MOTHERSHIP",
+                    TextStyle {
+                        font: font_light.clone(),
+                        font_size: 20.0,
+                        color: theme.text,
+                    },
+                ),
+                ThemedText(vec![ThemeColor::Text]),
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(8.0),
+                            margin: UiRect::top(Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: theme.text_secondary.with_a(0.3).into(),
+                        ..default()
+                    },
+                    ThemedBackground(ThemeColor::TextSecondary),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        BossHealthBarFill,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: theme.alien.into(),
+                            ..default()
+                        },
+                        ThemedBackground(ThemeColor::Alien),
+                    ));
+                });
+        });
 }
 
 fn destroy_system(mut commands: Commands, query: Query<Entity, With<HudPart>>) {
@@ -124,11 +273,51 @@ fn destroy_system(mut commands: Commands, query: Query<Entity, With<HudPart>>) {
 
 fn status_text_update_system(
     game: Res<GameManager>,
+    weapons: Query<&WeaponRack>,
     mut status_text: Query<&mut Text, With<StatusText>>
 ) {
     if let Some(mut status_text) = status_text.get_single_mut().ok() {
+        // NOTE: Keeps showing the last weapon while the rocket is respawning
+        if let Ok(weapons) = weapons.get_single() {
+            let name = weapons.active().name();
+            if status_text.sections[7].value != name {
+                status_text.sections[7].value = name.to_string();
+            }
+        }
         write_u32(&mut status_text.sections[1].value, game.player_points);
         write_u32(&mut status_text.sections[3].value, game.player_lives_remaining);
+        write_u32(&mut status_text.sections[5].value, game.smart_bombs_remaining);
+        if let Some(remaining) = game.time_remaining_secs() {
+            if status_text.sections[8].value.is_empty() {
+                status_text.sections[8].value = " TIME: ".to_string();
+            }
+            write_u32(&mut status_text.sections[9].value, remaining.ceil() as u32);
+        }
+    }
+}
+
+fn boss_health_bar_update_system(
+    motherships: Query<&Mothership>,
+    mut bar: Query<&mut Visibility, With<BossHealthBar>>,
+    mut fill: Query<&mut Style, With<BossHealthBarFill>>,
+) {
+    let mothership = motherships.get_single().ok();
+    if let Ok(mut visibility) = bar.get_single_mut() {
+        *visibility = if mothership.is_some() { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    if let (Some(mothership), Ok(mut style)) = (mothership, fill.get_single_mut()) {
+        style.width = Val::Percent(mothership.health_fraction * 100.0);
+    }
+}
+
+fn script_error_text_update_system(
+    status: Res<ScriptStatus>,
+    mut error_text: Query<&mut Text, With<ScriptErrorText>>
+) {
+    for mut error_text in error_text.iter_mut() {
+        if status.is_changed() || error_text.is_added() {
+            error_text.sections[0].value = status.error.clone().unwrap_or_default();
+        }
     }
 }
 
@@ -162,4 +351,6 @@ fn write_debug_info(output: &mut String, diag: &DiagnosticsStore, game: &GameMan
     writeln!(output, "asteroids pending spawn: {}", game.scheduled_asteroid_spawns.len()).unwrap();
     writeln!(output, "player state: {:?}", game.player_state).unwrap();
     writeln!(output, "alien state: {:?}", game.alien_state).unwrap();
+    writeln!(output, "wave: {} boss state: {:?}", game.wave, game.boss_state).unwrap();
+    writeln!(output, "mode: {:?}", game.mode()).unwrap();
 }
\ No newline at end of file
@@ -19,14 +19,25 @@ impl Plugin for InvulnerablePlugin {
 
 // Components
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Invulnerable {
-    timer: Timer,
+    /// None if invulnerable until the component is removed
+    timer: Option<Timer>,
 }
 
 impl Invulnerable {
     pub fn new(timer: Timer) -> Self {
-        Self { timer }
+        Self { timer: Some(timer) }
+    }
+
+    /// Invulnerable until the component is removed, without the blinking animation
+    pub fn indefinite() -> Self {
+        Self { timer: None }
+    }
+
+    #[cfg(feature = "dev-tools")]
+    pub fn is_indefinite(&self) -> bool {
+        self.timer.is_none()
     }
 }
 
@@ -36,7 +47,7 @@ pub trait TestInvulnerable {
 
 impl TestInvulnerable for Invulnerable {
     fn is_invulnerable(&self) -> bool {
-        !self.timer.finished()
+        !self.timer.as_ref().is_some_and(Timer::finished)
     }
 }
 
@@ -54,16 +65,16 @@ pub fn update_invulnerability_system(
 ) {
     for (mut invulnerable, stroke) in query.iter_mut() {
         // Tick all potentially-invulnerable entities
-        invulnerable.timer.tick(time.delta());
+        if let Some(timer) = invulnerable.timer.as_mut() {
+            timer.tick(time.delta());
+        }
 
-        // Optional animations
+        // Optional animations (indefinite invulnerability would blink forever, so isn't animated)
         if let Some(mut stroke) = stroke {
-            let new_alpha = if invulnerable.is_invulnerable() {
-                invulnerability_opacity_over_t(invulnerable.timer.elapsed_secs())
-            }
-            else {
-                1.0
-            };    
+            let new_alpha = match &invulnerable.timer {
+                Some(timer) if !timer.finished() => invulnerability_opacity_over_t(timer.elapsed_secs()),
+                _ => 1.0,
+            };
             stroke.color.set_a(new_alpha);
         }
     }
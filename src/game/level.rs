@@ -0,0 +1,856 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadState, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::render::camera::{OrthographicProjection, ScalingMode};
+use bevy::utils::BoxedFuture;
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::asset_paths;
+use super::alien::AlienUfoVariant;
+use super::asteroid::{AsteroidMaterial, AsteroidShapeId, AsteroidSize, AsteroidSpawn};
+use super::hazard::HazardSpawn;
+use super::manager::{GameCleanup, GameManager};
+use super::movable::Drag;
+
+// Level files
+//
+// Handcrafted layouts for challenge mode, loaded from `.level.ron` files.
+//
+// Example:
+// (
+//     name: "Crossfire",
+//     // The smallest area kept on screen, centred on the origin
+//     arena: (width: 240, height: 180),
+//     // Spin is in turns per second, and the angle in radians
+//     asteroids: [
+//         (size: Large, shape: A, position: (-80, 40), velocity: (12, -4), spin: 0.2),
+//         (size: Medium, shape: C, material: Metallic, position: (60, -30), velocity: (-8, 6), angle: 1.5),
+//     ],
+//     ufos: [
+//         (at_secs: 30, variant: Small),
+//     ],
+//     // UFOs steer around asteroids, rather than flying straight into them
+//     ufos_avoid_asteroids: true,
+//     hazards: [
+//         GravityWell(position: (0, 0), strength: 20000, core_radius: 4, range: 120),
+//         Nebula(position: (-75, 45), radius: 25, drag: (linear: 1.0, min_speed: 10)),
+//     ],
+//     // One of Endless, ClearAsteroids, Survive(secs: ...) or Score(points: ...)
+//     win: ClearAsteroids,
+//     par_score: 2000,
+// )
+//
+// Files are validated as they load. Errors are logged with the line and column of the problem. For
+// problems with the layout as a whole, that's where the asteroid or hazard (or win condition) starts.
+
+pub const LEVEL_ARENA_MIN_SIZE: f32 = 100.0;
+pub const LEVEL_ARENA_MAX_SIZE: f32 = 1000.0;
+pub const LEVEL_ASTEROID_MAX_SPEED: f32 = 100.0;
+pub const LEVEL_ASTEROID_MAX_SPIN: f32 = 2.0;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>();
+        app.init_asset_loader::<LevelLoader>();
+        app.add_systems(Startup, challenge_level_load_system);
+        app.add_systems(
+            Update,
+            (
+                challenge_level_update_system,
+                level_arena_system,
+            )
+        );
+        app.add_systems(GameCleanup, level_arena_reset_system);
+        app.add_systems(OnExit(AppState::Editor), level_arena_reset_system);
+    }
+}
+
+// Levels
+
+#[derive(TypeUuid, TypePath, Clone, Debug)]
+#[uuid = "e4a9c2d7-1f3b-4b8e-a6d5-9c0f2e7b3a18"]
+pub struct Level {
+    pub name: String,
+    /// (w, h) of the smallest area kept on screen, centred on the origin
+    pub arena: Vec2,
+    pub asteroids: Vec<LevelAsteroid>,
+    /// UFOs to spawn, and when
+    pub ufos: Vec<LevelUfo>,
+    /// UFOs steer around asteroids, rather than flying straight into them
+    pub ufos_avoid_asteroids: bool,
+    pub hazards: Vec<HazardSpawn>,
+    pub win: WinCondition,
+    /// The score to aim for, shown when the game ends
+    pub par_score: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelAsteroid {
+    pub size: AsteroidSize,
+    pub shape: AsteroidShapeId,
+    pub material: AsteroidMaterial,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Rotation speed (turns/sec)
+    pub spin: f32,
+    /// The direction the shape initially faces (rads)
+    pub angle: f32,
+}
+
+impl LevelAsteroid {
+    pub fn to_spawn(&self) -> AsteroidSpawn {
+        AsteroidSpawn {
+            size: self.size,
+            material: self.material,
+            shape: self.shape,
+            position: self.position,
+            velocity: self.velocity,
+            rotation: self.spin,
+            heading_angle: self.angle,
+            invulnerable: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LevelUfo {
+    /// Seconds after the start of the level
+    pub at_secs: f32,
+    pub variant: AlienUfoVariant,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Serialize)]
+pub enum WinCondition {
+    /// Only ends once the player runs out of lives
+    Endless,
+    /// Destroy every asteroid (including the pieces they break into)
+    ClearAsteroids,
+    /// Stay alive for this long
+    Survive { secs: f32 },
+    /// Reach this score
+    Score { points: u32 },
+}
+
+impl Level {
+    /// The layout for modes which don't use a level file. Asteroids are placed at random, and hazards
+    /// only appear in level files.
+    pub fn classic() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            arena: Vec2::splat(crate::FIXED_WIDTH_HEIGHT),
+            asteroids: Vec::new(),
+            ufos: Vec::new(),
+            ufos_avoid_asteroids: false,
+            hazards: Vec::new(),
+            win: WinCondition::Endless,
+            par_score: None,
+        }
+    }
+
+    /// A level with nothing in it, for the editor
+    pub fn blank() -> Self {
+        Self {
+            name: "Untitled".to_string(),
+            arena: Vec2::splat(crate::FIXED_WIDTH_HEIGHT),
+            asteroids: Vec::new(),
+            ufos: Vec::new(),
+            ufos_avoid_asteroids: false,
+            hazards: Vec::new(),
+            win: WinCondition::ClearAsteroids,
+            par_score: None,
+        }
+    }
+
+    /// Whether the position is inside the arena
+    pub fn contains(&self, position: Vec2) -> bool {
+        let half = self.arena * 0.5;
+        position.x.abs() <= half.x && position.y.abs() <= half.y
+    }
+
+    /// Checks the layout as a whole (single values are checked as the file is read)
+    pub fn validate(&self) -> Result<(), LevelError> {
+        self.validate_layout(&LevelFileSpans::default())
+    }
+
+    fn validate_layout(&self, spans: &LevelFileSpans) -> Result<(), LevelError> {
+        for (i, asteroid) in self.asteroids.iter().enumerate() {
+            let n = i + 1;
+            let position = spans.asteroids.get(i).copied();
+            if !self.contains(asteroid.position) {
+                let Vec2 { x, y } = asteroid.position;
+                return Err(LevelError::layout(position, format!("asteroid {n} at ({x}, {y}) is outside the arena")));
+            }
+            if asteroid.spin.abs() > LEVEL_ASTEROID_MAX_SPIN {
+                return Err(LevelError::layout(position, format!("asteroid {n} spins faster than {LEVEL_ASTEROID_MAX_SPIN} turns/sec")));
+            }
+        }
+        for (i, hazard) in self.hazards.iter().enumerate() {
+            let n = i + 1;
+            let position = spans.hazards.get(i).copied();
+            if !self.contains(hazard.position()) {
+                let Vec2 { x, y } = hazard.position();
+                return Err(LevelError::layout(position, format!("hazard {n} at ({x}, {y}) is outside the arena")));
+            }
+            if let HazardSpawn::GravityWell { core_radius, range, .. } = *hazard {
+                if range <= core_radius {
+                    return Err(LevelError::layout(position, format!("hazard {n} has a range ({range}) inside its core ({core_radius})")));
+                }
+            }
+        }
+        match self.win {
+            WinCondition::ClearAsteroids if self.asteroids.is_empty() => {
+                Err(LevelError::layout(spans.win, "win is ClearAsteroids, but there are no asteroids".to_string()))
+            },
+            WinCondition::Survive { secs } if Positive::try_from(secs).is_err() => {
+                Err(LevelError::layout(spans.win, format!("win is Survive for {secs} secs, which needs to be a finite number more than 0")))
+            },
+            WinCondition::Score { points: 0 } => {
+                Err(LevelError::layout(spans.win, "win is Score for 0 points, which needs to be more than 0".to_string()))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+// Errors
+
+#[derive(Debug)]
+pub struct LevelError {
+    /// (line, column) of the problem, if it can be pinned down to one place in the file
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl LevelError {
+    fn layout(position: Option<(usize, usize)>, message: String) -> Self {
+        Self { position, message }
+    }
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "line {line}, column {column}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<ron::error::SpannedError> for LevelError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self { position: Some((err.position.line, err.position.col)), message: err.code.to_string() }
+    }
+}
+
+// File format
+
+fn level_file_options() -> ron::Options {
+    // NOTE: Lets `par_score: 2000` stand in for `par_score: Some(2000)`
+    ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME)
+}
+
+/// Reads and validates a level file
+pub fn parse_level(source: &str) -> Result<Level, LevelError> {
+    let file: LevelFile = level_file_options().from_str(source)?;
+    let level = file.into_level();
+    level.validate_layout(&LevelFileSpans::find(source))?;
+    Ok(level)
+}
+
+/// Writes a level file, in the format read by `parse_level`
+pub fn write_level(level: &Level) -> Result<String, LevelError> {
+    level.validate()?;
+    // NOTE: Keeps each asteroid, UFO and hazard on a line of its own
+    let config = ron::ser::PrettyConfig::new().depth_limit(2);
+    level_file_options()
+        .to_string_pretty(&LevelFile::from_level(level), config)
+        .map_err(|err| LevelError::layout(None, err.to_string()))
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct LevelFile {
+    #[serde(default)]
+    name: String,
+    arena: ArenaFile,
+    #[serde(default)]
+    asteroids: Vec<LevelAsteroidFile>,
+    #[serde(default)]
+    ufos: Vec<LevelUfoFile>,
+    #[serde(default)]
+    ufos_avoid_asteroids: bool,
+    #[serde(default)]
+    hazards: Vec<HazardFile>,
+    win: WinCondition,
+    #[serde(default)]
+    par_score: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ArenaFile {
+    width: ArenaSize,
+    height: ArenaSize,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct LevelAsteroidFile {
+    size: AsteroidSize,
+    shape: AsteroidShapeId,
+    #[serde(default)]
+    material: AsteroidMaterial,
+    position: Point,
+    #[serde(default)]
+    velocity: Velocity,
+    #[serde(default)]
+    spin: Finite,
+    #[serde(default)]
+    angle: Finite,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct LevelUfoFile {
+    at_secs: NonNegative,
+    variant: AlienUfoVariant,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+enum HazardFile {
+    GravityWell { position: Point, strength: Positive, core_radius: Positive, range: Positive },
+    Nebula { position: Point, radius: Positive, drag: DragFile },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DragFile {
+    #[serde(default)]
+    constant: NonNegative,
+    #[serde(default)]
+    linear: NonNegative,
+    #[serde(default)]
+    min_speed: NonNegative,
+}
+
+impl LevelFile {
+    fn into_level(self) -> Level {
+        Level {
+            name: self.name,
+            arena: Vec2::new(self.arena.width.0, self.arena.height.0),
+            asteroids: self.asteroids.into_iter()
+                .map(|a| LevelAsteroid {
+                    size: a.size,
+                    shape: a.shape,
+                    material: a.material,
+                    position: a.position.0,
+                    velocity: a.velocity.0,
+                    spin: a.spin.0,
+                    angle: a.angle.0,
+                })
+                .collect(),
+            ufos: self.ufos.into_iter()
+                .map(|u| LevelUfo { at_secs: u.at_secs.0, variant: u.variant })
+                .collect(),
+            ufos_avoid_asteroids: self.ufos_avoid_asteroids,
+            hazards: self.hazards.into_iter()
+                .map(|h| match h {
+                    HazardFile::GravityWell { position, strength, core_radius, range } => HazardSpawn::GravityWell {
+                        position: position.0,
+                        strength: strength.0,
+                        core_radius: core_radius.0,
+                        range: range.0,
+                    },
+                    HazardFile::Nebula { position, radius, drag } => HazardSpawn::Nebula {
+                        position: position.0,
+                        radius: radius.0,
+                        drag: Drag { constant: drag.constant.0, linear: drag.linear.0, min_speed: drag.min_speed.0 },
+                    },
+                })
+                .collect(),
+            win: self.win,
+            par_score: self.par_score,
+        }
+    }
+
+    fn from_level(level: &Level) -> Self {
+        Self {
+            name: level.name.clone(),
+            arena: ArenaFile { width: ArenaSize(level.arena.x), height: ArenaSize(level.arena.y) },
+            asteroids: level.asteroids.iter()
+                .map(|a| LevelAsteroidFile {
+                    size: a.size,
+                    shape: a.shape,
+                    material: a.material,
+                    position: Point(a.position),
+                    velocity: Velocity(a.velocity),
+                    spin: Finite(a.spin),
+                    angle: Finite(a.angle),
+                })
+                .collect(),
+            ufos: level.ufos.iter()
+                .map(|u| LevelUfoFile { at_secs: NonNegative(u.at_secs), variant: u.variant })
+                .collect(),
+            ufos_avoid_asteroids: level.ufos_avoid_asteroids,
+            hazards: level.hazards.iter()
+                .map(|h| match *h {
+                    HazardSpawn::GravityWell { position, strength, core_radius, range } => HazardFile::GravityWell {
+                        position: Point(position),
+                        strength: Positive(strength),
+                        core_radius: Positive(core_radius),
+                        range: Positive(range),
+                    },
+                    HazardSpawn::Nebula { position, radius, drag } => HazardFile::Nebula {
+                        position: Point(position),
+                        radius: Positive(radius),
+                        drag: DragFile {
+                            constant: NonNegative(drag.constant),
+                            linear: NonNegative(drag.linear),
+                            min_speed: NonNegative(drag.min_speed),
+                        },
+                    },
+                })
+                .collect(),
+            win: level.win,
+            par_score: level.par_score,
+        }
+    }
+}
+
+/// Where each asteroid, hazard and the win condition start in a level file, as (line, column)
+#[derive(Default)]
+struct LevelFileSpans {
+    asteroids: Vec<(usize, usize)>,
+    hazards: Vec<(usize, usize)>,
+    win: Option<(usize, usize)>,
+}
+
+impl LevelFileSpans {
+    /// NOTE: Only used on files which have already been read, so doesn't need to cope with bad syntax
+    fn find(source: &str) -> Self {
+        let mut spans = Self::default();
+        let mut chars = Vec::new();
+        let (mut line, mut column) = (1, 1);
+        for c in source.chars() {
+            chars.push((c, (line, column)));
+            if c == '\n' {
+                (line, column) = (line + 1, 1);
+            } else {
+                column += 1;
+            }
+        }
+        let next = |i: usize| chars.get(i + 1).map(|&(c, _)| c);
+
+        let mut depth = 0;
+        // The top level field being read, and the identifier just read (which may be the next field)
+        let mut field = String::new();
+        let mut word = String::new();
+        // Set where a list item (or the win condition) is about to start
+        let mut expect_start = false;
+        let mut i = 0;
+        while i < chars.len() {
+            let (c, position) = chars[i];
+            i += 1;
+            // Comments
+            if c == '/' && next(i - 1) == Some('/') {
+                while i < chars.len() && chars[i].0 != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            if c == '/' && next(i - 1) == Some('*') {
+                i += 1;
+                while i < chars.len() && !(chars[i].0 == '*' && next(i) == Some('/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            if c.is_whitespace() {
+                continue;
+            }
+            if expect_start && c != ',' && c != ']' {
+                expect_start = false;
+                match (depth, field.as_str()) {
+                    (2, "asteroids") => spans.asteroids.push(position),
+                    (2, "hazards") => spans.hazards.push(position),
+                    (1, "win") => spans.win = Some(position),
+                    _ => {},
+                }
+            }
+            match c {
+                '"' => {
+                    // Skip strings (which may contain brackets)
+                    while i < chars.len() && chars[i].0 != '"' {
+                        i += if chars[i].0 == '\\' { 2 } else { 1 };
+                    }
+                    i += 1;
+                },
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    expect_start = c == '[' && depth == 2;
+                },
+                ')' | ']' | '}' => {
+                    depth -= 1;
+                    expect_start = false;
+                },
+                ',' => {
+                    word.clear();
+                    expect_start = depth == 2;
+                },
+                ':' if depth == 1 => {
+                    field = std::mem::take(&mut word);
+                    expect_start = field == "win";
+                },
+                c if depth == 1 && (c.is_alphanumeric() || c == '_') => word.push(c),
+                _ => word.clear(),
+            }
+        }
+        spans
+    }
+}
+
+// Checked values
+// NOTE: Checked as they are read, so any error has the position of the bad value
+
+/// (x, y)
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(try_from = "(f32, f32)", into = "(f32, f32)")]
+struct Point(Vec2);
+
+impl From<Point> for (f32, f32) {
+    fn from(point: Point) -> Self {
+        point.0.into()
+    }
+}
+
+impl TryFrom<(f32, f32)> for Point {
+    type Error = String;
+    fn try_from((x, y): (f32, f32)) -> Result<Self, Self::Error> {
+        let point = Vec2::new(x, y);
+        match point.is_finite() {
+            true => Ok(Self(point)),
+            false => Err(format!("({x}, {y}) is not a valid position")),
+        }
+    }
+}
+
+/// (x, y), no faster than `LEVEL_ASTEROID_MAX_SPEED`
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(try_from = "(f32, f32)", into = "(f32, f32)")]
+struct Velocity(Vec2);
+
+impl From<Velocity> for (f32, f32) {
+    fn from(velocity: Velocity) -> Self {
+        velocity.0.into()
+    }
+}
+
+impl TryFrom<(f32, f32)> for Velocity {
+    type Error = String;
+    fn try_from((x, y): (f32, f32)) -> Result<Self, Self::Error> {
+        let velocity = Vec2::new(x, y);
+        match velocity.is_finite() && velocity.length() <= LEVEL_ASTEROID_MAX_SPEED {
+            true => Ok(Self(velocity)),
+            false => Err(format!("({x}, {y}) is faster than the maximum speed of {LEVEL_ASTEROID_MAX_SPEED}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(try_from = "f32", into = "f32")]
+struct Finite(f32);
+
+impl From<Finite> for f32 {
+    fn from(value: Finite) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<f32> for Finite {
+    type Error = String;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        match value.is_finite() {
+            true => Ok(Self(value)),
+            false => Err(format!("{value} is not a valid number")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(try_from = "f32", into = "f32")]
+struct Positive(f32);
+
+impl From<Positive> for f32 {
+    fn from(value: Positive) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<f32> for Positive {
+    type Error = String;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        match value.is_finite() && value > 0. {
+            true => Ok(Self(value)),
+            false => Err(format!("{value} needs to be more than 0")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(try_from = "f32", into = "f32")]
+struct NonNegative(f32);
+
+impl From<NonNegative> for f32 {
+    fn from(value: NonNegative) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<f32> for NonNegative {
+    type Error = String;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        match value.is_finite() && value >= 0. {
+            true => Ok(Self(value)),
+            false => Err(format!("{value} can't be less than 0")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(try_from = "f32", into = "f32")]
+struct ArenaSize(f32);
+
+impl From<ArenaSize> for f32 {
+    fn from(value: ArenaSize) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<f32> for ArenaSize {
+    type Error = String;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        match (LEVEL_ARENA_MIN_SIZE..=LEVEL_ARENA_MAX_SIZE).contains(&value) {
+            true => Ok(Self(value)),
+            false => Err(format!("arena size {value} needs to be between {LEVEL_ARENA_MIN_SIZE} and {LEVEL_ARENA_MAX_SIZE}")),
+        }
+    }
+}
+
+// Loading
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = parse_level(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// The level played in challenge mode
+#[derive(Resource)]
+pub struct ChallengeLevel {
+    handle: Handle<Level>,
+    /// NOTE: Only available once the file has loaded (and replaced by the level editor when it saves)
+    pub level: Option<Level>,
+    /// Set if the file failed to load (the error is logged)
+    pub failed: bool,
+}
+
+fn challenge_level_load_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ChallengeLevel {
+        handle: asset_server.load(asset_paths::LEVEL_CHALLENGE),
+        level: None,
+        failed: false,
+    });
+}
+
+fn challenge_level_update_system(
+    asset_server: Res<AssetServer>,
+    levels: Res<Assets<Level>>,
+    mut challenge: ResMut<ChallengeLevel>,
+) {
+    let failed = asset_server.get_load_state(&challenge.handle) == LoadState::Failed;
+    if challenge.failed != failed {
+        challenge.failed = failed;
+    }
+    if challenge.level.is_none() {
+        if let Some(level) = levels.get(&challenge.handle) {
+            challenge.level = Some(level.clone());
+        }
+    }
+}
+
+// Arena
+
+fn level_arena_system(
+    game: Option<Res<GameManager>>,
+    mut projections: Query<&mut OrthographicProjection>,
+) {
+    let Some(game) = game else { return };
+    if !game.is_added() {
+        return;
+    }
+    set_arena_size(&mut projections, game.level().arena);
+}
+
+fn level_arena_reset_system(mut projections: Query<&mut OrthographicProjection>) {
+    set_arena_size(&mut projections, Vec2::splat(crate::FIXED_WIDTH_HEIGHT));
+}
+
+/// Keeps an area of (w, h) on screen
+pub fn set_arena_size(projections: &mut Query<&mut OrthographicProjection>, arena: Vec2) {
+    for mut projection in projections.iter_mut() {
+        projection.scaling_mode = ScalingMode::AutoMin { min_width: arena.x, min_height: arena.y };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = r#"// Test level
+(
+    name: "Test",
+    arena: (width: 240, height: 180),
+    asteroids: [
+        (size: Large, shape: A, position: (-80, 40), velocity: (12, -4), spin: 0.2),
+        (size: Medium, shape: C, material: Metallic, position: (60, -30), angle: 1.5),
+    ],
+    ufos: [ (at_secs: 30, variant: Small) ],
+    ufos_avoid_asteroids: true,
+    hazards: [
+        GravityWell(position: (0, 0), strength: 20000, core_radius: 4, range: 120),
+        Nebula(position: (-75, 45), radius: 25, drag: (linear: 1.0, min_speed: 10)),
+    ],
+    win: ClearAsteroids,
+    par_score: 2000,
+)
+"#;
+
+    fn error_position(source: &str) -> Option<(usize, usize)> {
+        parse_level(source).expect_err("level should be rejected").position
+    }
+
+    #[test]
+    fn parses_level() {
+        let level = parse_level(LEVEL).unwrap();
+        assert_eq!(level.name, "Test");
+        assert_eq!(level.arena, Vec2::new(240., 180.));
+        assert_eq!(level.asteroids.len(), 2);
+        assert_eq!(level.asteroids[1].material, AsteroidMaterial::Metallic);
+        assert_eq!(level.asteroids[1].velocity, Vec2::ZERO);
+        assert_eq!(level.asteroids[1].angle, 1.5);
+        assert_eq!(level.ufos.len(), 1);
+        assert!(level.ufos_avoid_asteroids);
+        assert_eq!(level.hazards.len(), 2);
+        assert_eq!(level.win, WinCondition::ClearAsteroids);
+        assert_eq!(level.par_score, Some(2000));
+    }
+
+    #[test]
+    fn round_trips_level() {
+        let level = parse_level(LEVEL).unwrap();
+        let written = write_level(&level).unwrap();
+        let reread = parse_level(&written).unwrap();
+        assert_eq!(write_level(&reread).unwrap(), written);
+        assert_eq!(reread.asteroids[0].position, level.asteroids[0].position);
+        assert_eq!(reread.asteroids[0].spin, level.asteroids[0].spin);
+        assert_eq!(reread.hazards[1].position(), level.hazards[1].position());
+        assert_eq!(reread.par_score, level.par_score);
+    }
+
+    #[test]
+    fn round_trips_blank_level() {
+        let mut level = Level::blank();
+        level.win = WinCondition::Survive { secs: 60. };
+        let reread = parse_level(&write_level(&level).unwrap()).unwrap();
+        assert_eq!(reread.win, level.win);
+        assert_eq!(reread.arena, level.arena);
+        assert!(reread.asteroids.is_empty());
+    }
+
+    #[test]
+    fn reports_bad_value_position() {
+        // NOTE: Values are reported just after their closing bracket
+        let source = LEVEL.replace("velocity: (12, -4)", "velocity: (120, -4)");
+        assert_eq!(error_position(&source), Some((6, 73)));
+    }
+
+    #[test]
+    fn reports_unknown_field_position() {
+        // NOTE: Field names are reported at the following colon
+        let source = LEVEL.replace("spin: 0.2", "spinn: 0.2");
+        assert_eq!(error_position(&source), Some((6, 79)));
+    }
+
+    #[test]
+    fn reports_asteroid_outside_arena() {
+        let source = LEVEL.replace("position: (60, -30)", "position: (60, -300)");
+        assert_eq!(error_position(&source), Some((7, 9)));
+    }
+
+    #[test]
+    fn reports_hazard_layout() {
+        let source = LEVEL.replace("core_radius: 4, range: 120", "core_radius: 4, range: 3");
+        assert_eq!(error_position(&source), Some((12, 9)));
+    }
+
+    #[test]
+    fn reports_win_condition() {
+        let source = LEVEL.replace("win: ClearAsteroids", "win: Score(points: 0)");
+        assert_eq!(error_position(&source), Some((15, 10)));
+    }
+
+    #[test]
+    fn reports_layout_past_comments_and_strings() {
+        let source = LEVEL
+            .replace("\"Test\"", "\"Test (with [brackets]\\\")\"")
+            .replace("    asteroids: [\n", "    asteroids: [ /* first, ( */\n        // second, [\n")
+            .replace("position: (60, -30)", "position: (60, -300)");
+        assert_eq!(error_position(&source), Some((8, 9)));
+    }
+
+    #[test]
+    fn rejects_non_finite_numbers() {
+        for (from, to) in [("spin: 0.2", "spin: NaN"), ("angle: 1.5", "angle: inf"), ("(0, 0)", "(0, NaN)")] {
+            let source = LEVEL.replace(from, to);
+            assert!(parse_level(&source).is_err(), "{to} should be rejected");
+        }
+        let source = LEVEL.replace("win: ClearAsteroids", "win: Survive(secs: NaN)");
+        assert_eq!(error_position(&source), Some((15, 10)));
+        let source = LEVEL.replace("win: ClearAsteroids", "win: Survive(secs: inf)");
+        assert_eq!(error_position(&source), Some((15, 10)));
+    }
+
+    #[test]
+    fn parses_challenge_level() {
+        parse_level(include_str!("../../assets/levels/challenge.level.ron")).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_layout_when_writing() {
+        let mut level = parse_level(LEVEL).unwrap();
+        level.asteroids[0].position = Vec2::new(1000., 0.);
+        assert!(write_level(&level).is_err());
+    }
+}
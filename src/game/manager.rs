@@ -1,13 +1,22 @@
 use bevy::prelude::*;
 use bevy::ecs::schedule::ScheduleLabel;
 use bevy::render::render_resource::encase::rts_array::Length;
-use rand::thread_rng;
+use rand::{SeedableRng, thread_rng};
+use rand::rngs::StdRng;
 use crate::AppState;
 use super::{FrameStage};
 use super::assets::GameAssets;
-use super::alien::{AlienSpawn, AlienUfoDestroyedEvent, spawn_alien_ufo};
+use super::mode::GameMode;
+use super::mine::MineDestroyedEvent;
+use super::missile::MissileDestroyedEvent;
+use super::boss::{BossDestroyedEvent, BossSegmentDestroyedEvent, BossSegmentKind, random_mothership_spawn, spawn_mothership};
+use super::alien::{AlienSpawn, AlienUfoVariant, AlienUfoDestroyedBy, AlienUfoDestroyedEvent, spawn_alien_ufo};
 use super::player::{PlayerRocketDestroyedEvent, RocketSpawn, spawn_player_rocket};
-use super::asteroid::{Asteroid, AsteroidDestroyedEvent, AsteroidSize, AsteroidSpawn, AsteroidShapeId, spawn_asteroid};
+use super::weapon::Weapon;
+use super::invulnerable::Invulnerable;
+use super::hazard::spawn_hazard;
+use super::level::{Level, LevelAsteroid, LevelUfo, WinCondition};
+use super::asteroid::{Asteroid, AsteroidDestroyedEvent, AsteroidMaterial, AsteroidMaterialChances, AsteroidSize, AsteroidSpawn, AsteroidShapeId, spawn_asteroid};
 use super::util::*;
 
 /// Register systems with this schedule to to clean up game components at the right time.
@@ -32,8 +41,11 @@ impl Plugin for GameManagerPlugin {
                     
                 game_events_system,
 
+                game_boss_events_system,
+
                 game_update_system
-                    .after(game_events_system),
+                    .after(game_events_system)
+                    .after(game_boss_events_system),
 
                 game_keyboard_system
             )
@@ -46,21 +58,44 @@ impl Plugin for GameManagerPlugin {
 const ALIEN_SPAWN_MIN_SECS: f32 = 25.0;
 const ALIEN_SPAWN_MAX_SECS: f32 = 60.0;
 
-pub fn game_create(commands: &mut Commands) {
+pub fn game_create(commands: &mut Commands, mode: GameMode, level: Level) {
     let mut rng = thread_rng();
     let alien_spawn_secs = ALIEN_SPAWN_MIN_SECS + rng.random_f32() * (ALIEN_SPAWN_MAX_SECS - ALIEN_SPAWN_MIN_SECS);
     let game_init = GameInit {
-        asteroid_count: 8,
-        player_lives: 3,
+        mode,
+        smart_bombs: 2,
         alien_spawn_secs,
+        level,
+        asteroid_materials: default_asteroid_materials(),
     };
+    for hazard in game_init.level.hazards.iter() {
+        spawn_hazard(commands, hazard);
+    }
     commands.insert_resource(GameManager::new(game_init));
+    commands.insert_resource(GameRng(StdRng::from_entropy()));
+}
+
+fn default_asteroid_materials() -> Vec<AsteroidMaterialChances> {
+    vec![
+        // Wave 1: mostly rock, with the odd block of ice
+        AsteroidMaterialChances { explosive: 0.0, metallic: 0.0, ice: 0.1 },
+        // Wave 2
+        AsteroidMaterialChances { explosive: 0.05, metallic: 0.05, ice: 0.1 },
+        // Wave 3 onwards
+        AsteroidMaterialChances { explosive: 0.1, metallic: 0.1, ice: 0.15 },
+    ]
 }
 
 fn game_teardown_system(mut commands: Commands) {
     commands.remove_resource::<GameManager>();
+    commands.remove_resource::<GameRng>();
 }
 
+/// Drives what the game spawns, and where (cosmetic effects use `thread_rng`)
+/// NOTE: Can be re-seeded to replay the same spawns
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
 // World boundary information
 
 #[derive(Resource, Default)]
@@ -71,28 +106,58 @@ pub struct WorldBoundaries {
     pub bottom: f32,
 }
 
-fn world_boundaries_update_system(
+impl WorldBoundaries {
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.right - self.left, self.top - self.bottom)
+    }
+
+    /// The shortest vector from `from` to `to`, taking the wrap-around edges of the world into account
+    pub fn torus_delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let size = self.size();
+        let mut delta = to - from;
+        if size.x > 0. {
+            delta.x -= size.x * (delta.x / size.x).round();
+        }
+        if size.y > 0. {
+            delta.y -= size.y * (delta.y / size.y).round();
+        }
+        delta
+    }
+}
+
+pub fn world_boundaries_update_system(
     mut world_boundaries: ResMut<WorldBoundaries>,
     projection: Query<&bevy::render::camera::OrthographicProjection>
 ) {
+    // NOTE: Remove the projection scale so camera zoom effects don't change the playing area
     let projection = projection.get_single().unwrap();
-    world_boundaries.left = projection.area.min.x;
-    world_boundaries.bottom = projection.area.min.y;
-    world_boundaries.right = projection.area.max.x;
-    world_boundaries.top = projection.area.max.y;
+    let area_min = projection.area.min / projection.scale;
+    let area_max = projection.area.max / projection.scale;
+    world_boundaries.left = area_min.x;
+    world_boundaries.bottom = area_min.y;
+    world_boundaries.right = area_max.x;
+    world_boundaries.top = area_max.y;
 }
 
 // Game Controller
 
 static GAME_PLAYER_RESPAWN_TIME_SECS: f32 = 1.5;
 static GAME_ASTEROID_SPAWN_TIME_SECS: f32 = 5.0;
+static GAME_PLAYER_SPAWN_CLEARANCE: f32 = 30.0;
+static GAME_WAVE_TIME_SECS: f32 = 45.0;
+/// Every n-th wave is a boss wave
+static GAME_BOSS_WAVE_INTERVAL: u32 = 3;
 
 #[derive(Clone)]
 pub struct GameInit {
-    /// The number of asteroids the game will try to maintain on screen
-    pub asteroid_count: u32,
-    pub player_lives: u32,
+    /// The ruleset (lives, scoring, asteroid density and end conditions)
+    pub mode: GameMode,
+    pub smart_bombs: u32,
     pub alien_spawn_secs: f32,
+    /// The arena, hazards and starting layout (see `Level::classic` for the random layout)
+    pub level: Level,
+    /// Chance of asteroids spawning as each material, per wave (the last entry is used for all later waves)
+    pub asteroid_materials: Vec<AsteroidMaterialChances>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -103,17 +168,27 @@ pub enum PlayerState {
     Destroyed,
 }
 
+#[derive(PartialEq, Eq, Debug)]
+pub enum BossState {
+    /// Normal wave, no boss on screen
+    None,
+    Spawning,
+    Active,
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum AlienState {
     Spawning,
     Ready,
+    /// UFOs only spawn when requested (see `GameManager::request_alien_ufo`)
+    OnRequest,
 }
 
 enum AsteroidSpawnInstruction {
     Anywhere,
     OffScreen,
     FromDestroyedAsteroid(AsteroidDestroyedEvent),
-    AtPosition(Vec2),
+    FromLevel(LevelAsteroid),
 }
 
 pub struct ScheduledAsteroidSpawn {
@@ -125,32 +200,60 @@ pub struct ScheduledAsteroidSpawn {
 pub struct GameManager {
     pub player_lives_remaining: u32,
     pub player_points: u32,
+    pub smart_bombs_remaining: u32,
+    /// The weapon the player last selected, which each new rocket starts with
+    pub player_weapon: Weapon,
     pub debug_asteroid_count_on_screen: u32,
     pub scheduled_asteroid_spawns: Vec<ScheduledAsteroidSpawn>,
     pub player_state: PlayerState,
     pub alien_state: AlienState,
+    pub wave: u32,
+    pub boss_state: BossState,
+    /// Seconds since the game began
+    pub elapsed_secs: f32,
+    /// Scales the speed of newly spawned asteroids
+    pub asteroid_speed: f32,
+    /// UFOs from the level which are still to spawn
+    scheduled_level_ufos: Vec<LevelUfo>,
+    /// Set once any asteroids have been on screen (see `WinCondition::ClearAsteroids`)
+    asteroids_seen: bool,
     player_spawn_timer: Timer,
     alien_spawn_timer: Timer,
+    wave_timer: Timer,
     init: GameInit,
 }
 
 impl GameManager {
     pub fn new(init: GameInit) -> Self {
-        let asteroid_count = init.asteroid_count;
+        let asteroid_count = init.mode.asteroid_count(1);
         let mut game = Self {
-            player_lives_remaining: init.player_lives,
+            player_lives_remaining: init.mode.player_lives(),
             player_points: 0,
+            smart_bombs_remaining: init.smart_bombs,
+            player_weapon: Weapon::SingleShot,
             player_state: PlayerState::FirstSpawn,
             alien_state: AlienState::Spawning,
+            wave: 1,
+            boss_state: BossState::None,
+            elapsed_secs: 0.0,
+            asteroid_speed: 1.0,
+            scheduled_level_ufos: init.level.ufos.clone(),
+            asteroids_seen: false,
             player_spawn_timer: Timer::from_seconds(0.0, TimerMode::Once),
             alien_spawn_timer: Timer::from_seconds(0.0, TimerMode::Once),
+            wave_timer: Timer::from_seconds(GAME_WAVE_TIME_SECS, TimerMode::Repeating),
             scheduled_asteroid_spawns: Vec::new(),
             debug_asteroid_count_on_screen: 0,
             init,
         };
         game.schedule_alien_ufo_to_spawn();
-        for _ in 0..asteroid_count {
-            game.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::Anywhere);
+        if game.init.level.asteroids.is_empty() {
+            for _ in 0..asteroid_count {
+                game.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::Anywhere);
+            }
+        }
+        for asteroid in game.init.level.asteroids.clone() {
+            game.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::FromLevel(asteroid));
         }
         game
     }
@@ -169,24 +272,112 @@ impl GameManager {
         }
     }
 
+    pub fn mode(&self) -> GameMode {
+        self.init.mode
+    }
+
+    /// Seconds left on the clock, for timed modes
+    pub fn time_remaining_secs(&self) -> Option<f32> {
+        self.init.mode.time_limit_secs().map(|limit| (limit - self.elapsed_secs).max(0.))
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.init.level
+    }
+
+    /// Whether the level's win condition has been met
+    pub fn is_level_cleared(&self) -> bool {
+        match self.init.level.win {
+            WinCondition::Endless => false,
+            WinCondition::ClearAsteroids => {
+                self.asteroids_seen &&
+                self.debug_asteroid_count_on_screen == 0 &&
+                self.scheduled_asteroid_spawns.is_empty()
+            },
+            WinCondition::Survive { secs } => self.elapsed_secs >= secs,
+            WinCondition::Score { points } => self.player_points >= points,
+        }
+    }
+
+    fn is_game_over(&self) -> bool {
+        self.init.mode.is_over(self.player_state == PlayerState::Destroyed, self.elapsed_secs) || self.is_level_cleared()
+    }
+
+    fn add_points(&mut self, points: u32) {
+        self.player_points += points;
+    }
+
     fn on_asteroid_destroyed(&mut self, event: AsteroidDestroyedEvent) {
-        self.player_points += get_points_for_asteroid(event.size);
+        if event.by_player {
+            self.add_points(get_points_for_asteroid(event.size, event.material));
+        }
         // Break apart large asteroids
         if event.size == AsteroidSize::Medium || event.size == AsteroidSize::Large {
             self.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::FromDestroyedAsteroid(event));
         }
     }
 
-    fn on_alien_ufo_destroyed(&mut self) {
-        self.player_points += get_points_for_alien_ufo();
+    fn on_alien_ufo_destroyed(&mut self, variant: AlienUfoVariant, destroyed_by: AlienUfoDestroyedBy) {
+        // No points if the UFO flew into an asteroid on its own (or its own mine)
+        if !matches!(destroyed_by, AlienUfoDestroyedBy::Asteroid | AlienUfoDestroyedBy::Blast) {
+            self.add_points(get_points_for_alien_ufo(variant));
+        }
         self.schedule_alien_ufo_to_spawn();
     }
 
+    fn on_missile_destroyed(&mut self) {
+        self.add_points(get_points_for_missile());
+    }
+
+    fn on_mine_destroyed(&mut self) {
+        self.add_points(get_points_for_mine());
+    }
+
+    /// Returns true if the player had a smart bomb left to use
+    pub fn try_use_smart_bomb(&mut self) -> bool {
+        if self.smart_bombs_remaining == 0 {
+            return false;
+        }
+        self.smart_bombs_remaining -= 1;
+        true
+    }
+
+    fn on_boss_segment_destroyed(&mut self, kind: BossSegmentKind) {
+        self.add_points(get_points_for_boss_segment(kind));
+    }
+
+    fn on_boss_destroyed(&mut self) {
+        self.add_points(get_points_for_boss());
+        self.boss_state = BossState::None;
+        self.next_wave();
+    }
+
+    /// Skips straight to the given wave (which may be a boss wave)
+    #[cfg(feature = "dev-tools")]
+    pub fn jump_to_wave(&mut self, wave: u32) {
+        self.wave = wave.max(1) - 1;
+        self.next_wave();
+    }
+
+    fn next_wave(&mut self) {
+        self.wave += 1;
+        self.wave_timer.reset();
+        if self.wave.is_multiple_of(GAME_BOSS_WAVE_INTERVAL) {
+            self.boss_state = BossState::Spawning;
+        }
+    }
+
     fn on_asteroid_count_update(&mut self, current_asteroid_count: u32) {
         self.debug_asteroid_count_on_screen = current_asteroid_count;
+        self.asteroids_seen |= current_asteroid_count > 0;
+        // Asteroids aren't refilled while the boss is around
+        if self.boss_state != BossState::None {
+            return;
+        }
         // Schedule asteroids to "refill" the configured number of asteroids
         let pending_asteroid_count = self.scheduled_asteroid_spawns.length() as i32;
-        let missing_asteroid_count = self.init.asteroid_count as i32 - current_asteroid_count as i32 - pending_asteroid_count;
+        let target_asteroid_count = self.init.mode.asteroid_count(self.wave);
+        let missing_asteroid_count = target_asteroid_count as i32 - current_asteroid_count as i32 - pending_asteroid_count;
         for _ in 0..missing_asteroid_count {
             self.schedule_asteroid_to_spawn(GAME_ASTEROID_SPAWN_TIME_SECS, AsteroidSpawnInstruction::OffScreen);
         }
@@ -199,14 +390,42 @@ impl GameManager {
         });
     }
 
+    /// Schedules a wave of asteroids to fly in from off-screen
+    pub fn schedule_asteroid_swarm(&mut self, count: u32) {
+        for _ in 0..count {
+            self.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::OffScreen);
+        }
+    }
+
+    /// Spawns a UFO straight away
+    pub fn request_alien_ufo(&mut self) {
+        self.alien_state = AlienState::Spawning;
+        self.alien_spawn_timer = Timer::from_seconds(0.0, TimerMode::Once);
+    }
+
+    /// UFOs steer around asteroids, rather than flying straight into them (see `Level::ufos_avoid_asteroids`)
+    pub fn alien_avoids_asteroids(&self) -> bool {
+        self.init.level.ufos_avoid_asteroids
+    }
+
     fn schedule_alien_ufo_to_spawn(&mut self) {
+        if !self.init.mode.spawns_aliens() {
+            self.alien_state = AlienState::OnRequest;
+            return;
+        }
         self.alien_state = AlienState::Spawning;
         self.alien_spawn_timer = Timer::from_seconds(self.init.alien_spawn_secs, TimerMode::Once);
     }
 
     fn tick(&mut self, delta: std::time::Duration) {
+        self.elapsed_secs += delta.as_secs_f32();
         self.player_spawn_timer.tick(delta);
         self.alien_spawn_timer.tick(delta);
+        // The wave only advances once the boss has been defeated
+        let has_waves = self.init.mode.has_waves();
+        if has_waves && self.boss_state == BossState::None && self.wave_timer.tick(delta).just_finished() {
+            self.next_wave();
+        }
         for s in self.scheduled_asteroid_spawns.iter_mut() {
             s.spawn_timer.tick(delta);
         }
@@ -220,29 +439,94 @@ impl GameManager {
         return should_spawn;
     }
 
+    /// The middle of the screen, or just below it if a hazard is in the way
+    fn player_spawn_position(&self) -> Vec2 {
+        let mut position = Vec2::ZERO;
+        while self.init.level.hazards.iter().any(|h| h.is_deadly_at(position, GAME_PLAYER_SPAWN_CLEARANCE)) {
+            position.y -= GAME_PLAYER_SPAWN_CLEARANCE;
+        }
+        position
+    }
+
     fn on_rocket_spawned(&mut self) {
         self.player_state = PlayerState::Ready;
     }
 
+    /// Level UFOs which are due to spawn
+    fn take_due_level_ufos(&mut self) -> Vec<LevelUfo> {
+        let elapsed_secs = self.elapsed_secs;
+        self.scheduled_level_ufos.extract_if(.., |u| u.at_secs <= elapsed_secs).collect()
+    }
+
     fn should_spawn_alien_ufo(&self) -> bool {
         return self.alien_state == AlienState::Spawning && self.alien_spawn_timer.finished();
     }
 
     fn on_alien_ufo_spawned(&mut self) {
-        self.alien_state = AlienState::Ready;
+        self.alien_state = match self.init.mode.spawns_aliens() {
+            true => AlienState::Ready,
+            false => AlienState::OnRequest,
+        };
+    }
+
+    /// Chance of new asteroids spawning as each material, for the current wave
+    pub fn asteroid_material_chances(&self) -> AsteroidMaterialChances {
+        let index = (self.wave as usize).saturating_sub(1);
+        self.init.asteroid_materials
+            .get(index)
+            .or(self.init.asteroid_materials.last())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn should_spawn_boss(&self) -> bool {
+        self.boss_state == BossState::Spawning
+    }
+
+    fn on_boss_spawned(&mut self) {
+        self.boss_state = BossState::Active;
     }
 }
 
-fn get_points_for_asteroid(size: AsteroidSize) -> u32 {
-    match size {
+fn get_points_for_asteroid(size: AsteroidSize, material: AsteroidMaterial) -> u32 {
+    let points = match size {
         AsteroidSize::Small => 10,
         AsteroidSize::Medium => 7,
         AsteroidSize::Large => 5,
+    };
+    let bonus = match material {
+        AsteroidMaterial::Metallic => 5,
+        AsteroidMaterial::Explosive => 2,
+        AsteroidMaterial::Rock | AsteroidMaterial::Ice => 0,
+    };
+    points + bonus
+}
+
+fn get_points_for_alien_ufo(variant: AlienUfoVariant) -> u32 {
+    match variant {
+        AlienUfoVariant::Large => 15,
+        AlienUfoVariant::Small => 25,
     }
 }
 
-fn get_points_for_alien_ufo() -> u32 {
-    15
+fn get_points_for_missile() -> u32 {
+    3
+}
+
+fn get_points_for_mine() -> u32 {
+    5
+}
+
+fn get_points_for_boss_segment(kind: BossSegmentKind) -> u32 {
+    match kind {
+        BossSegmentKind::Turret => 20,
+        BossSegmentKind::ShieldGenerator => 30,
+        BossSegmentKind::Core => 50,
+    }
+}
+
+fn get_points_for_boss() -> u32 {
+    200
 }
 
 // Systems
@@ -252,7 +536,9 @@ fn game_events_system(
     mut game: ResMut<GameManager>,
     mut rocket_destructions: EventReader<PlayerRocketDestroyedEvent>,
     mut asteroid_destructions: EventReader<AsteroidDestroyedEvent>,
-    mut alien_destructions: EventReader<AlienUfoDestroyedEvent>
+    mut alien_destructions: EventReader<AlienUfoDestroyedEvent>,
+    mut missile_destructions: EventReader<MissileDestroyedEvent>,
+    mut mine_destructions: EventReader<MineDestroyedEvent>,
 ) {
     if rocket_destructions.iter().next().is_some() {
         game.on_rocket_destroyed();
@@ -262,8 +548,30 @@ fn game_events_system(
         game.on_asteroid_destroyed(ev.clone());
     }
 
-    for _ in alien_destructions.iter() {
-        game.on_alien_ufo_destroyed();
+    for ev in alien_destructions.iter() {
+        game.on_alien_ufo_destroyed(ev.variant, ev.destroyed_by);
+    }
+
+    for _ in missile_destructions.iter() {
+        game.on_missile_destroyed();
+    }
+
+    for _ in mine_destructions.iter() {
+        game.on_mine_destroyed();
+    }
+}
+
+fn game_boss_events_system(
+    mut game: ResMut<GameManager>,
+    mut boss_segment_destructions: EventReader<BossSegmentDestroyedEvent>,
+    mut boss_destructions: EventReader<BossDestroyedEvent>,
+) {
+    for ev in boss_segment_destructions.iter() {
+        game.on_boss_segment_destroyed(ev.kind);
+    }
+
+    for _ in boss_destructions.iter() {
+        game.on_boss_destroyed();
     }
 }
 
@@ -283,29 +591,55 @@ fn game_effects_system(
     world_boundaries: Res<WorldBoundaries>,
     time: Res<Time>,
     assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let mut rng = thread_rng();
+    let rng = &mut rng.0;
 
     game.tick(time.delta());
     
     if game.should_spawn_player() {
         game.on_rocket_spawned();
-        spawn_player_rocket(&mut commands, &assets.rocket, RocketSpawn::default());
+        let position = game.player_spawn_position();
+        let mode = game.mode();
+        let mut spawn = RocketSpawn { position, max_bullets: mode.player_max_bullets(), weapon: game.player_weapon, ..default() };
+        if !mode.spawn_invulnerability() {
+            spawn.invulnerable = None;
+        }
+        if mode.rocket_indestructible() {
+            spawn.invulnerable = Some(Invulnerable::indefinite());
+        }
+        spawn_player_rocket(&mut commands, &assets.rocket, spawn);
     }
     
     if game.should_spawn_alien_ufo() {
         game.on_alien_ufo_spawned();
-        handle_alien_ufo_spawn(&mut commands, &mut rng, &world_boundaries, &assets);
+        let avoid_asteroids = game.alien_avoids_asteroids();
+        handle_alien_ufo_spawn(&mut commands, rng, &world_boundaries, &assets, avoid_asteroids);
+    }
+
+    for ufo in game.take_due_level_ufos() {
+        let spawn = random_alien_ufo_spawn(rng, &world_boundaries, ufo.variant, game.alien_avoids_asteroids());
+        spawn_alien_ufo(&mut commands, &assets.alien, spawn);
     }
 
-    for spawn in game.scheduled_asteroid_spawns.extract_if(|s| s.spawn_timer.finished()) {
-        handle_asteroid_spawn(&mut commands, &mut rng, &world_boundaries, &assets, spawn);
+    if game.should_spawn_boss() {
+        game.on_boss_spawned();
+        spawn_mothership(&mut commands, &assets.boss, random_mothership_spawn(rng, &world_boundaries));
+    }
+
+    let material_chances = game.asteroid_material_chances();
+    let speed = game.asteroid_speed;
+    for spawn in game.scheduled_asteroid_spawns.extract_if(.., |s| s.spawn_timer.finished()) {
+        handle_asteroid_spawn(&mut commands, rng, &world_boundaries, &assets, &material_chances, speed, spawn);
     }
 
     // Game over?
-    if game.player_state == PlayerState::Destroyed {
+    if game.is_game_over() {
         commands.insert_resource(crate::game_over_screen::GameResults {
+            mode: game.mode(),
             score: game.player_points,
+            cleared: game.is_level_cleared(),
+            par_score: game.level().par_score,
         });
         next_app_state.set(AppState::GameOver);
         return;
@@ -316,70 +650,89 @@ const ALIEN_UFO_SPEED: f32 = 35.0;
 
 fn handle_alien_ufo_spawn(
     commands: &mut Commands,
-    rng: &mut rand::rngs::ThreadRng,
+    rng: &mut impl RngUtil,
     world_boundaries: &WorldBoundaries,
-    assets: &GameAssets
+    assets: &GameAssets,
+    avoid_asteroids: bool,
 ) {
+    let variant = *rng.random_choice(&AlienUfoVariant::VALUES).unwrap();
+    let spawn = random_alien_ufo_spawn(rng, world_boundaries, variant, avoid_asteroids);
+    spawn_alien_ufo(commands, &assets.alien, spawn);
+}
+
+/// A UFO flying in from just off the left or right edge of the screen
+pub fn random_alien_ufo_spawn(
+    rng: &mut impl RngUtil,
+    world_boundaries: &WorldBoundaries,
+    variant: AlienUfoVariant,
+    avoid_asteroids: bool,
+) -> AlienSpawn {
 
     // Pick a position off-screen
     let from_left = rng.random_bool();
     let x = if from_left { world_boundaries.left - 10.0 } else { world_boundaries.right + 10.0 };
     let y = (rng.random_f32() * 2. - 1.) * (world_boundaries.top * 0.8);
     let x_speed = if from_left { ALIEN_UFO_SPEED } else { -ALIEN_UFO_SPEED };
-    
-    spawn_alien_ufo(commands, &assets.alien, AlienSpawn {
+
+    AlienSpawn {
+        variant,
         position: Vec2::new(x, y),
         velocity: Vec2::new(x_speed, 0.),
-    });
+        avoid_asteroids,
+    }
 }
 
 fn handle_asteroid_spawn(
     commands: &mut Commands,
-    rng: &mut rand::rngs::ThreadRng,
+    rng: &mut impl RngUtil,
     world_boundaries: &Res<WorldBoundaries>,
     assets: &Res<GameAssets>,
+    material_chances: &AsteroidMaterialChances,
+    speed: f32,
     sched: ScheduledAsteroidSpawn
 ) {
     match sched.instruction {
         AsteroidSpawnInstruction::Anywhere => {
             // Spawn on-screen asteroids
             let position = random_onscreen_position(rng, world_boundaries);
-            let velocity = random_asteroid_velocity(rng);
+            let velocity = random_asteroid_velocity(rng) * speed;
             let rotation = random_asteroid_rotation(rng);
             let size = random_asteroid_size(rng);
             let shape = random_asteroid_shape(rng);
-            let spawn = AsteroidSpawn { size, shape, position, velocity, rotation, invulnerable: None };
+            let material = material_chances.pick(rng);
+            let spawn = AsteroidSpawn { size, material, shape, position, velocity, rotation, heading_angle: 0., invulnerable: None };
             spawn_asteroid(commands, &assets.asteroid, spawn);
 
         },
         AsteroidSpawnInstruction::OffScreen => {
             // Spawn off-screen asteroids
             let position = random_offscreen_position(rng, world_boundaries, 10.0);
-            let velocity = random_asteroid_velocity(rng);
+            let velocity = random_asteroid_velocity(rng) * speed;
             let rotation = random_asteroid_rotation(rng);
             let size = random_asteroid_size(rng);
             let shape = random_asteroid_shape(rng);
-            let spawn = AsteroidSpawn { size, shape, position, velocity, rotation, invulnerable: None };
+            let material = material_chances.pick(rng);
+            let spawn = AsteroidSpawn { size, material, shape, position, velocity, rotation, heading_angle: 0., invulnerable: None };
             spawn_asteroid(commands, &assets.asteroid, spawn);
         },
         AsteroidSpawnInstruction::FromDestroyedAsteroid(ev) => {
             // Spawn child asteroids
-            let [a, b] = random_chunk_asteroid_state(rng, ev.position, ev.velocity);
+            let chunks = random_chunk_asteroid_state(rng, ev.position, ev.velocity, ev.material.fragment_count());
             let size = match ev.size {
                 AsteroidSize::Small => unreachable!(),
                 AsteroidSize::Medium => AsteroidSize::Small,
                 AsteroidSize::Large => AsteroidSize::Medium,
             };
+            let material = ev.material.fragment_material();
             let invulnerable = Some(Timer::from_seconds(CHUNK_ASTEROID_INVULNERABLE_SECS, TimerMode::Once));
-            spawn_asteroid(commands, &assets.asteroid, AsteroidSpawn { size, position: a.0, velocity: a.1, rotation: a.2, shape: a.3, invulnerable: invulnerable.clone() });
-            spawn_asteroid(commands, &assets.asteroid, AsteroidSpawn { size, position: b.0, velocity: b.1, rotation: b.2, shape: b.3, invulnerable });
+            for (position, velocity, rotation, shape) in chunks {
+                spawn_asteroid(commands, &assets.asteroid, AsteroidSpawn { size, material, position, velocity, rotation, shape, heading_angle: 0., invulnerable: invulnerable.clone() });
+            }
         },
-        AsteroidSpawnInstruction::AtPosition(position) => {
-            let velocity = Vec2::ZERO; // random_asteroid_velocity(rng);
-            let rotation = 0.0;
-            let size = random_asteroid_size(rng);
-            let shape = random_asteroid_shape(rng);
-            let spawn = AsteroidSpawn { size, shape, position, velocity, rotation, invulnerable: None };
+        AsteroidSpawnInstruction::FromLevel(asteroid) => {
+            // Spawn a handcrafted asteroid exactly where the level puts it
+            let mut spawn = asteroid.to_spawn();
+            spawn.velocity *= speed;
             spawn_asteroid(commands, &assets.asteroid, spawn);
         },
     };
@@ -391,36 +744,34 @@ static CHILD_ASTEROID_MAX_ADD_SPEED: f32 = 15.0;
 static CHUNK_ASTEROID_VELOCITY_REDUCTION: f32 = 0.8;
 static CHUNK_ASTEROID_INVULNERABLE_SECS: f32 = 0.5;
 
-pub fn random_chunk_asteroid_state(rng: &mut rand::rngs::ThreadRng, position: Vec2, velocity: Vec2) -> [(Vec2, Vec2, f32, AsteroidShapeId); 2] {
+pub fn random_chunk_asteroid_state(rng: &mut impl RngUtil, position: Vec2, velocity: Vec2, count: usize) -> Vec<(Vec2, Vec2, f32, AsteroidShapeId)> {
 
-    // Generate some random position and velocity for these two asteroids
-    let chunk_direction = rng.random_unit_vec2();
+    // Generate some random position and velocity for these asteroids, spread evenly around a random direction
+    let first_direction = rng.random_unit_vec2();
     let chunk_velocity = CHILD_ASTEROID_MIN_ADD_SPEED + rng.random_f32() * (CHILD_ASTEROID_MAX_ADD_SPEED - CHILD_ASTEROID_MIN_ADD_SPEED);
 
-    let p1 = position + chunk_direction * CHILD_ASTEROID_SPAWN_DISTANCE;
-    let p2 = position + -chunk_direction * CHILD_ASTEROID_SPAWN_DISTANCE;
-
-    let v1 = velocity * CHUNK_ASTEROID_VELOCITY_REDUCTION + chunk_direction * chunk_velocity;
-    let v2 = velocity * CHUNK_ASTEROID_VELOCITY_REDUCTION + -chunk_direction * chunk_velocity;
-    
-    let r1 = random_asteroid_rotation(rng);
-    let r2 = random_asteroid_rotation(rng);
-
-    let s1 = random_asteroid_shape(rng);
-    let s2 = random_asteroid_shape(rng);
-    
-    [(p1, v1, r1, s1), (p2, v2, r2, s2)]
+    (0..count)
+        .map(|i| {
+            let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+            let chunk_direction = Vec2::from_angle(angle).rotate(first_direction);
+            let p = position + chunk_direction * CHILD_ASTEROID_SPAWN_DISTANCE;
+            let v = velocity * CHUNK_ASTEROID_VELOCITY_REDUCTION + chunk_direction * chunk_velocity;
+            let r = random_asteroid_rotation(rng);
+            let s = random_asteroid_shape(rng);
+            (p, v, r, s)
+        })
+        .collect()
 }
 
 const ASTEROID_MAX_SPEED: f32 = 50.0;
 const ASTEROID_MIN_SPEED: f32 = 5.0;
-fn random_asteroid_velocity(rng: &mut rand::rngs::ThreadRng) -> Vec2 {
+pub fn random_asteroid_velocity(rng: &mut impl RngUtil) -> Vec2 {
     ASTEROID_MIN_SPEED + rng.random_unit_vec2() * (ASTEROID_MAX_SPEED - ASTEROID_MIN_SPEED)
 }
 
 const ASTEROID_MAX_SPIN_RATE: f32 = 0.4;
 const ASTEROID_MIN_SPIN_RATE: f32 = 0.05;
-fn random_asteroid_rotation(rng: &mut rand::rngs::ThreadRng) -> f32 {
+pub fn random_asteroid_rotation(rng: &mut impl RngUtil) -> f32 {
     ASTEROID_MIN_SPIN_RATE + rng.random_f32() * (ASTEROID_MAX_SPIN_RATE - ASTEROID_MIN_SPIN_RATE)
 }
 
@@ -437,7 +788,7 @@ fn get_world_boundary_lines(world_boundaries: &WorldBoundaries) -> [Line; 4] {
     ]
 }
 
-fn random_offscreen_position(rng: &mut rand::rngs::ThreadRng, world_boundaries: &WorldBoundaries, add_t: f32) -> Vec2 {
+fn random_offscreen_position(rng: &mut impl RngUtil, world_boundaries: &WorldBoundaries, add_t: f32) -> Vec2 {
     use std::cmp::Ordering::*;
     // TODO: Pick a random position off the screen
     // Project this line until it intersects with one of the edges of the world_boundaries.
@@ -451,15 +802,15 @@ fn random_offscreen_position(rng: &mut rand::rngs::ThreadRng, world_boundaries:
     return ray.point_at_t(t + add_t);
 }
 
-fn random_onscreen_position(rng: &mut rand::rngs::ThreadRng, world_boundaries: &WorldBoundaries) -> Vec2 {
+fn random_onscreen_position(rng: &mut impl RngUtil, world_boundaries: &WorldBoundaries) -> Vec2 {
     rng.random_unit_vec2() * Vec2::new(world_boundaries.right, world_boundaries.top)
 }
 
-fn random_asteroid_size(rng: &mut rand::rngs::ThreadRng) -> AsteroidSize {
+fn random_asteroid_size(rng: &mut impl RngUtil) -> AsteroidSize {
     *rng.random_choice(&AsteroidSize::VALUES).unwrap()
 }
 
-fn random_asteroid_shape(rng: &mut rand::rngs::ThreadRng) -> AsteroidShapeId {
+fn random_asteroid_shape(rng: &mut impl RngUtil) -> AsteroidShapeId {
     *rng.random_choice(&AsteroidShapeId::VALUES).unwrap()
 }
 
@@ -467,23 +818,86 @@ fn random_asteroid_shape(rng: &mut rand::rngs::ThreadRng) -> AsteroidShapeId {
 
 fn game_keyboard_system(
     mut kb: ResMut<Input<KeyCode>>,
-    mut game: ResMut<GameManager>,
     mut next_app_state: ResMut<NextState<AppState>>,
 ) {
-    // Spawn a single asteroid
-    if kb.just_released(KeyCode::O) {
-        game.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::AtPosition(Vec2::new(0., 20.)));
+    // Pause
+    if kb.clear_just_released(KeyCode::Escape) {
+        next_app_state.set(AppState::Pause);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use bevy_prototype_lyon::prelude::*;
+    use crate::camera::CameraEffects;
+    use super::*;
+    use super::super::alien::AlienUfo;
+    use super::super::asteroid::{AsteroidCollidable, AsteroidPlugin};
+    use super::super::collidable::{Collidable, Collider};
+    use super::super::hit::HitPlugin;
+    use super::super::movable::Movable;
+    use super::super::particle::ParticlePool;
+
+    fn game_app() -> App {
+        let mut app = App::new();
+        app.add_state::<AppState>();
+        app.add_plugins((HitPlugin, AsteroidPlugin));
+        app.add_event::<PlayerRocketDestroyedEvent>();
+        app.add_event::<AlienUfoDestroyedEvent>();
+        app.add_event::<MissileDestroyedEvent>();
+        app.add_event::<MineDestroyedEvent>();
+        app.init_resource::<Time>();
+        app.init_resource::<CameraEffects>();
+        app.insert_resource(ParticlePool::with_capacity(256));
+        app.insert_resource(GameManager::new(GameInit {
+            mode: GameMode::Classic,
+            smart_bombs: 0,
+            alien_spawn_secs: 10.0,
+            level: Level::classic(),
+            asteroid_materials: default_asteroid_materials(),
+        }));
+        app.add_systems(Update, game_events_system);
+        app.insert_resource(NextState(Some(AppState::Game)));
+        app
     }
 
-    // Spawn a hundred asteroids
-    if kb.just_released(KeyCode::P) {
-        for _ in 0..100 {
-            game.schedule_asteroid_to_spawn(0.0, AsteroidSpawnInstruction::OffScreen);
+    fn spawn_test_asteroid(app: &mut App, position: Vec2) -> Entity {
+        let circle = shapes::Circle { radius: 5.0, center: Vec2::ZERO };
+        app.world.spawn((
+            Asteroid::new(AsteroidSize::Large, AsteroidMaterial::Rock),
+            Movable::new(position, Vec2::ZERO),
+            GeometryBuilder::build_as(&circle),
+            Collidable { collider: Collider::circle(position, 5.0) },
+        )).id()
+    }
+
+    fn run_frames(app: &mut App) {
+        for _ in 0..3 {
+            app.update();
         }
     }
 
-    // Pause
-    if kb.clear_just_released(KeyCode::Escape) {
-        next_app_state.set(AppState::Pause);
+    #[test]
+    fn asteroid_rammed_by_player_scores_points() {
+        let mut app = game_app();
+        let asteroid = spawn_test_asteroid(&mut app, Vec2::ZERO);
+        app.world.spawn((AsteroidCollidable, Collidable { collider: Collider::circle(Vec2::new(3.0, 0.0), 5.0) }));
+        run_frames(&mut app);
+        assert!(app.world.get_entity(asteroid).is_none());
+        let points = get_points_for_asteroid(AsteroidSize::Large, AsteroidMaterial::Rock);
+        assert_eq!(app.world.resource::<GameManager>().player_points, points);
+    }
+
+    #[test]
+    fn asteroid_hit_by_ufo_scores_nothing() {
+        let mut app = game_app();
+        let asteroid = spawn_test_asteroid(&mut app, Vec2::ZERO);
+        app.world.spawn((
+            AlienUfo::new(AlienUfoVariant::Large),
+            AsteroidCollidable,
+            Collidable { collider: Collider::circle(Vec2::new(3.0, 0.0), 5.0) },
+        ));
+        run_frames(&mut app);
+        assert!(app.world.get_entity(asteroid).is_none());
+        assert_eq!(app.world.resource::<GameManager>().player_points, 0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
+use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::assets::GameAssets;
+use super::bullet::{BulletCollidable, BulletSource};
+use super::collidable::{Collidable, Collider};
+use super::explosion::{ExplosionCollidable, SpawnExplosion, spawn_explosion};
+use super::hit::{BlastHitEvent, HitEvent, blast_hit_entities, distinct_hit_events};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::{GameCleanup, WorldBoundaries};
+use super::movable::{Movable, MovableTorusConstraint};
+use super::particle::{ParticleBurst, ParticleKind, ParticlePool};
+use super::player::PlayerRocket;
+use super::shape::{ShapeCollider, VectorShape};
+use super::util::*;
+
+// Proximity mines
+//
+// Mines drift slowly and arm after a short delay. Once armed, a rocket entering the trigger radius
+// starts a countdown, after which the mine detonates and destroys everything within the blast radius.
+// Shooting a mine detonates it safely, while a mine caught in another's blast goes off straight away.
+// Nothing destroyed by a blast is worth any points (see `BlastHitEvent`). Mines which are left alone
+// fade away after a while, so they don't pile up over a long game.
+
+const MINE_Z: f32 = 10.0;
+const MINE_ARMING_SECS: f32 = 2.0;
+const MINE_COUNTDOWN_SECS: f32 = 1.0;
+const MINE_TRIGGER_RADIUS: f32 = 15.0;
+const MINE_BLAST_RADIUS: f32 = 25.0;
+const MINE_DRIFT_SPEED: f32 = 4.0;
+const MINE_SPIN_RATE: f32 = 0.5; // rads per second
+const MINE_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 1.0;
+const MINE_BLAST_TRAUMA: f32 = 0.5;
+const MINE_LIFETIME_SECS: f32 = 30.0;
+/// Mines fade out over the end of their lifetime
+const MINE_FADE_SECS: f32 = 3.0;
+
+pub struct MinePlugin;
+
+impl Plugin for MinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MineDestroyedEvent>();
+        app.add_systems(
+            Update,
+            (
+                mine_layer_system
+                    .after(FrameStage::Movement),
+
+                mine_trigger_system
+                    .after(FrameStage::Movement),
+
+                mine_expiry_system
+                    .after(FrameStage::Movement),
+
+                mine_detonation_system
+                    .in_set(FrameStage::Collision)
+                    .after(FrameStage::Movement)
+                    .after(mine_trigger_system),
+
+                mine_hit_system
+                    .in_set(FrameStage::CollisionEffect)
+                    .after(FrameStage::Collision),
+
+                mine_blink_system,
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, mine_teardown_system);
+    }
+}
+
+// Events
+
+/// Sent when a mine is destroyed without detonating (e.g. shot by the player)
+#[derive(Event)]
+pub struct MineDestroyedEvent;
+
+// Setup
+
+pub struct MineAssets {
+    mine_collider: ShapeCollider,
+    mine_shape: Path,
+}
+
+pub fn create_mine_assets(mine: &VectorShape) -> MineAssets {
+    MineAssets {
+        mine_collider: mine.collider,
+        mine_shape: Path(mine.path.0.clone()),
+    }
+}
+
+// Teardown
+
+fn mine_teardown_system(mut commands: Commands, query: Query<Entity, With<Mine>>) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+}
+
+// Entity
+
+enum MineState {
+    Arming(Timer),
+    Armed,
+    Triggered(Timer),
+}
+
+#[derive(Component)]
+pub struct Mine {
+    state: MineState,
+    lifetime: Timer,
+}
+
+impl Mine {
+    /// Fades from 1 to 0 over the end of the mine's lifetime (unless it's about to go off)
+    fn fade(&self) -> f32 {
+        match self.state {
+            MineState::Triggered(_) => 1.0,
+            MineState::Arming(_) | MineState::Armed => (self.lifetime.remaining_secs() / MINE_FADE_SECS).min(1.0),
+        }
+    }
+}
+
+/// Periodically drops mines behind the entity
+#[derive(Component)]
+pub struct MineLayer {
+    timer: Timer,
+}
+
+impl MineLayer {
+    pub fn new(interval_secs: f32) -> Self {
+        Self { timer: Timer::from_seconds(interval_secs, TimerMode::Repeating) }
+    }
+}
+
+// Spawning
+
+#[derive(Clone)]
+pub struct MineSpawn {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+const LINE_WIDTH: f32 = 0.2;
+
+pub fn spawn_mine(
+    commands: &mut Commands,
+    assets: &MineAssets,
+    spawn: MineSpawn
+) {
+    let position = spawn.position;
+    let velocity = spawn.velocity;
+
+    // NOTE: Colour is applied by the theme
+    let mine_stroke = Stroke::new(Color::WHITE, LINE_WIDTH);
+    let transform = Transform::from_translation(Vec3::new(position.x, position.y, MINE_Z));
+    let collider = assets.mine_collider.to_collider(position, 1.0);
+    let radius = match assets.mine_collider {
+        ShapeCollider::Circle { radius } => radius,
+        ShapeCollider::Capsule { arm: (x, y), radius } => Vec2::new(x, y).length() + radius,
+    };
+
+    commands.spawn((
+        Mine {
+            state: MineState::Arming(Timer::from_seconds(MINE_ARMING_SECS, TimerMode::Once)),
+            lifetime: Timer::from_seconds(MINE_LIFETIME_SECS, TimerMode::Once),
+        },
+        Movable::new(position, velocity)
+            .with_rotational_velocity(MINE_SPIN_RATE),
+        MovableTorusConstraint { radius },
+        // Collision detection
+        BulletCollidable { source: BulletSource::PlayerRocket },
+        ExplosionCollidable,
+        Collidable { collider },
+        // Rendering
+        ShapeBundle {
+            path: Path(assets.mine_shape.0.clone()),
+            transform,
+            ..default()
+        },
+        mine_stroke,
+        Themed(ThemeColor::Alien),
+    ));
+}
+
+fn mine_layer_system(
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    mut commands: Commands,
+    mut query: Query<(&Movable, &mut MineLayer)>
+) {
+    let mut rng = thread_rng();
+    for (movable, mut layer) in query.iter_mut() {
+        layer.timer.tick(time.delta());
+        if layer.timer.just_finished() {
+            let velocity = rng.random_unit_vec2() * MINE_DRIFT_SPEED;
+            spawn_mine(&mut commands, &assets.mine, MineSpawn { position: movable.position, velocity });
+        }
+    }
+}
+
+// Trigger and detonation
+
+fn mine_trigger_system(
+    time: Res<Time>,
+    world_boundaries: Res<WorldBoundaries>,
+    mut mines: Query<(&mut Mine, &Movable)>,
+    rockets: Query<&Movable, With<PlayerRocket>>,
+) {
+    for (mut mine, movable) in mines.iter_mut() {
+        match mine.state {
+            MineState::Arming(ref mut timer) => {
+                if timer.tick(time.delta()).finished() {
+                    mine.state = MineState::Armed;
+                }
+            },
+            MineState::Armed => {
+                let rocket_in_range = rockets.iter().any(|rocket| {
+                    world_boundaries.torus_delta(movable.position, rocket.position).length() < MINE_TRIGGER_RADIUS
+                });
+                if rocket_in_range {
+                    mine.state = MineState::Triggered(Timer::from_seconds(MINE_COUNTDOWN_SECS, TimerMode::Once));
+                }
+            },
+            MineState::Triggered(ref mut timer) => {
+                timer.tick(time.delta());
+            },
+        }
+    }
+}
+
+fn mine_detonation_system(
+    mut commands: Commands,
+    mut hit_events: EventWriter<HitEvent>,
+    mut blast_hit_events: EventWriter<BlastHitEvent>,
+    mut particles: ResMut<ParticlePool>,
+    mut camera_effects: ResMut<CameraEffects>,
+    mines: Query<(Entity, &Mine, &Movable, &Path)>,
+    targets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<Movable>>,
+) {
+    let mut rng = thread_rng();
+    for (entity, mine, movable, path) in mines.iter() {
+        let detonate = matches!(mine.state, MineState::Triggered(ref timer) if timer.finished());
+        if !detonate {
+            continue;
+        }
+
+        // Area blast - destroy everything nearby
+        let blast = Collidable { collider: Collider::circle(movable.position, MINE_BLAST_RADIUS) };
+        for (other, collidable, invulnerable) in targets.iter() {
+            if other == entity || invulnerable.is_invulnerable() {
+                continue;
+            }
+            if blast.test_collision_with(collidable) {
+                hit_events.send(HitEvent(other));
+                blast_hit_events.send(BlastHitEvent(other));
+            }
+        }
+
+        commands.entity(entity).despawn();
+        spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+            shape: path,
+            shape_scale: 1.0,
+            position: movable.position,
+            velocity: movable.velocity,
+            heading_angle: movable.heading_angle,
+            rotational_velocity: movable.rotational_velocity,
+            despawn_after_secs: MINE_EXPLOSION_DESPAWN_AFTER_SECS,
+            shockwave_radius: None,
+        });
+        particles.burst(&mut rng, ParticleBurst {
+            position: movable.position,
+            velocity: movable.velocity,
+            count: 40,
+            speed: (MINE_BLAST_RADIUS, MINE_BLAST_RADIUS * 3.),
+            lifetime_secs: (0.2, 0.4),
+            kind: ParticleKind::Line,
+            color: ThemeColor::Alien,
+        });
+        camera_effects.add_trauma(MINE_BLAST_TRAUMA);
+    }
+}
+
+// Expiry
+
+fn mine_expiry_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Mine)>
+) {
+    for (entity, mut mine) in query.iter_mut() {
+        // NOTE: A triggered mine goes off regardless
+        let triggered = matches!(mine.state, MineState::Triggered(_));
+        if mine.lifetime.tick(time.delta()).finished() && !triggered {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Hit handling system
+
+fn mine_hit_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<HitEvent>,
+    mut blast_hit_events: EventReader<BlastHitEvent>,
+    mut mine_destroyed: EventWriter<MineDestroyedEvent>,
+    mut query: Query<(&mut Mine, &Movable, &Path)>
+) {
+    let blasted = blast_hit_entities(&mut blast_hit_events);
+    for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
+        if let Ok((mut mine, movable, path)) = query.get_mut(entity) {
+            // Mines caught in a blast set each other off
+            if blasted.contains(&entity) {
+                mine.state = MineState::Triggered(Timer::from_seconds(0., TimerMode::Once));
+                continue;
+            }
+            // Otherwise they're defused, and break apart without detonating
+            let mut rng = thread_rng();
+            commands.entity(entity).despawn();
+            spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+                shape: path,
+                shape_scale: 1.0,
+                position: movable.position,
+                velocity: movable.velocity,
+                heading_angle: movable.heading_angle,
+                rotational_velocity: movable.rotational_velocity,
+                despawn_after_secs: MINE_EXPLOSION_DESPAWN_AFTER_SECS,
+                shockwave_radius: None,
+            });
+            mine_destroyed.send(MineDestroyedEvent);
+        }
+    }
+}
+
+// Blink animation
+
+fn mine_blink_system(time: Res<Time>, mut query: Query<(&Mine, &mut Stroke)>) {
+    let t_secs = time.elapsed_seconds();
+    for (mine, mut stroke) in query.iter_mut() {
+        let alpha = match mine.state {
+            MineState::Arming(_) => 0.3,
+            MineState::Armed => 0.8 + 0.2 * (t_secs * std::f32::consts::TAU).sin(),
+            MineState::Triggered(_) => if (t_secs * 8.0).fract() < 0.5 { 1.0 } else { 0.2 },
+        };
+        stroke.color.set_a(alpha * mine.fade());
+    }
+}
@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
+use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::bullet::{BulletCollidable, BulletSource};
+use super::collidable::Collidable;
+use super::explosion::{ExplosionCollidable, SpawnExplosion, spawn_explosion};
+use super::hit::{BlastHitEvent, HitEvent, blast_hit_entities, distinct_hit_events};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::{GameCleanup, WorldBoundaries};
+use super::movable::{Movable, MovableTorusConstraint};
+use super::particle::{ParticleBurst, ParticleEmitter, ParticleKind, ParticlePool};
+use super::player::PlayerRocket;
+use super::shape::{ShapeCollider, VectorShape};
+
+// Homing missiles
+//
+// Missiles thrust continuously and steer towards the player's rocket with a limited turn rate.
+// They explode when they run out of fuel, hit the rocket, or are shot down.
+
+const MISSILE_Z: f32 = 5.0;
+const MISSILE_THRUST: f32 = 120.0;
+const MISSILE_MAX_SPEED: f32 = 70.0;
+const MISSILE_TURN_ACCELERATION: f32 = 12.0; // rads/sec/sec
+const MISSILE_TURN_DRAG: f32 = 12.0;
+const MISSILE_MAX_TURN_RATE: f32 = 2.5; // rads/sec
+const MISSILE_AIM_TOLERANCE: f32 = 0.05; // rads
+const MISSILE_EXHAUST_PARTICLE_RATE: f32 = 40.0; // per second
+const MISSILE_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 0.6;
+
+pub struct MissilePlugin;
+
+impl Plugin for MissilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MissileDestroyedEvent>();
+        app.add_systems(
+            Update,
+            (
+                missile_steering_system
+                    .after(FrameStage::Input)
+                    .before(FrameStage::Movement),
+
+                missile_collision_system
+                    .in_set(FrameStage::Collision)
+                    .after(FrameStage::Movement),
+
+                missile_hit_system
+                    .in_set(FrameStage::CollisionEffect)
+                    .after(FrameStage::Collision),
+
+                missile_fuel_system
+                    .after(FrameStage::Movement),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, missile_teardown_system);
+    }
+}
+
+// Events
+
+/// Sent when a missile is shot down
+#[derive(Event)]
+pub struct MissileDestroyedEvent;
+
+// Setup
+
+pub struct MissileAssets {
+    missile_collider: ShapeCollider,
+    missile_shape: Path,
+}
+
+pub fn create_missile_assets(missile: &VectorShape) -> MissileAssets {
+    MissileAssets {
+        missile_collider: missile.collider,
+        missile_shape: Path(missile.path.0.clone()),
+    }
+}
+
+// Teardown
+
+fn missile_teardown_system(mut commands: Commands, query: Query<Entity, With<HomingMissile>>) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+}
+
+// Entity
+
+#[derive(Component)]
+pub struct HomingMissile {
+    fuel: Timer,
+}
+
+// Spawning
+
+#[derive(Clone)]
+pub struct MissileSpawn {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heading_angle: f32,
+    pub fuel_secs: f32,
+}
+
+const LINE_WIDTH: f32 = 0.2;
+
+pub fn spawn_missile(
+    commands: &mut Commands,
+    assets: &MissileAssets,
+    spawn: MissileSpawn
+) {
+    let position = spawn.position;
+
+    // NOTE: Colour is applied by the theme
+    let missile_stroke = Stroke::new(Color::WHITE, LINE_WIDTH);
+    let transform = Transform::from_translation(Vec3::new(position.x, position.y, MISSILE_Z))
+        .with_rotation(Quat::from_rotation_z(spawn.heading_angle));
+    let collider = assets.missile_collider.to_collider(position, 1.0);
+
+    let exhaust_particles = ParticleEmitter::new(MISSILE_EXHAUST_PARTICLE_RATE, ParticleKind::Dot, ThemeColor::Alien)
+        .with_offset(Vec2::new(-1.5, 0.0))
+        .with_direction(std::f32::consts::PI, 0.4)
+        .with_speed(10.0, 20.0)
+        .with_lifetime(0.2, 0.4);
+
+    commands.spawn((
+        HomingMissile { fuel: Timer::from_seconds(spawn.fuel_secs, TimerMode::Once) },
+        Movable::new(position, spawn.velocity)
+            .with_heading_angle(spawn.heading_angle)
+            .with_max_speed(MISSILE_MAX_SPEED)
+            .with_rotational_drag(MISSILE_TURN_DRAG)
+            .with_max_rotational_speed(MISSILE_MAX_TURN_RATE),
+        MovableTorusConstraint { radius: 2.0 },
+        exhaust_particles,
+        // Collision detection
+        BulletCollidable { source: BulletSource::PlayerRocket },
+        ExplosionCollidable,
+        Collidable { collider },
+        // Rendering
+        ShapeBundle {
+            path: Path(assets.missile_shape.0.clone()),
+            transform,
+            ..default()
+        },
+        missile_stroke,
+        Themed(ThemeColor::Alien),
+    ));
+}
+
+// Steering
+
+fn missile_steering_system(
+    world_boundaries: Res<WorldBoundaries>,
+    mut missiles: Query<&mut Movable, With<HomingMissile>>,
+    rockets: Query<&Movable, (With<PlayerRocket>, Without<HomingMissile>)>,
+) {
+    let target = rockets.get_single().ok();
+    for mut movable in missiles.iter_mut() {
+        // Turn towards the target (if any)
+        movable.rotational_acceleration = match target {
+            Some(target) => {
+                let to_target = world_boundaries.torus_delta(movable.position, target.position);
+                let error = movable.heading_normal().angle_between(to_target);
+                if error.abs() < MISSILE_AIM_TOLERANCE { 0. }
+                else { MISSILE_TURN_ACCELERATION * error.signum() }
+            },
+            None => 0.,
+        };
+        // Full thrust ahead
+        let thrust = movable.heading_normal() * MISSILE_THRUST * movable.mass;
+        movable.add_force(thrust);
+    }
+}
+
+// Fuel
+
+fn missile_fuel_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut particles: ResMut<ParticlePool>,
+    mut query: Query<(Entity, &mut HomingMissile, &Movable, &Path)>
+) {
+    for (entity, mut missile, movable, path) in query.iter_mut() {
+        if missile.fuel.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+            explode_missile(&mut commands, &mut particles, movable, path);
+        }
+    }
+}
+
+// Collision detection
+
+fn missile_collision_system(
+    missiles: Query<(Entity, &Collidable), With<HomingMissile>>,
+    rockets: Query<(Entity, &Collidable, Option<&Invulnerable>), With<PlayerRocket>>,
+    mut hit_events: EventWriter<HitEvent>
+) {
+    for (missile, missile_collidable) in missiles.iter() {
+        for (rocket, rocket_collidable, invulnerable) in rockets.iter() {
+            if invulnerable.is_invulnerable() {
+                continue;
+            }
+            if missile_collidable.test_collision_with(rocket_collidable) {
+                hit_events.send(HitEvent(missile));
+                hit_events.send(HitEvent(rocket));
+            }
+        }
+    }
+}
+
+// Hit handling system
+
+fn missile_hit_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<HitEvent>,
+    mut blast_hit_events: EventReader<BlastHitEvent>,
+    mut missile_destroyed: EventWriter<MissileDestroyedEvent>,
+    mut particles: ResMut<ParticlePool>,
+    missiles: Query<(&Movable, &Path, &Collidable), With<HomingMissile>>,
+    rockets: Query<&Collidable, With<PlayerRocket>>,
+) {
+    let events = distinct_hit_events(&mut hit_events).map(|e| e.0).collect::<Vec<_>>();
+    let blasted = blast_hit_entities(&mut blast_hit_events);
+    for &entity in events.iter() {
+        if let Ok((movable, path, collidable)) = missiles.get(entity) {
+            commands.entity(entity).despawn();
+            explode_missile(&mut commands, &mut particles, movable, path);
+            // Only award points if the missile didn't take the rocket with it (or get caught in a blast)
+            let hit_rocket = events.iter()
+                .filter_map(|&e| rockets.get(e).ok())
+                .any(|rocket| rocket.test_collision_with(collidable));
+            if !blasted.contains(&entity) && !hit_rocket {
+                missile_destroyed.send(MissileDestroyedEvent);
+            }
+        }
+    }
+}
+
+fn explode_missile(commands: &mut Commands, particles: &mut ParticlePool, movable: &Movable, path: &Path) {
+    let mut rng = thread_rng();
+    spawn_explosion(commands, &mut rng, SpawnExplosion {
+        shape: path,
+        shape_scale: 1.0,
+        position: movable.position,
+        velocity: movable.velocity * 0.5,
+        heading_angle: movable.heading_angle,
+        rotational_velocity: movable.rotational_velocity,
+        despawn_after_secs: MISSILE_EXPLOSION_DESPAWN_AFTER_SECS,
+        shockwave_radius: None,
+    });
+    particles.burst(&mut rng, ParticleBurst {
+        position: movable.position,
+        velocity: movable.velocity * 0.5,
+        count: 12,
+        speed: (10.0, 40.0),
+        lifetime_secs: (0.2, 0.5),
+        kind: ParticleKind::Line,
+        color: ThemeColor::Alien,
+    });
+}
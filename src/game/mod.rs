@@ -1,4 +1,5 @@
 pub mod svg;
+pub mod shape;
 pub mod util;
 pub mod movable;
 pub mod collidable;
@@ -7,9 +8,23 @@ pub mod player;
 pub mod alien;
 pub mod invulnerable;
 pub mod bullet;
+pub mod weapon;
 pub mod asteroid;
 pub mod explosion;
+pub mod particle;
+pub mod starfield;
+pub mod hazard;
+pub mod mine;
+pub mod missile;
+pub mod boss;
 pub mod hud;
+pub mod debug_overlay;
+pub mod mode;
+pub mod level;
+pub mod practice;
+pub mod script;
+#[cfg(feature = "dev-tools")]
+pub mod console;
 pub mod manager;
 pub mod assets;
 
@@ -31,7 +46,7 @@ pub struct GamePluginGroup;
 
 impl PluginGroup for GamePluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>()
+        let group = PluginGroupBuilder::start::<Self>()
             .add(manager::GameManagerPlugin)
             .add(collidable::CollidablePlugin)
             .add(movable::MovablePlugin)
@@ -41,8 +56,22 @@ impl PluginGroup for GamePluginGroup {
             .add(player::PlayerPlugin)
             .add(alien::AlienPlugin)
             .add(bullet::BulletPlugin)
+            .add(weapon::WeaponPlugin)
             .add(asteroid::AsteroidPlugin)
             .add(explosion::ExplosionPlugin)
+            .add(particle::ParticlePlugin)
+            .add(starfield::StarfieldPlugin)
+            .add(hazard::HazardPlugin)
+            .add(mine::MinePlugin)
+            .add(missile::MissilePlugin)
+            .add(boss::BossPlugin)
             .add(hud::HeadsUpDisplayPlugin)
+            .add(debug_overlay::DebugOverlayPlugin)
+            .add(practice::PracticePlugin)
+            .add(level::LevelPlugin)
+            .add(script::ScriptPlugin);
+        #[cfg(feature = "dev-tools")]
+        let group = group.add(console::ConsolePlugin);
+        group
     }
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+// Game modes
+//
+// Each mode is a ruleset: how many lives the player gets, how many asteroids are kept on screen
+// and when the game ends. `GameManager` asks the active mode rather than hard-coding these.
+// Practice is a sandbox: nothing spawns on its own (see `practice`). Challenge plays a handcrafted
+// layout from a level file (see `level`).
+
+const CLASSIC_ASTEROID_COUNT: u32 = 8;
+const CLASSIC_PLAYER_MAX_BULLETS: u32 = 4;
+const TIME_ATTACK_SECS: f32 = 180.0;
+/// Extra asteroids kept on screen for each wave survived
+const SURVIVAL_ASTEROIDS_PER_WAVE: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    /// Three lives, with a respawn after each one
+    #[default]
+    Classic,
+    /// Score as many points as possible before the clock runs out
+    TimeAttack,
+    /// A single life, against ever more asteroids
+    Survival,
+    /// Classic, without the spawn invulnerability
+    Hardcore,
+    /// A sandbox where the rocket can't be destroyed, and the player spawns things on demand
+    Practice,
+    /// A handcrafted level, which ends once its win condition is met
+    Challenge,
+}
+
+impl GameMode {
+    pub const VALUES: [Self; 6] = [ Self::Classic, Self::TimeAttack, Self::Survival, Self::Hardcore, Self::Practice, Self::Challenge ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "CLASSIC",
+            GameMode::TimeAttack => "TIME ATTACK",
+            GameMode::Survival => "SURVIVAL",
+            GameMode::Hardcore => "HARDCORE",
+            GameMode::Practice => "PRACTICE",
+            GameMode::Challenge => "CHALLENGE",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Three lives, four shots on screen",
+            GameMode::TimeAttack => "Most points in three minutes",
+            GameMode::Survival => "One life, more asteroids every wave",
+            GameMode::Hardcore => "Three lives, no spawn protection",
+            GameMode::Practice => "Sandbox with live spawn controls",
+            GameMode::Challenge => "A handcrafted level, with a par score to beat",
+        }
+    }
+
+    /// The next (or previous, for negative steps) mode, for menus
+    pub fn cycle(self, step: i32) -> Self {
+        let len = Self::VALUES.len() as i32;
+        let index = Self::VALUES.iter().position(|&m| m == self).unwrap_or(0) as i32;
+        Self::VALUES[(index + step).rem_euclid(len) as usize]
+    }
+
+    /// Lives in reserve once the first rocket has spawned
+    pub fn player_lives(self) -> u32 {
+        match self {
+            GameMode::Survival => 0,
+            GameMode::Classic | GameMode::TimeAttack | GameMode::Hardcore | GameMode::Practice | GameMode::Challenge => 3,
+        }
+    }
+
+    /// Whether the rocket stays invulnerable for the whole game
+    pub fn rocket_indestructible(self) -> bool {
+        self == GameMode::Practice
+    }
+
+    /// The most bullets the player can have on screen at once
+    pub fn player_max_bullets(self) -> Option<u32> {
+        match self {
+            GameMode::Classic => Some(CLASSIC_PLAYER_MAX_BULLETS),
+            GameMode::TimeAttack | GameMode::Survival | GameMode::Hardcore | GameMode::Practice | GameMode::Challenge => None,
+        }
+    }
+
+    /// Whether the rocket is briefly invulnerable each time it spawns
+    pub fn spawn_invulnerability(self) -> bool {
+        self != GameMode::Hardcore
+    }
+
+    /// The game ends once this much time has passed
+    pub fn time_limit_secs(self) -> Option<f32> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_SECS),
+            GameMode::Classic | GameMode::Survival | GameMode::Hardcore | GameMode::Practice | GameMode::Challenge => None,
+        }
+    }
+
+    /// Whether the layout comes from a level file, rather than being random
+    pub fn uses_level_file(self) -> bool {
+        self == GameMode::Challenge
+    }
+
+    /// Whether waves (and the boss) advance on their own
+    pub fn has_waves(self) -> bool {
+        !matches!(self, GameMode::Practice | GameMode::Challenge)
+    }
+
+    /// Whether UFOs turn up on their own, rather than on request (or on the level's schedule)
+    pub fn spawns_aliens(self) -> bool {
+        !matches!(self, GameMode::Practice | GameMode::Challenge)
+    }
+
+    /// The number of asteroids to keep on screen during a wave
+    pub fn asteroid_count(self, wave: u32) -> u32 {
+        match self {
+            GameMode::Survival => CLASSIC_ASTEROID_COUNT + SURVIVAL_ASTEROIDS_PER_WAVE * wave.saturating_sub(1),
+            GameMode::Classic | GameMode::TimeAttack | GameMode::Hardcore => CLASSIC_ASTEROID_COUNT,
+            GameMode::Practice | GameMode::Challenge => 0,
+        }
+    }
+
+    /// Whether the game has ended, given whether the player is out of lives and how long it has run
+    pub fn is_over(self, player_destroyed: bool, elapsed_secs: f32) -> bool {
+        let out_of_time = self.time_limit_secs().is_some_and(|limit| elapsed_secs >= limit);
+        player_destroyed || out_of_time
+    }
+}
@@ -32,29 +32,28 @@ impl Plugin for MovablePlugin {
     }
 }
 
-#[derive(Debug)]
-pub enum AcceleratingTo {
-    /// No acceleration limit
-    Infinite,
-    /// Accelerating to Zero (decelerating)
-    Zero,
-    /// Accelerating to Max (NOTE: encodes an _absolute_ velocity)
-    Max(f32)
+/// Deceleration which opposes the velocity of a `Movable`.
+/// Drag never reverses the direction of travel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Drag {
+    /// Constant deceleration (units/sec/sec)
+    pub constant: f32,
+    /// Deceleration proportional to the current speed (per sec)
+    pub linear: f32,
+    /// Drag is not applied below this speed
+    pub min_speed: f32,
 }
 
-#[derive(Debug)]
-pub struct Acceleration<TAcc> {
-    pub value: TAcc,
-    pub limit: AcceleratingTo,
-}
-
-impl<T> Acceleration<T> {
-    pub fn new(value: T) -> Self {
-        Self { value, limit: AcceleratingTo::Infinite }
-    }
+impl Drag {
+    pub const NONE: Drag = Drag { constant: 0., linear: 0., min_speed: 0. };
 
-    pub fn with_limit(self, limit: AcceleratingTo) -> Self {
-        Self { limit, ..self }
+    /// Returns the speed after `t_secs` of drag
+    pub fn apply(&self, speed: f32, t_secs: f32) -> f32 {
+        if speed <= self.min_speed {
+            return speed;
+        }
+        let deceleration = (self.constant + self.linear * speed) * t_secs;
+        (speed - deceleration).max(self.min_speed)
     }
 }
 
@@ -64,18 +63,85 @@ pub struct Movable {
     pub position: Vec2,
     /// velocity (vector - the movement direction + speed)
     pub velocity: Vec2,
-    /// current acceleration (per second per second - a vector representing the current acceleration)
-    pub acceleration: Option<Acceleration<Vec2>>,
+    /// mass (forces are divided by this)
+    pub mass: f32,
+    /// linear drag, applied every tick
+    pub drag: Drag,
+    /// speed cap
+    pub max_speed: Option<f32>,
     /// heading angle in rads (the direction the entity is facing)
     /// 0 = East, PI/2 = North, PI = West, 3(PI/2) = South
     pub heading_angle: f32,
     /// rotational velocity (rads/sec - the speed with which the entity is rotating)
     pub rotational_velocity: f32,
-    /// current rotation acceleration (rad/sec - the rate of change of the rotation)
-    pub rotational_acceleration: Option<Acceleration<f32>>,
+    /// current rotation acceleration (rad/sec/sec - the rate of change of the rotation)
+    pub rotational_acceleration: f32,
+    /// rotational deceleration (rad/sec/sec), applied while there is no rotational acceleration
+    pub rotational_drag: f32,
+    /// rotation speed cap
+    pub max_rotational_speed: Option<f32>,
+    /// forces accumulated since the last tick
+    force: Vec2,
+    /// acceleration applied by forces on the last tick
+    acceleration: Vec2,
 }
 
 impl Movable {
+    pub fn new(position: Vec2, velocity: Vec2) -> Self {
+        Self {
+            position,
+            velocity,
+            mass: 1.0,
+            drag: Drag::NONE,
+            max_speed: None,
+            heading_angle: 0.,
+            rotational_velocity: 0.,
+            rotational_acceleration: 0.,
+            rotational_drag: 0.,
+            max_rotational_speed: None,
+            force: Vec2::ZERO,
+            acceleration: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_heading_angle(self, heading_angle: f32) -> Self {
+        Self { heading_angle, ..self }
+    }
+
+    pub fn with_rotational_velocity(self, rotational_velocity: f32) -> Self {
+        Self { rotational_velocity, ..self }
+    }
+
+    pub fn with_mass(self, mass: f32) -> Self {
+        Self { mass, ..self }
+    }
+
+    pub fn with_drag(self, drag: Drag) -> Self {
+        Self { drag, ..self }
+    }
+
+    pub fn with_max_speed(self, max_speed: f32) -> Self {
+        Self { max_speed: Some(max_speed), ..self }
+    }
+
+    pub fn with_rotational_drag(self, rotational_drag: f32) -> Self {
+        Self { rotational_drag, ..self }
+    }
+
+    pub fn with_max_rotational_speed(self, max_rotational_speed: f32) -> Self {
+        Self { max_rotational_speed: Some(max_rotational_speed), ..self }
+    }
+
+    /// Adds a continuous force (units/sec/sec * mass) for the current tick
+    pub fn add_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    /// The acceleration (units/sec/sec) from the forces applied on the last tick
+    pub fn acceleration(&self) -> Vec2 {
+        self.acceleration
+    }
+
     pub fn heading_normal(&self) -> Vec2 {
         Vec2::from_angle(self.heading_angle)
     }
@@ -101,59 +167,40 @@ fn movable_system(
     time: Res<Time>,
     mut query: Query<&mut Movable>
 ) {
-    use AcceleratingTo::*;
     use std::f32::consts::TAU;
 
     // Update the position of each moving object
     let t_secs = time.delta_seconds_f64() as f32;
     for mut movable in query.iter_mut() {
+        let movable = movable.as_mut();
+
+        // Update velocity from accumulated forces
+        let inverse_mass = if movable.mass > 0. { 1. / movable.mass } else { 0. };
+        movable.acceleration = movable.force * inverse_mass;
+        movable.velocity += movable.force * t_secs * inverse_mass;
+        movable.force = Vec2::ZERO;
 
-        // Update velocity
-        if let Some(acc) = &movable.acceleration {
-            let new_v = movable.velocity + acc.value * t_secs;
-            movable.velocity = match acc.limit {
-                Infinite => new_v,
-                // Apply "accelerating to n" limit
-                Max(max) => {
-                    let overspeed = new_v.length() - max;
-                    if overspeed > 0. {
-                        // Subtract the overspeed
-                        new_v - new_v.normalize() * overspeed
-                    }
-                    else {
-                        new_v
-                    }
-                },
-                // Apply "decelerating to zero" limit
-                Zero => {
-                    // Determine if the new vector lies on the line between ZERO and the original vector.
-                    // Convert both vectors to the unit and compare them - if they are (close to) the same, then both vectors are 
-                    // on the same side of zero. If they differ, then the second vector is on the other side of zero (i.e. has gone past it)
-                    if new_v.normalize().abs_diff_eq(movable.velocity.normalize(), 0.1) {
-                        // Same side of zero - we're still decelerating
-                        new_v
-                    }
-                    else {
-                        // Other side of zero - we've blown past it
-                        Vec2::ZERO
-                    }
-                },
-            };
+        // Apply drag and the speed cap
+        let speed = movable.velocity.length();
+        let mut new_speed = movable.drag.apply(speed, t_secs);
+        if let Some(max) = movable.max_speed {
+            new_speed = new_speed.min(max);
+        }
+        if new_speed != speed {
+            movable.velocity = movable.velocity.normalize_or_zero() * new_speed;
         }
 
         // Update rotational velocity
-        if let Some(acc) = &movable.rotational_acceleration {
-            let new_v = movable.rotational_velocity + acc.value * t_secs;
-            movable.rotational_velocity = match acc.limit {
-                // No limit
-                Infinite => new_v,
-                // Apply "accelerating to n" limit
-                Max(max) => new_v.clamp(-max, max),
-                // Apply "decelerating to zero" limit
-                Zero =>
-                    if movable.rotational_velocity > 0. && new_v < 0. || movable.rotational_velocity < 0. && new_v > 0. { 0. }
-                    else { new_v }
-            };
+        if movable.rotational_acceleration != 0. {
+            movable.rotational_velocity += movable.rotational_acceleration * t_secs;
+        }
+        else {
+            // Decelerate to zero (without passing it)
+            let rotational_speed = (movable.rotational_velocity.abs() - movable.rotational_drag * t_secs).max(0.);
+            movable.rotational_velocity = rotational_speed.copysign(movable.rotational_velocity);
+        }
+        if let Some(max) = movable.max_rotational_speed {
+            movable.rotational_velocity = movable.rotational_velocity.clamp(-max, max);
         }
 
         // Update heading
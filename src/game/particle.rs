@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::thread_rng;
+use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::manager::GameCleanup;
+use super::movable::Movable;
+use super::util::*;
+
+// Particles
+//
+// Particles are plain data stored in a fixed-capacity pool (`ParticlePool`) rather than entities.
+// They are drawn by a small, fixed set of "layer" entities - one per colour and fade level - whose
+// paths are rebuilt every frame from the live particles.
+
+const PARTICLE_Z: f32 = 8.0;
+const PARTICLE_POOL_CAPACITY: usize = 4096;
+const PARTICLE_FADE_LEVELS: usize = 4;
+const PARTICLE_COLORS: [ThemeColor; 4] = [
+    ThemeColor::Rocket,
+    ThemeColor::Alien,
+    ThemeColor::Asteroid,
+    ThemeColor::Bullet,
+];
+const LINE_WIDTH: f32 = 0.2;
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ParticlePool::with_capacity(PARTICLE_POOL_CAPACITY));
+        app.add_systems(Startup, particle_layer_setup_system);
+        app.add_systems(
+            Update,
+            (
+                particle_emitter_system
+                    .after(FrameStage::Movement),
+
+                particle_update_system
+                    .after(particle_emitter_system),
+
+                particle_render_system
+                    .after(particle_update_system),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, particle_teardown_system);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ParticleKind {
+    /// A short streak, drawn along the direction of travel
+    Line,
+    /// A single speck
+    Dot,
+}
+
+#[derive(Clone, Copy)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub lifetime_secs: f32,
+    pub kind: ParticleKind,
+    pub color: ThemeColor,
+    age_secs: f32,
+}
+
+impl Particle {
+    pub fn new(position: Vec2, velocity: Vec2, lifetime_secs: f32, kind: ParticleKind, color: ThemeColor) -> Self {
+        Self { position, velocity, lifetime_secs, kind, color, age_secs: 0. }
+    }
+
+    fn percent_left(&self) -> f32 {
+        1.0 - (self.age_secs / self.lifetime_secs).clamp(0.0, 1.0)
+    }
+}
+
+/// Fixed-capacity particle storage.
+/// Live particles are kept packed at the front of the buffer, so emitting and expiring never allocates.
+#[derive(Resource)]
+pub struct ParticlePool {
+    particles: Vec<Particle>,
+    live: usize,
+}
+
+impl ParticlePool {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { particles: Vec::with_capacity(capacity), live: 0 }
+    }
+
+    /// Adds a particle to the pool. The particle is dropped if the pool is full.
+    pub fn emit(&mut self, particle: Particle) {
+        if self.live == self.particles.capacity() {
+            return;
+        }
+        if self.live < self.particles.len() {
+            self.particles[self.live] = particle;
+        }
+        else {
+            self.particles.push(particle);
+        }
+        self.live += 1;
+    }
+
+    /// Emits `burst.count` particles in random directions
+    pub fn burst(&mut self, rng: &mut impl RngUtil, burst: ParticleBurst) {
+        for _ in 0..burst.count {
+            let speed = rng.random_range(burst.speed);
+            let lifetime = rng.random_range(burst.lifetime_secs);
+            let velocity = burst.velocity + rng.random_unit_vec2() * speed;
+            self.emit(Particle::new(burst.position, velocity, lifetime, burst.kind, burst.color));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.live = 0;
+    }
+
+    fn update(&mut self, t_secs: f32) {
+        let mut i = 0;
+        while i < self.live {
+            let p = &mut self.particles[i];
+            p.age_secs += t_secs;
+            if p.age_secs >= p.lifetime_secs {
+                // Expired - move the last live particle into this slot
+                self.live -= 1;
+                self.particles.swap(i, self.live);
+                continue;
+            }
+            p.position += p.velocity * t_secs;
+            i += 1;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item=&Particle> {
+        self.particles[..self.live].iter()
+    }
+}
+
+#[derive(Clone)]
+pub struct ParticleBurst {
+    pub position: Vec2,
+    /// Base velocity shared by every particle in the burst
+    pub velocity: Vec2,
+    pub count: u32,
+    /// (min, max) speed added in a random direction
+    pub speed: (f32, f32),
+    /// (min, max) lifetime
+    pub lifetime_secs: (f32, f32),
+    pub kind: ParticleKind,
+    pub color: ThemeColor,
+}
+
+// Emitters
+
+/// Continuously emits particles from an entity while `enabled` is set.
+/// Particles inherit the velocity of the entity.
+#[derive(Component, Clone)]
+pub struct ParticleEmitter {
+    pub enabled: bool,
+    /// particles per second
+    pub rate: f32,
+    /// Emission point, relative to the position and heading of the entity
+    pub offset: Vec2,
+    /// Emission direction in rads, relative to the heading of the entity
+    pub direction: f32,
+    /// Maximum deviation from `direction` in rads
+    pub spread: f32,
+    /// (min, max) speed
+    pub speed: (f32, f32),
+    /// (min, max) lifetime
+    pub lifetime_secs: (f32, f32),
+    pub kind: ParticleKind,
+    pub color: ThemeColor,
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(rate: f32, kind: ParticleKind, color: ThemeColor) -> Self {
+        Self {
+            enabled: true,
+            rate,
+            offset: Vec2::ZERO,
+            direction: 0.,
+            spread: 0.,
+            speed: (0., 0.),
+            lifetime_secs: (0.5, 0.5),
+            kind,
+            color,
+            pending: 0.,
+        }
+    }
+
+    pub fn with_offset(self, offset: Vec2) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub fn with_direction(self, direction: f32, spread: f32) -> Self {
+        Self { direction, spread, ..self }
+    }
+
+    pub fn with_speed(self, min: f32, max: f32) -> Self {
+        Self { speed: (min, max), ..self }
+    }
+
+    pub fn with_lifetime(self, min_secs: f32, max_secs: f32) -> Self {
+        Self { lifetime_secs: (min_secs, max_secs), ..self }
+    }
+
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+}
+
+fn particle_emitter_system(
+    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(&Movable, &mut ParticleEmitter)>
+) {
+    let mut rng = thread_rng();
+    let t_secs = time.delta_seconds();
+    for (movable, mut emitter) in query.iter_mut() {
+        if !emitter.enabled {
+            emitter.pending = 0.;
+            continue;
+        }
+        emitter.pending += emitter.rate * t_secs;
+        let heading = movable.heading_normal();
+        let origin = movable.position + heading.rotate(emitter.offset);
+        while emitter.pending >= 1.0 {
+            emitter.pending -= 1.0;
+            let angle = movable.heading_angle + emitter.direction + (rng.random_f32() * 2. - 1.) * emitter.spread;
+            let speed = rng.random_range(emitter.speed);
+            let lifetime = rng.random_range(emitter.lifetime_secs);
+            let velocity = movable.velocity + Vec2::from_angle(angle) * speed;
+            pool.emit(Particle::new(origin, velocity, lifetime, emitter.kind, emitter.color));
+        }
+    }
+}
+
+fn particle_update_system(time: Res<Time>, mut pool: ResMut<ParticlePool>) {
+    pool.update(time.delta_seconds());
+}
+
+// Rendering
+
+#[derive(Component)]
+struct ParticleLayer {
+    color: ThemeColor,
+    fade_level: usize,
+}
+
+fn particle_layer_setup_system(mut commands: Commands) {
+    for color in PARTICLE_COLORS {
+        for fade_level in 0..PARTICLE_FADE_LEVELS {
+            let alpha = (fade_level + 1) as f32 / PARTICLE_FADE_LEVELS as f32;
+            let mut stroke = Stroke::new(Color::WHITE.with_a(alpha), LINE_WIDTH);
+            stroke.options = stroke.options.with_line_cap(LineCap::Round);
+            commands.spawn((
+                ParticleLayer { color, fade_level },
+                ShapeBundle {
+                    transform: Transform::from_xyz(0., 0., PARTICLE_Z),
+                    ..default()
+                },
+                stroke,
+                Themed(color),
+            ));
+        }
+    }
+}
+
+const PARTICLE_DOT_LENGTH: f32 = 0.05;
+const PARTICLE_LINE_LENGTH_PER_SPEED: f32 = 0.02;
+const PARTICLE_LINE_MAX_LENGTH: f32 = 1.5;
+
+fn particle_render_system(
+    pool: Res<ParticlePool>,
+    mut layers: Query<(&ParticleLayer, &mut Path)>
+) {
+    let mut builders = Vec::new();
+    builders.resize_with(PARTICLE_COLORS.len() * PARTICLE_FADE_LEVELS, tess::path::Path::builder);
+    let layer_index = |color: ThemeColor, fade_level: usize| {
+        let color_index = PARTICLE_COLORS.iter().position(|&c| c == color).unwrap_or(0);
+        color_index * PARTICLE_FADE_LEVELS + fade_level
+    };
+
+    for particle in pool.iter() {
+        let fade_level = ((particle.percent_left() * PARTICLE_FADE_LEVELS as f32).ceil() as usize).clamp(1, PARTICLE_FADE_LEVELS) - 1;
+        let length = match particle.kind {
+            ParticleKind::Dot => PARTICLE_DOT_LENGTH,
+            ParticleKind::Line => (particle.velocity.length() * PARTICLE_LINE_LENGTH_PER_SPEED).min(PARTICLE_LINE_MAX_LENGTH),
+        };
+        let tail = particle.position - particle.velocity.normalize_or_zero() * length;
+        let builder = &mut builders[layer_index(particle.color, fade_level)];
+        builder.begin(tess::math::point(tail.x, tail.y));
+        builder.line_to(tess::math::point(particle.position.x, particle.position.y));
+        builder.end(false);
+    }
+
+    let mut paths = builders.into_iter().map(|b| b.build()).collect::<Vec<_>>();
+    for (layer, mut path) in layers.iter_mut() {
+        let index = layer_index(layer.color, layer.fade_level);
+        path.0 = std::mem::replace(&mut paths[index], tess::path::Path::new());
+    }
+}
+
+// Teardown
+
+fn particle_teardown_system(mut pool: ResMut<ParticlePool>, mut layers: Query<&mut Path, With<ParticleLayer>>) {
+    pool.clear();
+    for mut path in layers.iter_mut() {
+        path.0 = tess::path::Path::new();
+    }
+}
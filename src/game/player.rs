@@ -2,32 +2,36 @@ use std::f32::consts::TAU;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Themed, ThemeColor};
 use super::FrameStage;
 use super::assets::GameAssets;
 use super::asteroid::AsteroidCollidable;
 use super::hit::{HitEvent, distinct_hit_events};
-use super::manager::GameCleanup;
-use super::movable::{Movable, MovableTorusConstraint, Acceleration, AcceleratingTo};
-use super::collidable::{Collidable, Collider};
-use super::explosion::{ExplosionShapeId, SpawnExplosion, spawn_explosion};
-use super::bullet::{BulletController, BulletFireResult, BulletSpawn, BulletSource, BulletCollidable, spawn_bullet};
+use super::manager::{GameCleanup, GameManager};
+use super::movable::{Drag, Movable, MovableTorusConstraint};
+use super::collidable::Collidable;
+use super::explosion::{ExplosionCollidable, SpawnExplosion, SpawnShockwave, spawn_explosion, spawn_shockwave};
+use super::bullet::{BulletController, BulletFireResult, BulletSource, BulletCollidable};
 use super::invulnerable::Invulnerable;
-use super::svg::simple_svg_to_path;
+use super::particle::{ParticleEmitter, ParticleKind};
+use super::shape::{ShapeCollider, VectorShape};
+use super::weapon::{Weapon, WeaponFire, WeaponRack, fire_weapon};
 
 // Player's Rocket
 
 const ROCKET_RATE_OF_TURN: f32 = 999.0; // Instant rotation acceleration / deceleration
 const ROCKET_RATE_OF_TURN_DRAG: f32 = 999.0;
-const ROCKET_RATE_OF_ACCELERATION: f32 = 300.0;
-const ROCKET_RATE_OF_ACCELERATION_DRAG: f32 = 50.0;
+const ROCKET_THRUST: f32 = 350.0; // Net acceleration of 300 once drag is accounted for
+const ROCKET_DRAG: Drag = Drag { constant: 50.0, linear: 0.0, min_speed: 20.0 }; // "space drag"
 const ROCKET_MAX_SPEED: f32 = 200.0;
-const ROCKET_MAX_DRAG_SPEED: f32 = 20.0;
 const ROCKET_MAX_ROTATION_SPEED: f32 = TAU; // 1 rotation per second
-const ROCKET_BULLET_SPEED: f32 = 250.0;
-const ROCKET_BULLET_MAX_AGE_SECS: f32 = 1.0;
-const ROCKET_FIRE_RATE: f32 = 5.0; // per second
 const ROCKET_SPAWN_INVULNERABILITY_SECS: f32 = 3.0;
 const ROCKET_Z: f32 = 10.0;
+const ROCKET_EXHAUST_PARTICLE_RATE: f32 = 60.0; // per second
+const SMART_BOMB_RADIUS: f32 = 150.0;
+const SMART_BOMB_TRAUMA: f32 = 0.6;
+const ROCKET_CHARGE_GLOW_RADIUS: f32 = 3.0; // at full charge
 
 pub struct PlayerPlugin;
 
@@ -40,15 +44,28 @@ impl Plugin for PlayerPlugin {
                 player_keyboard_event_system
                     .in_set(FrameStage::Input),
 
+                player_weapon_select_system
+                    .in_set(FrameStage::Input),
+
                 player_bullet_system
                     .after(FrameStage::Movement),
 
+                player_smart_bomb_system
+                    .after(FrameStage::Movement)
+                    .before(FrameStage::Collision),
+
                 player_update_movable_system
                     .after(player_keyboard_event_system),
 
                 rocket_exhaust_update_system
                     .after(player_keyboard_event_system),
 
+                rocket_exhaust_particles_system
+                    .after(player_keyboard_event_system),
+
+                rocket_charge_glow_system
+                    .after(player_bullet_system),
+
                 player_hit_system
                     .in_set(FrameStage::CollisionEffect)
                     .after(FrameStage::Collision),
@@ -67,21 +84,18 @@ pub struct PlayerRocketDestroyedEvent;
 // Setup
 
 pub struct RocketAssets {
-    rocket_dimension: (f32, f32), // (w, h) of the rocket shape
+    rocket_dimension: Vec2, // (w, h) of the rocket shape
+    rocket_collider: ShapeCollider,
     rocket_shape: Path,
     rocket_exhaust_shape: Path,
 }
 
-pub fn create_roket_assets() -> RocketAssets {
-    // See: https://yqnn.github.io/svg-path-editor/
-    let rocket_dimension = (4.0, 6.0);
-    let rocket_path = "M 3 0 L -2 -2 M -1 -1.6 L -1 1.6 M 3 0 L -2 2";
-    let exhaust_path = "M -1 1 L -3 0 L -1 -1";
-
+pub fn create_roket_assets(rocket: &VectorShape, exhaust: &VectorShape) -> RocketAssets {
     RocketAssets {
-        rocket_dimension,
-        rocket_shape: simple_svg_to_path(rocket_path),
-        rocket_exhaust_shape: simple_svg_to_path(exhaust_path),
+        rocket_dimension: rocket.size,
+        rocket_collider: rocket.collider,
+        rocket_shape: Path(rocket.path.0.clone()),
+        rocket_exhaust_shape: Path(exhaust.path.0.clone()),
     }
 }
 
@@ -107,6 +121,10 @@ pub struct PlayerRocket {
 #[derive(Component)]
 pub struct PlayerRocketExhaust;
 
+/// Grows on the nose of the rocket as a shot charges
+#[derive(Component)]
+pub struct PlayerRocketChargeGlow;
+
 fn player_keyboard_event_system(
     kb: Res<Input<KeyCode>>,
     mut rocket_query: Query<(&mut PlayerRocket, &mut BulletController)>
@@ -124,6 +142,28 @@ fn player_keyboard_event_system(
     }
 }
 
+fn player_weapon_select_system(
+    kb: Res<Input<KeyCode>>,
+    mut game: ResMut<GameManager>,
+    mut rocket_query: Query<(&mut WeaponRack, &mut BulletController), With<PlayerRocket>>
+) {
+    let step = match (kb.just_pressed(KeyCode::Q), kb.just_pressed(KeyCode::E)) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    for (mut weapons, mut bullet_controller) in rocket_query.iter_mut() {
+        // Each weapon fires at its own rate
+        let weapon = weapons.cycle(step);
+        // Remembered for the next rocket
+        game.player_weapon = weapon;
+        let spawn_translation = bullet_controller.spawn_translation.unwrap_or_default();
+        let max_live_bullets = bullet_controller.max_live_bullets;
+        *bullet_controller = weapon.bullet_controller().with_spawn_translation(spawn_translation);
+        bullet_controller.max_live_bullets = max_live_bullets;
+    }
+}
+
 fn player_update_movable_system(
     mut rocket_query: Query<(&PlayerRocket, &mut Movable)>
 ) {
@@ -131,29 +171,17 @@ fn player_update_movable_system(
     for (rocket, mut movable) in rocket_query.iter_mut() {
         // Update rotational acceleration
         movable.rotational_acceleration = match (rocket.turning_left, rocket.turning_right) {
-            (true, false) => Some(Acceleration::new(ROCKET_RATE_OF_TURN).with_limit(AcceleratingTo::Max(ROCKET_MAX_ROTATION_SPEED))),
-            (false, true) => Some(Acceleration::new(-ROCKET_RATE_OF_TURN).with_limit(AcceleratingTo::Max(ROCKET_MAX_ROTATION_SPEED))),
-            // Apply "turn drag"
-            _ if movable.rotational_velocity > 0. => Some(Acceleration::new(-ROCKET_RATE_OF_TURN_DRAG).with_limit(AcceleratingTo::Zero)),
-            _ if movable.rotational_velocity < 0. => Some(Acceleration::new(ROCKET_RATE_OF_TURN_DRAG).with_limit(AcceleratingTo::Zero)),
-            _ => None
+            (true, false) => ROCKET_RATE_OF_TURN,
+            (false, true) => -ROCKET_RATE_OF_TURN,
+            // Not turning - "turn drag" is applied
+            _ => 0.
         };
 
-        // Update acceleration
-        movable.acceleration =
-            if rocket.accelerating {
-                let acc = movable.heading_normal() * ROCKET_RATE_OF_ACCELERATION;
-                Some(Acceleration::new(acc).with_limit(AcceleratingTo::Max(ROCKET_MAX_SPEED)))
-            }
-            // Apply "space drag"
-            else if movable.velocity.length() > ROCKET_MAX_DRAG_SPEED {
-                let acc = -movable.velocity.normalize() * ROCKET_RATE_OF_ACCELERATION_DRAG;
-                Some(Acceleration::new(acc).with_limit(AcceleratingTo::Zero))
-            }
-            // Not accelerating
-            else {
-                None
-            };
+        // Apply thrust
+        if rocket.accelerating {
+            let thrust = movable.heading_normal() * ROCKET_THRUST * movable.mass;
+            movable.add_force(thrust);
+        }
     }
 }
 
@@ -178,6 +206,30 @@ fn rocket_exhaust_update_system(
     }
 }
 
+fn rocket_exhaust_particles_system(mut query: Query<(&PlayerRocket, &mut ParticleEmitter)>) {
+    for (rocket, mut emitter) in query.iter_mut() {
+        emitter.enabled = rocket.accelerating;
+    }
+}
+
+// Charge glow system
+
+fn rocket_charge_glow_system(
+    rocket_query: Query<(&BulletController, &Children), With<PlayerRocket>>,
+    mut glow_query: Query<(&mut Transform, &mut Stroke), With<PlayerRocketChargeGlow>>
+) {
+    for (controller, children) in rocket_query.iter() {
+        let charge = controller.charge();
+        for &child in children.iter() {
+            if let Ok((mut transform, mut stroke)) = glow_query.get_mut(child) {
+                // Avoid a zero scale, which can't be inverted
+                transform.scale = Vec3::splat(charge.max(0.01));
+                stroke.color.set_a(charge);
+            }
+        }
+    }
+}
+
 fn exhaust_opacity_over_t(t_secs: f32) -> f32 {
     // flicker the exhaust between (0.2, 1.0), eight times per second
     let (min, max) = (0.2, 1.0);
@@ -192,7 +244,11 @@ fn exhaust_opacity_over_t(t_secs: f32) -> f32 {
 pub struct RocketSpawn {
     pub position: Vec2,
     pub velocity: Vec2,
-    pub invulnerable: Option<Timer>,
+    pub invulnerable: Option<Invulnerable>,
+    /// The most bullets the rocket can have on screen at once
+    pub max_bullets: Option<u32>,
+    /// The weapon selected when the rocket spawns
+    pub weapon: Weapon,
 }
 
 impl Default for RocketSpawn {
@@ -200,7 +256,9 @@ impl Default for RocketSpawn {
         Self {
             position: default(),
             velocity: default(),
-            invulnerable: Some(Timer::from_seconds(ROCKET_SPAWN_INVULNERABILITY_SECS, TimerMode::Once))
+            invulnerable: Some(Invulnerable::new(Timer::from_seconds(ROCKET_SPAWN_INVULNERABILITY_SECS, TimerMode::Once))),
+            max_bullets: None,
+            weapon: Weapon::SingleShot,
         }
     }
 }
@@ -215,48 +273,57 @@ pub fn spawn_player_rocket(
      // Spawn stationary, in the middle of the screen
     let position = spawn.position;
     let velocity = spawn.velocity;
-    let (_, rocket_shape_height) = assets.rocket_dimension;
+    let rocket_shape_height = assets.rocket_dimension.y;
 
     let initial_heading_angle = std::f32::consts::PI / 2.0; // straight up
 
     // Rocket
-    let rocket_color = Color::rgba(1., 1., 1., 1.);
-    let rocket_stroke = Stroke::new(rocket_color, LINE_WIDTH);
+    // NOTE: Colours are applied by the theme
+    let rocket_stroke = Stroke::new(Color::WHITE, LINE_WIDTH);
 
-    // Rocket exhaust
-    let rocket_exhaust_color = Color::rgba(1., 1., 1., 0.);
-    let rocket_exhaust_stroke = Stroke::new(rocket_exhaust_color, LINE_WIDTH);
+    // Rocket exhaust (initially hidden)
+    let rocket_exhaust_stroke = Stroke::new(Color::WHITE.with_a(0.), LINE_WIDTH);
     
     // Transform
     let transform = Transform::from_translation(Vec3::new(position.x, position.y, ROCKET_Z))
         .with_rotation(Quat::from_rotation_z(initial_heading_angle));
     
     // Collision detection
-    // NOTE: Currently using a spherical collision box, shrunk down to fit within the hull
     // TODO(benf): Make a triangular Polygon collision shape
     let radius = rocket_shape_height / 2.;
-    let collider = Collider::circle(position.into(), radius / 2.);
+    let collider = assets.rocket_collider.to_collider(position, 1.0);
 
     // Bullet control
-    let bullet_fire_rate = ROCKET_FIRE_RATE;
+    let weapons = WeaponRack::new(Weapon::VALUES.to_vec()).with_active(spawn.weapon);
+    let mut bullet_controller = weapons.active().bullet_controller();
+    bullet_controller.max_live_bullets = spawn.max_bullets;
     let bullet_spawn_translation = Vec2::new(radius, 0.0);
 
+    // Exhaust trail (enabled while accelerating)
+    let exhaust_particles = ParticleEmitter::new(ROCKET_EXHAUST_PARTICLE_RATE, ParticleKind::Line, ThemeColor::Rocket)
+        .with_offset(Vec2::new(-radius * 0.8, 0.0))
+        .with_direction(std::f32::consts::PI, 0.3)
+        .with_speed(20.0, 40.0)
+        .with_lifetime(0.3, 0.6)
+        .with_enabled(false);
+
     let entity = commands
         .spawn((
             PlayerRocket::default(),
-            Movable {
-                position,
-                velocity,
-                acceleration: None,
-                heading_angle: initial_heading_angle,
-                rotational_velocity: 0.,
-                rotational_acceleration: None,
-            },
+            Movable::new(position, velocity)
+                .with_heading_angle(initial_heading_angle)
+                .with_drag(ROCKET_DRAG)
+                .with_max_speed(ROCKET_MAX_SPEED)
+                .with_rotational_drag(ROCKET_RATE_OF_TURN_DRAG)
+                .with_max_rotational_speed(ROCKET_MAX_ROTATION_SPEED),
             MovableTorusConstraint { radius },
-            BulletController::new(bullet_fire_rate).with_spawn_translation(bullet_spawn_translation),
+            bullet_controller.with_spawn_translation(bullet_spawn_translation),
+            weapons,
+            exhaust_particles,
             // Collision detection
             AsteroidCollidable,
             BulletCollidable { source: BulletSource::AlienUfo },
+            ExplosionCollidable,
             Collidable { collider },
             // Rendering
             ShapeBundle {
@@ -265,6 +332,7 @@ pub fn spawn_player_rocket(
                 ..default()
             },
             rocket_stroke,
+            Themed(ThemeColor::Rocket),
         ))
         .with_children(|child_commands| {
             child_commands.spawn((
@@ -274,15 +342,27 @@ pub fn spawn_player_rocket(
                     ..default()
                 },
                 rocket_exhaust_stroke,
+                Themed(ThemeColor::Rocket),
+            ));
+            child_commands.spawn((
+                PlayerRocketChargeGlow,
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Circle { radius: ROCKET_CHARGE_GLOW_RADIUS, center: Vec2::ZERO }),
+                    transform: Transform::from_translation(bullet_spawn_translation.extend(0.1))
+                        .with_scale(Vec3::splat(0.01)),
+                    ..default()
+                },
+                Stroke::new(Color::WHITE.with_a(0.), LINE_WIDTH),
+                Themed(ThemeColor::Bullet),
             ));
         })
         .id();
 
     // Add invulnerability?
-    if let Some(timer) = spawn.invulnerable {
+    if let Some(invulnerable) = spawn.invulnerable {
         commands
             .entity(entity)
-            .insert(Invulnerable::new(timer));
+            .insert(invulnerable);
     }
 }
 
@@ -292,19 +372,50 @@ fn player_bullet_system(
     time: Res<Time>,
     assets: Res<GameAssets>,
     mut commands: Commands,
-    mut query: Query<(&Movable, &mut BulletController), With<PlayerRocket>>
+    mut query: Query<(Entity, &Movable, &mut BulletController, &WeaponRack), With<PlayerRocket>>
 ) {
-    for (movable, mut controller) in query.iter_mut() {
-        if controller.update(&time) == BulletFireResult::FireBullet {
-            let translation = movable.heading_normal().rotate(controller.spawn_translation.unwrap_or_default());
-            let velocity = movable.heading_normal() * ROCKET_BULLET_SPEED;
-            spawn_bullet(&mut commands, &assets.bullet, BulletSpawn {
-                source: BulletSource::PlayerRocket,
-                position: movable.position + translation,
-                velocity: movable.velocity + velocity,
-                heading_angle: movable.heading_angle,
-                despawn_after_secs: ROCKET_BULLET_MAX_AGE_SECS,
+    for (entity, movable, mut controller, weapons) in query.iter_mut() {
+        let charge = match controller.update(&time) {
+            BulletFireResult::None => continue,
+            BulletFireResult::FireBullet => 0.,
+            BulletFireResult::FireChargedBullet(charge) => charge,
+        };
+        let translation = movable.heading_normal().rotate(controller.spawn_translation.unwrap_or_default());
+        let fired = fire_weapon(&mut commands, &assets.bullet, weapons.active(), WeaponFire {
+            source: BulletSource::PlayerRocket,
+            owner: entity,
+            position: movable.position + translation,
+            velocity: movable.velocity,
+            heading_angle: movable.heading_angle,
+            charge,
+            max_bullets: controller.bullet_allowance(),
+        });
+        // NOTE: Counted again next frame, once the bullets have spawned
+        controller.live_bullets += fired;
+    }
+}
+
+// Smart bomb
+
+fn player_smart_bomb_system(
+    kb: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    mut game: ResMut<GameManager>,
+    mut camera_effects: ResMut<CameraEffects>,
+    query: Query<(Entity, &Movable), With<PlayerRocket>>
+) {
+    if !kb.just_pressed(KeyCode::B) {
+        return;
+    }
+    // Clears everything nearby (except the rocket itself)
+    if let Ok((entity, movable)) = query.get_single() {
+        if game.try_use_smart_bomb() {
+            spawn_shockwave(&mut commands, SpawnShockwave {
+                position: movable.position,
+                max_radius: SMART_BOMB_RADIUS,
+                ignore: Some(entity),
             });
+            camera_effects.add_trauma(SMART_BOMB_TRAUMA);
         }
     }
 }
@@ -312,29 +423,34 @@ fn player_bullet_system(
 // Destruction system
 
 static PLAYER_ROCKET_EXPLOSION_DESPAWN_AFTER_SECS: f32 = 3.0;
+static PLAYER_ROCKET_DESTROYED_TRAUMA: f32 = 0.8;
+static PLAYER_ROCKET_DESTROYED_ZOOM_PUNCH: f32 = 0.1;
 
 fn player_hit_system(
     mut commands: Commands,
     mut hit_events: EventReader<HitEvent>,
     mut rocket_destroyed: EventWriter<PlayerRocketDestroyedEvent>,
-    assets: Res<GameAssets>,
-    query: Query<&Movable, With<PlayerRocket>>
+    mut camera_effects: ResMut<CameraEffects>,
+    query: Query<(&Movable, &Path), With<PlayerRocket>>
 ) {
     for &HitEvent(entity) in distinct_hit_events(&mut hit_events) {
-        if let Ok(movable) = query.get(entity) {
+        if let Ok((movable, path)) = query.get(entity) {
             let mut rng = rand::thread_rng();
             // Despawn the entity
             commands.entity(entity).despawn_recursive();
             // Start the explosion
-            spawn_explosion(&mut commands, &mut rng, &assets.explosion, SpawnExplosion {
-                shape_id: ExplosionShapeId::RocketDebris,
+            spawn_explosion(&mut commands, &mut rng, SpawnExplosion {
+                shape: path,
                 shape_scale: 1.0,
                 position: movable.position,
                 velocity: movable.velocity,
                 heading_angle: movable.heading_angle,
                 rotational_velocity: movable.rotational_velocity,
                 despawn_after_secs: PLAYER_ROCKET_EXPLOSION_DESPAWN_AFTER_SECS,
+                shockwave_radius: None,
             });
+            camera_effects.add_trauma(PLAYER_ROCKET_DESTROYED_TRAUMA);
+            camera_effects.punch_zoom(PLAYER_ROCKET_DESTROYED_ZOOM_PUNCH);
             // Send events
             rocket_destroyed.send(PlayerRocketDestroyedEvent);
         }
@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rand::thread_rng;
+use crate::AppState;
+use crate::theme::{Theme, ThemeColor, ThemedText};
+use super::FrameStage;
+use super::alien::AlienUfo;
+use super::assets::GameAssets;
+use super::asteroid::{Asteroid, AsteroidMaterial, AsteroidShapeId, AsteroidSize, AsteroidSpawn, spawn_asteroid};
+use super::bullet::BulletController;
+use super::manager::{GameManager, random_asteroid_rotation, random_asteroid_velocity};
+use super::mode::GameMode;
+use super::movable::Movable;
+
+// Practice sandbox
+//
+// Only active in `GameMode::Practice`. Asteroids spawn where the player clicks, UFOs spawn on
+// request, and a side panel shows (and adjusts) the live spawn settings.
+
+const PRACTICE_SWARM_SIZE: u32 = 100;
+const PRACTICE_ASTEROID_SPEED_STEP: f32 = 0.25;
+const PRACTICE_ASTEROID_SPEED_MAX: f32 = 3.0;
+const PRACTICE_ALIEN_AIM_ERROR_STEP: f32 = 0.1;
+const PRACTICE_ALIEN_AIM_ERROR_MAX: f32 = 0.8;
+const PRACTICE_ALIEN_FIRE_RATE_STEP: f32 = 0.25;
+const PRACTICE_ALIEN_FIRE_RATE_MIN: f32 = 0.25;
+const PRACTICE_ALIEN_FIRE_RATE_MAX: f32 = 4.0;
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeSettings>();
+        app.add_systems(
+            OnEnter(AppState::Game),
+            practice_panel_setup_system
+                .run_if(in_practice_mode)
+        );
+        app.add_systems(
+            Update,
+            (
+                practice_keyboard_system
+                    .in_set(FrameStage::Input),
+
+                practice_mouse_system
+                    .in_set(FrameStage::Input),
+
+                practice_asteroid_speed_system
+                    .after(practice_keyboard_system)
+                    .before(FrameStage::Movement),
+
+                practice_alien_settings_system
+                    .after(practice_keyboard_system)
+                    .before(FrameStage::Movement),
+
+                practice_panel_update_system,
+            )
+            .run_if(in_state(AppState::Game))
+            .run_if(in_practice_mode)
+        );
+        app.add_systems(
+            OnExit(AppState::Game),
+            practice_panel_destroy_system
+        );
+    }
+}
+
+fn in_practice_mode(game: Option<Res<GameManager>>) -> bool {
+    game.is_some_and(|game| game.mode() == GameMode::Practice)
+}
+
+// Settings
+
+/// Live spawn settings (kept between practice sessions)
+#[derive(Resource)]
+pub struct PracticeSettings {
+    pub asteroid_size: AsteroidSize,
+    pub asteroid_shape: AsteroidShapeId,
+    /// Scales the speed of asteroids
+    pub asteroid_speed: f32,
+    /// See `AlienUfo::aim_error`
+    pub alien_aim_error: f32,
+    /// Scales the fire rate of UFOs
+    pub alien_fire_rate: f32,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        Self {
+            asteroid_size: AsteroidSize::Large,
+            asteroid_shape: AsteroidShapeId::A,
+            asteroid_speed: 1.0,
+            alien_aim_error: 0.0,
+            alien_fire_rate: 1.0,
+        }
+    }
+}
+
+// Controls
+
+fn practice_keyboard_system(
+    kb: Res<Input<KeyCode>>,
+    mut game: ResMut<GameManager>,
+    mut settings: ResMut<PracticeSettings>,
+) {
+    // Asteroid size and shape
+    for (key, size) in [(KeyCode::Key1, AsteroidSize::Small), (KeyCode::Key2, AsteroidSize::Medium), (KeyCode::Key3, AsteroidSize::Large)] {
+        if kb.just_pressed(key) {
+            settings.asteroid_size = size;
+        }
+    }
+    if kb.just_pressed(KeyCode::Tab) {
+        let shapes = AsteroidShapeId::VALUES;
+        let index = shapes.iter().position(|&s| s == settings.asteroid_shape).unwrap_or(0);
+        settings.asteroid_shape = shapes[(index + 1) % shapes.len()];
+    }
+
+    // Spawning
+    if kb.just_pressed(KeyCode::U) {
+        game.request_alien_ufo();
+    }
+    if kb.just_pressed(KeyCode::P) {
+        game.schedule_asteroid_swarm(PRACTICE_SWARM_SIZE);
+    }
+
+    // Live tuning
+    if let Some(step) = key_step(&kb, KeyCode::Z, KeyCode::X) {
+        settings.asteroid_speed = (settings.asteroid_speed + step * PRACTICE_ASTEROID_SPEED_STEP)
+            .clamp(PRACTICE_ASTEROID_SPEED_STEP, PRACTICE_ASTEROID_SPEED_MAX);
+    }
+    if let Some(step) = key_step(&kb, KeyCode::C, KeyCode::V) {
+        settings.alien_aim_error = (settings.alien_aim_error + step * PRACTICE_ALIEN_AIM_ERROR_STEP)
+            .clamp(0.0, PRACTICE_ALIEN_AIM_ERROR_MAX);
+    }
+    if let Some(step) = key_step(&kb, KeyCode::N, KeyCode::M) {
+        settings.alien_fire_rate = (settings.alien_fire_rate + step * PRACTICE_ALIEN_FIRE_RATE_STEP)
+            .clamp(PRACTICE_ALIEN_FIRE_RATE_MIN, PRACTICE_ALIEN_FIRE_RATE_MAX);
+    }
+}
+
+/// -1 or 1 if the decrease or increase key was just pressed
+fn key_step(kb: &Input<KeyCode>, decrease: KeyCode, increase: KeyCode) -> Option<f32> {
+    match (kb.just_pressed(decrease), kb.just_pressed(increase)) {
+        (true, false) => Some(-1.),
+        (false, true) => Some(1.),
+        _ => None,
+    }
+}
+
+fn practice_mouse_system(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    assets: Res<GameAssets>,
+    settings: Res<PracticeSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single()) else { return };
+    let Some(position) = window.cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor)) else { return };
+
+    // Spawn an asteroid under the cursor
+    let mut rng = thread_rng();
+    spawn_asteroid(&mut commands, &assets.asteroid, AsteroidSpawn {
+        size: settings.asteroid_size,
+        material: AsteroidMaterial::Rock,
+        shape: settings.asteroid_shape,
+        position,
+        velocity: random_asteroid_velocity(&mut rng) * settings.asteroid_speed,
+        rotation: random_asteroid_rotation(&mut rng),
+        heading_angle: 0.,
+        invulnerable: None,
+    });
+}
+
+fn practice_asteroid_speed_system(
+    mut game: ResMut<GameManager>,
+    settings: Res<PracticeSettings>,
+    mut asteroids: Query<&mut Movable, With<Asteroid>>,
+) {
+    if game.asteroid_speed == settings.asteroid_speed {
+        return;
+    }
+    // Speed up (or slow down) everything already on screen
+    // NOTE: The speed never reaches zero, so this can always be undone
+    let scale = settings.asteroid_speed / game.asteroid_speed;
+    for mut movable in asteroids.iter_mut() {
+        movable.velocity *= scale;
+    }
+    game.asteroid_speed = settings.asteroid_speed;
+}
+
+fn practice_alien_settings_system(
+    settings: Res<PracticeSettings>,
+    mut ufos: Query<(&mut AlienUfo, &mut BulletController)>,
+) {
+    for (mut ufo, mut controller) in ufos.iter_mut() {
+        if settings.is_changed() || ufo.is_added() {
+            controller.set_fire_rate(ufo.variant().fire_rate() * settings.alien_fire_rate);
+            ufo.aim_error = settings.alien_aim_error;
+        }
+    }
+}
+
+// Side panel
+
+#[derive(Component)]
+struct PracticePanel;
+
+fn practice_panel_setup_system(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
+
+    commands.spawn((
+        PracticePanel,
+        // NOTE: Text is set by `practice_panel_update_system`
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_light,
+                font_size: 20.0,
+                color: theme.text_secondary,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            right: Val::Px(15.0),
+            ..default()
+        }),
+        ThemedText(vec![ThemeColor::TextSecondary]),
+    ));
+}
+
+fn practice_panel_update_system(
+    settings: Res<PracticeSettings>,
+    mut query: Query<&mut Text, With<PracticePanel>>
+) {
+    for mut text in query.iter_mut() {
+        if settings.is_changed() || text.is_added() {
+            write_practice_panel(&mut text.sections[0].value, &settings);
+        }
+    }
+}
+
+fn write_practice_panel(output: &mut String, settings: &PracticeSettings) {
+    use std::fmt::Write;
+    output.clear();
+    writeln!(output, "PRACTICE").unwrap();
+    writeln!(output, "[click] asteroid").unwrap();
+    writeln!(output, "[1-3] size: {:?}", settings.asteroid_size).unwrap();
+    writeln!(output, "[tab] shape: {:?}", settings.asteroid_shape).unwrap();
+    writeln!(output, "[z/x] speed: {:.2}x", settings.asteroid_speed).unwrap();
+    writeln!(output, "[u] UFO").unwrap();
+    writeln!(output, "[c/v] UFO aim error: {:.1}", settings.alien_aim_error).unwrap();
+    writeln!(output, "[n/m] UFO fire rate: {:.2}x", settings.alien_fire_rate).unwrap();
+    writeln!(output, "[p] asteroid swarm").unwrap();
+}
+
+fn practice_panel_destroy_system(mut commands: Commands, query: Query<Entity, With<PracticePanel>>) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+}
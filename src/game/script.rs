@@ -0,0 +1,504 @@
+use std::sync::{Arc, Mutex};
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use rhai::{AST, Dynamic, Engine, EvalAltResult, FLOAT, FnPtr, INT};
+use crate::AppState;
+use crate::asset_paths;
+use super::FrameStage;
+use super::alien::{AlienUfoVariant, spawn_alien_ufo};
+use super::assets::GameAssets;
+use super::asteroid::{AsteroidShapeId, AsteroidSize, AsteroidSpawn, spawn_asteroid};
+use super::boss::Mothership;
+use super::manager::{GameCleanup, GameManager, GameRng, WorldBoundaries, random_alien_ufo_spawn, random_asteroid_rotation, random_asteroid_velocity};
+use super::util::*;
+
+// Scripted waves and encounters
+//
+// `scripts/waves.rhai` is a Rhai script (https://rhai.rs) which runs at the start of each game,
+// on top of the regular waves, and registers events:
+//
+//     // At t=10s, three large asteroids from the left
+//     at(10, || spawn_asteroids(3, "large", "left"));
+//     // Once the player has 500 points, a small UFO
+//     when(|| score() > 500, || spawn_ufo("small"));
+//
+// Triggers: `at(secs, f)`, `after(secs, f)`, `every(secs, f)` and `when(|| condition, f)`.
+// Actions: `spawn_asteroids(count, size, side)` and `spawn_ufo(variant)`, where side is one of
+// "left", "right", "top", "bottom" or "any".
+// Queries: `time()`, `score()`, `wave()`, `lives()`, `asteroids()`, `boss_active()` and `mode()`.
+//
+// With the `dev-tools` feature, the script is re-read every second, and restarts (skipping events
+// already in the past) when it changes. Errors are shown in the HUD, and the last working version keeps running.
+
+#[cfg(feature = "dev-tools")]
+const SCRIPT_RELOAD_POLL_SECS: f32 = 1.0;
+/// Stops runaway scripts (e.g. an infinite loop) from freezing the game
+const SCRIPT_MAX_OPERATIONS: u64 = 100_000;
+/// The most asteroids a single `spawn_asteroids` call can spawn
+const SCRIPT_MAX_SPAWN_COUNT: INT = 50;
+/// Asteroids spawn this far outside the edge of the screen
+const SCRIPT_SPAWN_MARGIN: f32 = 10.0;
+/// Asteroids head into the screen within this many radians of straight on
+const SCRIPT_SPAWN_SPREAD: f32 = 0.5;
+
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WaveScript>();
+        app.init_asset_loader::<WaveScriptLoader>();
+        app.init_resource::<ScriptStatus>();
+        app.insert_resource(ScriptRuntime::new());
+        app.add_systems(Startup, script_load_system);
+        app.add_systems(Update, script_compile_system);
+        // NOTE: The asset watcher is off, so the file is polled instead (only while developing)
+        #[cfg(feature = "dev-tools")]
+        app.add_systems(Update, script_reload_system.before(script_compile_system));
+        app.add_systems(
+            Update,
+            (
+                script_update_system
+                    .in_set(FrameStage::Start),
+
+                script_actions_system
+                    .in_set(FrameStage::Start)
+                    .after(script_update_system),
+            )
+            .run_if(in_state(AppState::Game))
+            .run_if(has_scripted_waves)
+        );
+        app.add_systems(GameCleanup, script_teardown_system);
+    }
+}
+
+fn has_scripted_waves(game: Option<Res<GameManager>>) -> bool {
+    game.is_some_and(|game| game.mode().has_waves())
+}
+
+/// Problems with the wave script, for the HUD
+#[derive(Resource, Default)]
+pub struct ScriptStatus {
+    /// The most recent compile or runtime error
+    pub error: Option<String>,
+}
+
+// Script files
+
+#[derive(TypeUuid, TypePath)]
+#[uuid = "b3e1f6a2-4c8d-4e57-9a0b-7d2c5f1e8a36"]
+pub struct WaveScript {
+    pub source: String,
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            // NOTE: Compiled by `script_compile_system`, so errors can be shown in the HUD
+            let source = std::str::from_utf8(bytes)?.to_string();
+            load_context.set_default_asset(LoadedAsset::new(WaveScript { source }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+#[derive(Resource)]
+struct WaveScriptFile {
+    handle: Handle<WaveScript>,
+    #[cfg(feature = "dev-tools")]
+    reload_timer: Timer,
+}
+
+// Script state
+
+/// What scripts can see of the game, updated before they run
+#[derive(Default, Clone)]
+struct ScriptGameView {
+    elapsed_secs: f32,
+    score: u32,
+    wave: u32,
+    lives: u32,
+    asteroids: u32,
+    boss_active: bool,
+    mode: &'static str,
+}
+
+enum ScriptTrigger {
+    /// Once the game has run this long
+    At(f32),
+    /// Repeatedly, starting at `next`
+    Every { secs: f32, next: f32 },
+    /// Once the script function returns true
+    When(FnPtr),
+}
+
+struct ScriptEvent {
+    trigger: ScriptTrigger,
+    action: FnPtr,
+}
+
+enum ScriptAction {
+    SpawnAsteroids { count: u32, size: AsteroidSize, side: ScriptSide },
+    SpawnAlienUfo(AlienUfoVariant),
+}
+
+#[derive(Clone, Copy)]
+enum ScriptSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Any,
+}
+
+impl ScriptSide {
+    const EDGES: [Self; 4] = [ Self::Left, Self::Right, Self::Top, Self::Bottom ];
+}
+
+/// Shared between the engine's registered functions and the systems below
+#[derive(Default)]
+struct ScriptContext {
+    game: ScriptGameView,
+    events: Vec<ScriptEvent>,
+    actions: Vec<ScriptAction>,
+}
+
+type SharedScriptContext = Arc<Mutex<ScriptContext>>;
+
+struct CompiledScript {
+    source: String,
+    ast: AST,
+}
+
+#[derive(Resource)]
+struct ScriptRuntime {
+    engine: Engine,
+    context: SharedScriptContext,
+    script: Option<CompiledScript>,
+    /// Whether the script has run for the current game
+    started: bool,
+    /// Set when the script is reloaded part-way through a game
+    skip_past_events: bool,
+}
+
+impl ScriptRuntime {
+    fn new() -> Self {
+        let context = SharedScriptContext::default();
+        Self {
+            engine: create_engine(&context),
+            context,
+            script: None,
+            started: false,
+            skip_past_events: false,
+        }
+    }
+
+    fn context(&self) -> std::sync::MutexGuard<'_, ScriptContext> {
+        self.context.lock().unwrap()
+    }
+
+    /// Runs the top level of the script, which registers its events
+    fn start(&mut self) -> Result<(), Box<EvalAltResult>> {
+        self.started = true;
+        self.context().events.clear();
+        let Some(script) = &self.script else { return Ok(()) };
+        self.engine.run_ast(&script.ast)?;
+        if std::mem::take(&mut self.skip_past_events) {
+            let mut context = self.context();
+            let elapsed_secs = context.game.elapsed_secs;
+            context.events.retain(|event| !matches!(event.trigger, ScriptTrigger::At(secs) if secs < elapsed_secs));
+        }
+        Ok(())
+    }
+
+    /// Runs the actions of any events which have triggered
+    fn update(&mut self) -> Result<(), Box<EvalAltResult>> {
+        let Some(script) = &self.script else { return Ok(()) };
+        // NOTE: Taken out of the context, as the script functions called below need to lock it
+        let mut events = std::mem::take(&mut self.context().events);
+        let elapsed_secs = self.context().game.elapsed_secs;
+        let mut result = Ok(());
+        events.retain_mut(|event| {
+            if result.is_err() {
+                return true;
+            }
+            let (fire, keep) = match &mut event.trigger {
+                ScriptTrigger::At(secs) => (elapsed_secs >= *secs, elapsed_secs < *secs),
+                ScriptTrigger::Every { secs, next } => {
+                    let fire = elapsed_secs >= *next;
+                    if fire {
+                        *next += *secs;
+                    }
+                    (fire, true)
+                },
+                ScriptTrigger::When(condition) => match condition.call::<bool>(&self.engine, &script.ast, ()) {
+                    Ok(fire) => (fire, !fire),
+                    Err(err) => {
+                        result = Err(err);
+                        return false;
+                    },
+                },
+            };
+            if fire {
+                if let Err(err) = event.action.call::<Dynamic>(&self.engine, &script.ast, ()) {
+                    result = Err(err);
+                }
+            }
+            keep
+        });
+        // Keep any events which the actions registered
+        let mut context = self.context();
+        events.append(&mut context.events);
+        context.events = events;
+        result
+    }
+}
+
+// Script API
+
+fn create_engine(context: &SharedScriptContext) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(SCRIPT_MAX_OPERATIONS);
+    engine.on_print(|text| info!("{}: {}", asset_paths::SCRIPT_WAVES, text));
+    engine.on_debug(|text, _, position| debug!("{}:{}: {}", asset_paths::SCRIPT_WAVES, position, text));
+
+    // Triggers
+    register_timer(&mut engine, context, "at", |secs, _| Ok(ScriptTrigger::At(secs)));
+    register_timer(&mut engine, context, "after", |secs, game| Ok(ScriptTrigger::At(game.elapsed_secs + secs)));
+    register_timer(&mut engine, context, "every", |secs, game| match secs > 0. {
+        true => Ok(ScriptTrigger::Every { secs, next: game.elapsed_secs + secs }),
+        false => Err("every() needs an interval of more than 0 seconds".into()),
+    });
+    let c = context.clone();
+    engine.register_fn("when", move |condition: FnPtr, action: FnPtr| {
+        c.lock().unwrap().events.push(ScriptEvent { trigger: ScriptTrigger::When(condition), action });
+    });
+
+    // Actions
+    let c = context.clone();
+    engine.register_fn("spawn_asteroids", move |count: INT, size: &str, side: &str| -> Result<(), Box<EvalAltResult>> {
+        if !(0..=SCRIPT_MAX_SPAWN_COUNT).contains(&count) {
+            return Err(format!("spawn_asteroids() can spawn between 0 and {SCRIPT_MAX_SPAWN_COUNT} asteroids, not {count}").into());
+        }
+        let size = parse_name("asteroid size", size, &[
+            ("small", AsteroidSize::Small),
+            ("medium", AsteroidSize::Medium),
+            ("large", AsteroidSize::Large),
+        ])?;
+        let side = parse_name("side", side, &[
+            ("left", ScriptSide::Left),
+            ("right", ScriptSide::Right),
+            ("top", ScriptSide::Top),
+            ("bottom", ScriptSide::Bottom),
+            ("any", ScriptSide::Any),
+        ])?;
+        c.lock().unwrap().actions.push(ScriptAction::SpawnAsteroids { count: count as u32, size, side });
+        Ok(())
+    });
+    let c = context.clone();
+    engine.register_fn("spawn_ufo", move |variant: &str| -> Result<(), Box<EvalAltResult>> {
+        let variant = parse_name("UFO variant", variant, &[
+            ("large", AlienUfoVariant::Large),
+            ("small", AlienUfoVariant::Small),
+        ])?;
+        c.lock().unwrap().actions.push(ScriptAction::SpawnAlienUfo(variant));
+        Ok(())
+    });
+
+    // Queries
+    let c = context.clone();
+    engine.register_fn("time", move || c.lock().unwrap().game.elapsed_secs as FLOAT);
+    let c = context.clone();
+    engine.register_fn("score", move || c.lock().unwrap().game.score as INT);
+    let c = context.clone();
+    engine.register_fn("wave", move || c.lock().unwrap().game.wave as INT);
+    let c = context.clone();
+    engine.register_fn("lives", move || c.lock().unwrap().game.lives as INT);
+    let c = context.clone();
+    engine.register_fn("asteroids", move || c.lock().unwrap().game.asteroids as INT);
+    let c = context.clone();
+    engine.register_fn("boss_active", move || c.lock().unwrap().game.boss_active);
+    let c = context.clone();
+    engine.register_fn("mode", move || c.lock().unwrap().game.mode.to_string());
+
+    engine
+}
+
+type ScriptTriggerFn = fn(f32, &ScriptGameView) -> Result<ScriptTrigger, Box<EvalAltResult>>;
+
+/// Registers `name(secs, action)`, taking whole or fractional seconds
+fn register_timer(engine: &mut Engine, context: &SharedScriptContext, name: &str, trigger: ScriptTriggerFn) {
+    let c = context.clone();
+    engine.register_fn(name, move |secs: FLOAT, action: FnPtr| add_timer(&c, trigger, secs as f32, action));
+    let c = context.clone();
+    engine.register_fn(name, move |secs: INT, action: FnPtr| add_timer(&c, trigger, secs as f32, action));
+}
+
+fn add_timer(context: &SharedScriptContext, trigger: ScriptTriggerFn, secs: f32, action: FnPtr) -> Result<(), Box<EvalAltResult>> {
+    let mut context = context.lock().unwrap();
+    let trigger = trigger(secs, &context.game)?;
+    context.events.push(ScriptEvent { trigger, action });
+    Ok(())
+}
+
+fn parse_name<T: Copy>(kind: &str, name: &str, values: &[(&str, T)]) -> Result<T, Box<EvalAltResult>> {
+    values.iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, value)| value)
+        .ok_or_else(|| {
+            let expected: Vec<_> = values.iter().map(|(n, _)| *n).collect();
+            format!("unknown {kind} '{name}' (expected one of: {})", expected.join(", ")).into()
+        })
+}
+
+// Systems
+
+fn script_load_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WaveScriptFile {
+        handle: asset_server.load(asset_paths::SCRIPT_WAVES),
+        #[cfg(feature = "dev-tools")]
+        reload_timer: Timer::from_seconds(SCRIPT_RELOAD_POLL_SECS, TimerMode::Repeating),
+    });
+}
+
+/// Polls the script file for changes
+#[cfg(feature = "dev-tools")]
+fn script_reload_system(time: Res<Time>, asset_server: Res<AssetServer>, mut file: ResMut<WaveScriptFile>) {
+    if file.reload_timer.tick(time.delta()).just_finished() {
+        asset_server.reload_asset(asset_paths::SCRIPT_WAVES);
+    }
+}
+
+fn script_compile_system(
+    mut events: EventReader<AssetEvent<WaveScript>>,
+    scripts: Res<Assets<WaveScript>>,
+    file: Res<WaveScriptFile>,
+    mut runtime: ResMut<ScriptRuntime>,
+    mut status: ResMut<ScriptStatus>,
+) {
+    for event in events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else { continue };
+        if *handle != file.handle {
+            continue;
+        }
+        let Some(script) = scripts.get(handle) else { continue };
+        // NOTE: Every poll counts as a modification, even if nothing has changed
+        if runtime.script.as_ref().is_some_and(|s| s.source == script.source) {
+            continue;
+        }
+        match runtime.engine.compile(&script.source) {
+            Ok(ast) => {
+                info!("Loaded {}", asset_paths::SCRIPT_WAVES);
+                runtime.script = Some(CompiledScript { source: script.source.clone(), ast });
+                // Restart, without replaying anything which has already happened this game
+                runtime.skip_past_events = runtime.started;
+                runtime.started = false;
+                status.error = None;
+            },
+            Err(err) => {
+                // Keep running the last version which compiled
+                status.error = Some(format!("{}: {}", asset_paths::SCRIPT_WAVES, err));
+            },
+        }
+    }
+}
+
+fn script_update_system(
+    game: Res<GameManager>,
+    motherships: Query<(), With<Mothership>>,
+    mut runtime: ResMut<ScriptRuntime>,
+    mut status: ResMut<ScriptStatus>,
+) {
+    runtime.context().game = ScriptGameView {
+        elapsed_secs: game.elapsed_secs,
+        score: game.player_points,
+        wave: game.wave,
+        lives: game.player_lives_remaining,
+        asteroids: game.debug_asteroid_count_on_screen,
+        boss_active: !motherships.is_empty(),
+        mode: game.mode().name(),
+    };
+    let result = match runtime.started {
+        true => runtime.update(),
+        false => runtime.start(),
+    };
+    if let Err(err) = result {
+        status.error = Some(format!("{}: {}", asset_paths::SCRIPT_WAVES, err));
+    }
+}
+
+fn script_actions_system(
+    mut commands: Commands,
+    runtime: Res<ScriptRuntime>,
+    game: Res<GameManager>,
+    world_boundaries: Res<WorldBoundaries>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    let rng = &mut rng.0;
+    let actions = std::mem::take(&mut runtime.context().actions);
+    for action in actions {
+        match action {
+            ScriptAction::SpawnAsteroids { count, size, side } => {
+                let material_chances = game.asteroid_material_chances();
+                for _ in 0..count {
+                    let (position, direction) = random_side_position(rng, &world_boundaries, side);
+                    let speed = random_asteroid_velocity(rng).length() * game.asteroid_speed;
+                    spawn_asteroid(&mut commands, &assets.asteroid, AsteroidSpawn {
+                        size,
+                        material: material_chances.pick(rng),
+                        shape: *rng.random_choice(&AsteroidShapeId::VALUES).unwrap(),
+                        position,
+                        velocity: direction * speed,
+                        rotation: random_asteroid_rotation(rng),
+                        heading_angle: 0.,
+                        invulnerable: None,
+                    });
+                }
+            },
+            ScriptAction::SpawnAlienUfo(variant) => {
+                let spawn = random_alien_ufo_spawn(rng, &world_boundaries, variant, game.alien_avoids_asteroids());
+                spawn_alien_ufo(&mut commands, &assets.alien, spawn);
+            },
+        }
+    }
+}
+
+/// A position just off the given side of the screen, and a direction heading into it
+fn random_side_position(rng: &mut impl RngUtil, world_boundaries: &WorldBoundaries, side: ScriptSide) -> (Vec2, Vec2) {
+    let side = match side {
+        ScriptSide::Any => *rng.random_choice(&ScriptSide::EDGES).unwrap(),
+        side => side,
+    };
+    let along = rng.random_f32() * 2. - 1.;
+    let (position, inwards) = match side {
+        ScriptSide::Left => (Vec2::new(world_boundaries.left - SCRIPT_SPAWN_MARGIN, along * world_boundaries.top), Vec2::X),
+        ScriptSide::Right => (Vec2::new(world_boundaries.right + SCRIPT_SPAWN_MARGIN, along * world_boundaries.top), Vec2::NEG_X),
+        ScriptSide::Top => (Vec2::new(along * world_boundaries.right, world_boundaries.top + SCRIPT_SPAWN_MARGIN), Vec2::NEG_Y),
+        ScriptSide::Bottom | ScriptSide::Any => (Vec2::new(along * world_boundaries.right, world_boundaries.bottom - SCRIPT_SPAWN_MARGIN), Vec2::Y),
+    };
+    let spread = (rng.random_f32() * 2. - 1.) * SCRIPT_SPAWN_SPREAD;
+    (position, Vec2::from_angle(spread).rotate(inwards))
+}
+
+// Teardown
+
+fn script_teardown_system(mut runtime: ResMut<ScriptRuntime>) {
+    runtime.started = false;
+    runtime.skip_past_events = false;
+    let mut context = runtime.context();
+    context.events.clear();
+    context.actions.clear();
+}
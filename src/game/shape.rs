@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use bevy_prototype_lyon::prelude::*;
+use serde::Deserialize;
+use super::collidable::Collider;
+use super::svg::svg_to_path;
+
+// Vector shapes loaded from `.shape.ron` files
+//
+// Example:
+// (
+//     // See: https://yqnn.github.io/svg-path-editor/
+//     path: "M 3 0 L -2 -2 M -1 -1.6 L -1 1.6 M 3 0 L -2 2",
+//     size: (4.0, 6.0),
+//     collider: Circle(radius: 1.5),
+// )
+
+#[derive(TypeUuid, TypePath)]
+#[uuid = "5d7f3c1e-8a4b-4f0e-9b61-2c3f6e8d1a97"]
+pub struct VectorShape {
+    /// The outline of the shape
+    pub path: Path,
+    /// (w, h) of the shape
+    pub size: Vec2,
+    /// Collision shape, relative to the centre of the shape
+    pub collider: ShapeCollider,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ShapeCollider {
+    Circle { radius: f32 },
+    Capsule { arm: (f32, f32), radius: f32 },
+}
+
+impl ShapeCollider {
+    pub fn to_collider(self, position: Vec2, scale: f32) -> Collider {
+        match self {
+            ShapeCollider::Circle { radius } => Collider::circle(position, radius * scale),
+            ShapeCollider::Capsule { arm: (x, y), radius } => Collider::capsule(position, Vec2::new(x, y) * scale, radius * scale),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VectorShapeFile {
+    path: String,
+    size: (f32, f32),
+    collider: ShapeCollider,
+}
+
+#[derive(Default)]
+pub struct VectorShapeLoader;
+
+impl AssetLoader for VectorShapeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file = ron::de::from_bytes::<VectorShapeFile>(bytes)?;
+            let shape = VectorShape {
+                path: svg_to_path(&file.path)?,
+                size: Vec2::new(file.size.0, file.size.1),
+                collider: file.collider,
+            };
+            load_context.set_default_asset(LoadedAsset::new(shape));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["shape.ron"]
+    }
+}
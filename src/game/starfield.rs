@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
+use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::manager::WorldBoundaries;
+use super::movable::Movable;
+use super::player::PlayerRocket;
+use super::util::*;
+
+// Starfield
+//
+// Several layers of seeded stars behind the game. Each layer drifts against the velocity of the
+// player's rocket (nearer layers drift faster) and wraps around the edges of the world.
+// Stars within a layer are split into a few groups which twinkle out of phase with each other.
+
+const STAR_TWINKLE_GROUPS: usize = 3;
+const STAR_TWINKLE_FREQUENCY: f32 = 0.7; // per second
+const STAR_TWINKLE_DEPTH: f32 = 0.4; // fraction of alpha lost at the bottom of a twinkle
+const STAR_LENGTH: f32 = 0.05;
+
+struct StarLayerConfig {
+    /// Stars per square world unit
+    density: f32,
+    /// Fraction of the rocket's velocity this layer drifts at
+    parallax: f32,
+    alpha: f32,
+    line_width: f32,
+    z: f32,
+}
+
+const STAR_LAYERS: [StarLayerConfig; 3] = [
+    // Far
+    StarLayerConfig { density: 0.004, parallax: 0.02, alpha: 0.35, line_width: 0.3, z: 1.0 },
+    // Middle
+    StarLayerConfig { density: 0.002, parallax: 0.05, alpha: 0.55, line_width: 0.4, z: 1.5 },
+    // Near
+    StarLayerConfig { density: 0.001, parallax: 0.1, alpha: 0.8, line_width: 0.5, z: 2.0 },
+];
+
+pub struct StarfieldPlugin;
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Starfield::new(rand::random()));
+        app.add_systems(
+            Update,
+            (
+                starfield_generate_system
+                    .after(FrameStage::Start),
+
+                starfield_drift_system
+                    .after(starfield_generate_system)
+                    .after(FrameStage::Movement),
+
+                starfield_twinkle_system,
+            )
+            .run_if(in_state(AppState::Game))
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct Starfield {
+    seed: u64,
+    /// (w, h) of the world the stars were generated for
+    size: Vec2,
+    /// Drift of each layer
+    offsets: [Vec2; STAR_LAYERS.len()],
+}
+
+impl Starfield {
+    fn new(seed: u64) -> Self {
+        Self { seed, size: Vec2::ZERO, offsets: default() }
+    }
+}
+
+#[derive(Component)]
+struct StarLayer {
+    layer: usize,
+    group: usize,
+    /// Star positions, relative to the bottom left corner of the world
+    stars: Vec<Vec2>,
+}
+
+// Generation
+
+fn starfield_generate_system(
+    mut commands: Commands,
+    mut starfield: ResMut<Starfield>,
+    world_boundaries: Res<WorldBoundaries>,
+    layers: Query<Entity, With<StarLayer>>,
+) {
+    let size = world_boundaries.size();
+    if size == starfield.size || size.x <= 0. || size.y <= 0. {
+        return;
+    }
+    starfield.size = size;
+
+    for entity in layers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // NOTE: The same seed is used each time, so a given world size always produces the same stars
+    let mut rng = StdRng::seed_from_u64(starfield.seed);
+    for (layer, config) in STAR_LAYERS.iter().enumerate() {
+        let count = (size.x * size.y * config.density) as usize;
+        let mut groups = vec![Vec::new(); STAR_TWINKLE_GROUPS];
+        for _ in 0..count {
+            let star = Vec2::new(rng.random_f32(), rng.random_f32()) * size;
+            let group = (rng.random_f32() * STAR_TWINKLE_GROUPS as f32) as usize;
+            groups[group.min(STAR_TWINKLE_GROUPS - 1)].push(star);
+        }
+        for (group, stars) in groups.into_iter().enumerate() {
+            let mut stroke = Stroke::new(Color::WHITE.with_a(config.alpha), config.line_width);
+            stroke.options = stroke.options.with_line_cap(LineCap::Round);
+            commands.spawn((
+                StarLayer { layer, group, stars },
+                ShapeBundle {
+                    transform: Transform::from_xyz(0., 0., config.z),
+                    ..default()
+                },
+                stroke,
+                Themed(ThemeColor::Star),
+            ));
+        }
+    }
+}
+
+// Drift
+
+fn starfield_drift_system(
+    time: Res<Time>,
+    mut starfield: ResMut<Starfield>,
+    world_boundaries: Res<WorldBoundaries>,
+    rocket_query: Query<&Movable, With<PlayerRocket>>,
+    mut layers: Query<(Ref<StarLayer>, &mut Path)>,
+) {
+    let size = starfield.size;
+    if size == Vec2::ZERO {
+        return;
+    }
+    let velocity = rocket_query.get_single().map(|m| m.velocity).unwrap_or(Vec2::ZERO);
+    let t_secs = time.delta_seconds();
+    for (offset, config) in starfield.offsets.iter_mut().zip(STAR_LAYERS.iter()) {
+        *offset = (*offset - velocity * config.parallax * t_secs).rem_euclid(size);
+    }
+
+    let moved = velocity != Vec2::ZERO;
+    let origin = Vec2::new(world_boundaries.left, world_boundaries.bottom);
+    for (star_layer, mut path) in layers.iter_mut() {
+        if !moved && !star_layer.is_added() {
+            continue;
+        }
+        let offset = starfield.offsets[star_layer.layer];
+        let mut builder = tess::path::Path::builder();
+        for star in star_layer.stars.iter() {
+            let p = origin + (*star + offset).rem_euclid(size);
+            builder.begin(tess::math::point(p.x, p.y));
+            builder.line_to(tess::math::point(p.x + STAR_LENGTH, p.y));
+            builder.end(false);
+        }
+        path.0 = builder.build();
+    }
+}
+
+// Twinkle
+
+fn starfield_twinkle_system(time: Res<Time>, mut layers: Query<(&StarLayer, &mut Stroke)>) {
+    let t_secs = time.elapsed_seconds();
+    for (star_layer, mut stroke) in layers.iter_mut() {
+        let phase = star_layer.group as f32 / STAR_TWINKLE_GROUPS as f32 * std::f32::consts::TAU;
+        let wave = ((t_secs * STAR_TWINKLE_FREQUENCY * std::f32::consts::TAU + phase).sin() + 1.0) / 2.0;
+        let alpha = STAR_LAYERS[star_layer.layer].alpha * (1.0 - STAR_TWINKLE_DEPTH * wave);
+        stroke.color.set_a(alpha);
+    }
+}
@@ -1,99 +1,416 @@
 use bevy_prototype_lyon::prelude::*;
+use tess::geom::ArcFlags;
+use tess::math::{Angle, Point, Transform, Vector, point, vector};
+use tess::path::traits::SvgPathBuilder;
 
-type BoxError = Box<dyn std::error::Error + 'static>;
+// Errors
 
-struct SvgPoint(f32, f32);
+#[derive(Debug, Clone, PartialEq)]
+pub enum SvgPathErrorKind {
+    /// A character which is not a command, number or separator
+    UnexpectedCharacter(char),
+    /// The path ended in the middle of a command
+    UnexpectedEnd,
+    /// A command was missing one of its numeric arguments
+    ExpectedNumber,
+    /// An arc command was missing one of its `0` / `1` flags
+    ExpectedFlag,
+    /// A path must begin with a `M` or `m` command
+    ExpectedMoveTo,
+}
+
+/// An error encountered while parsing SVG path data.
+/// `position` is the byte offset into the input at which the error was detected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgPathError {
+    pub kind: SvgPathErrorKind,
+    pub position: usize,
+}
 
-impl SvgPoint {
-    fn to_vec2(&self) -> bevy::prelude::Vec2 {
-        // NOTE: SVG uses {x right, y down}, bevy uses {x right, y up}
-        // Flip the y coordinates
-        bevy::prelude::Vec2::new(self.0, -self.1)
+impl std::fmt::Display for SvgPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use SvgPathErrorKind::*;
+        match self.kind {
+            UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'")?,
+            UnexpectedEnd => write!(f, "unexpected end of path data")?,
+            ExpectedNumber => write!(f, "expected a number")?,
+            ExpectedFlag => write!(f, "expected an arc flag (0 or 1)")?,
+            ExpectedMoveTo => write!(f, "path data must begin with a move-to command")?,
+        }
+        write!(f, " at position {}", self.position)
     }
 }
 
+impl std::error::Error for SvgPathError {}
+
+// Instructions
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Coords {
+    Absolute,
+    Relative,
+}
+
 struct SvgCubicBezierCurveTo {
-    ctrl1: SvgPoint,
-    ctrl2: SvgPoint,
-    to: SvgPoint,
+    ctrl1: Point,
+    ctrl2: Point,
+    to: Point,
+}
+
+struct SvgSmoothCubicBezierCurveTo {
+    ctrl2: Point,
+    to: Point,
 }
 
 struct SvgQuadraticBezierCurveTo {
-    ctrl: SvgPoint,
-    to: SvgPoint,
+    ctrl: Point,
+    to: Point,
+}
+
+struct SvgArcTo {
+    radii: Vector,
+    x_rotation_degrees: f32,
+    flags: ArcFlags,
+    to: Point,
 }
 
 enum Instruction {
-    MoveTo(SvgPoint),
-    LineTo(SvgPoint),
-    CubicCurveTo(SvgCubicBezierCurveTo),
-    QuadraticCurveTo(SvgQuadraticBezierCurveTo),
+    MoveTo(Coords, Point),
+    LineTo(Coords, Point),
+    HorizontalLineTo(Coords, f32),
+    VerticalLineTo(Coords, f32),
+    CubicCurveTo(Coords, SvgCubicBezierCurveTo),
+    SmoothCubicCurveTo(Coords, SvgSmoothCubicBezierCurveTo),
+    QuadraticCurveTo(Coords, SvgQuadraticBezierCurveTo),
+    SmoothQuadraticCurveTo(Coords, Point),
+    ArcTo(Coords, SvgArcTo),
     ClosePath,
 }
 
-trait SvgParse {
-    fn parse_point(&mut self) -> Result<SvgPoint, BoxError>;
-    fn parse_cubic_curve_to(&mut self) -> Result<SvgCubicBezierCurveTo, BoxError>;
-    fn parse_quadratic_curve_to(&mut self) -> Result<SvgQuadraticBezierCurveTo, BoxError>;
+// Parsing
+
+struct Cursor<'a> {
+    input: &'a [u8],
+    position: usize,
 }
 
-impl<'a, T: Iterator<Item=&'a str>> SvgParse for T {
-    fn parse_point(&mut self) -> Result<SvgPoint, BoxError> {
-        let a = self.next().ok_or("x coord")?.parse::<f32>()?;
-        let b = self.next().ok_or("y coord")?.parse::<f32>()?;
-        Ok(SvgPoint(a, b))
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input: input.as_bytes(), position: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn error(&self, kind: SvgPathErrorKind) -> SvgPathError {
+        SvgPathError { kind, position: self.position }
+    }
+
+    fn unexpected(&self) -> SvgPathError {
+        match self.peek() {
+            Some(c) => self.error(SvgPathErrorKind::UnexpectedCharacter(c as char)),
+            None => self.error(SvgPathErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r' | b'\x0C') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace with at most one comma
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.position += 1;
+            self.skip_whitespace();
+        }
     }
 
-    fn parse_cubic_curve_to(&mut self) -> Result<SvgCubicBezierCurveTo, BoxError> {
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    /// Parses a number according to the SVG grammar, which does not require separators
+    /// between numbers when a sign or second decimal point makes the boundary unambiguous (e.g. `1-2.5.5`)
+    fn parse_number(&mut self) -> Result<f32, SvgPathError> {
+        self.skip_separator();
+        let start = self.position;
+        if let Some(b'-' | b'+') = self.peek() {
+            self.position += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.position = start;
+            return Err(match self.peek() {
+                None => self.error(SvgPathErrorKind::UnexpectedEnd),
+                Some(_) => self.error(SvgPathErrorKind::ExpectedNumber),
+            });
+        }
+        // Exponent (only consumed if followed by digits)
+        if let Some(b'e' | b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'-' | b'+') = self.peek() {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.position = mantissa_end;
+            }
+        }
+        // NOTE: The slice only contains ASCII digits, signs, '.' and 'e'
+        let text = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+        text.parse::<f32>().map_err(|_| SvgPathError { kind: SvgPathErrorKind::ExpectedNumber, position: start })
+    }
+
+    fn parse_flag(&mut self) -> Result<bool, SvgPathError> {
+        self.skip_separator();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            None => return Err(self.error(SvgPathErrorKind::UnexpectedEnd)),
+            Some(_) => return Err(self.error(SvgPathErrorKind::ExpectedFlag)),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+
+    fn parse_point(&mut self) -> Result<Point, SvgPathError> {
+        let x = self.parse_number()?;
+        let y = self.parse_number()?;
+        Ok(point(x, y))
+    }
+
+    fn parse_cubic_curve_to(&mut self) -> Result<SvgCubicBezierCurveTo, SvgPathError> {
         let ctrl1 = self.parse_point()?;
         let ctrl2 = self.parse_point()?;
         let to = self.parse_point()?;
         Ok(SvgCubicBezierCurveTo { ctrl1, ctrl2, to })
     }
 
-    fn parse_quadratic_curve_to(&mut self) -> Result<SvgQuadraticBezierCurveTo, BoxError> {
+    fn parse_smooth_cubic_curve_to(&mut self) -> Result<SvgSmoothCubicBezierCurveTo, SvgPathError> {
+        let ctrl2 = self.parse_point()?;
+        let to = self.parse_point()?;
+        Ok(SvgSmoothCubicBezierCurveTo { ctrl2, to })
+    }
+
+    fn parse_quadratic_curve_to(&mut self) -> Result<SvgQuadraticBezierCurveTo, SvgPathError> {
         let ctrl = self.parse_point()?;
         let to = self.parse_point()?;
         Ok(SvgQuadraticBezierCurveTo { ctrl, to })
     }
+
+    fn parse_arc_to(&mut self) -> Result<SvgArcTo, SvgPathError> {
+        let rx = self.parse_number()?;
+        let ry = self.parse_number()?;
+        let x_rotation_degrees = self.parse_number()?;
+        let large_arc = self.parse_flag()?;
+        let sweep = self.parse_flag()?;
+        let to = self.parse_point()?;
+        Ok(SvgArcTo { radii: vector(rx, ry), x_rotation_degrees, flags: ArcFlags { large_arc, sweep }, to })
+    }
 }
 
-fn parse_svg_instructions(path: &str) -> Result<Vec<Instruction>, BoxError> {
+fn parse_svg_instructions(path: &str) -> Result<Vec<Instruction>, SvgPathError> {
+    use Coords::*;
     let mut instructions = Vec::new();
-    let mut tokens = path.split(&[' ', '\n', '\r']).map(|s| s.trim()).filter(|s| s.len() > 0).into_iter();
-    while let Some(token) = tokens.next() {
-        instructions.push(match token {
-            "M" => Instruction::MoveTo(tokens.parse_point()?),
-            "L" => Instruction::LineTo(tokens.parse_point()?),
-            "C" => Instruction::CubicCurveTo(tokens.parse_cubic_curve_to()?),
-            "Q" => Instruction::QuadraticCurveTo(tokens.parse_quadratic_curve_to()?),
-            "Z" => Instruction::ClosePath,
-            ins => return Err(format!("Unknown instruction: {ins}").into())
+    let mut cursor = Cursor::new(path);
+    let mut previous_command: Option<u8> = None;
+    loop {
+        cursor.skip_whitespace();
+        let Some(next) = cursor.peek() else { break };
+
+        let command = if next.is_ascii_alphabetic() {
+            cursor.position += 1;
+            next
+        }
+        else {
+            // Numbers without a command repeat the previous command
+            // NOTE: Repeated move-to commands are treated as line-to commands
+            if previous_command.is_some() && cursor.at_number() {
+                match previous_command {
+                    Some(b'M') => b'L',
+                    Some(b'm') => b'l',
+                    Some(b'Z' | b'z') => return Err(cursor.unexpected()),
+                    Some(c) => c,
+                    None => unreachable!(),
+                }
+            }
+            else {
+                return Err(cursor.unexpected());
+            }
+        };
+
+        if previous_command.is_none() && !matches!(command, b'M' | b'm') {
+            return Err(SvgPathError { kind: SvgPathErrorKind::ExpectedMoveTo, position: cursor.position - 1 });
+        }
+
+        let coords = if command.is_ascii_lowercase() { Relative } else { Absolute };
+        instructions.push(match command.to_ascii_uppercase() {
+            b'M' => Instruction::MoveTo(coords, cursor.parse_point()?),
+            b'L' => Instruction::LineTo(coords, cursor.parse_point()?),
+            b'H' => Instruction::HorizontalLineTo(coords, cursor.parse_number()?),
+            b'V' => Instruction::VerticalLineTo(coords, cursor.parse_number()?),
+            b'C' => Instruction::CubicCurveTo(coords, cursor.parse_cubic_curve_to()?),
+            b'S' => Instruction::SmoothCubicCurveTo(coords, cursor.parse_smooth_cubic_curve_to()?),
+            b'Q' => Instruction::QuadraticCurveTo(coords, cursor.parse_quadratic_curve_to()?),
+            b'T' => Instruction::SmoothQuadraticCurveTo(coords, cursor.parse_point()?),
+            b'A' => Instruction::ArcTo(coords, cursor.parse_arc_to()?),
+            b'Z' => Instruction::ClosePath,
+            _ => {
+                cursor.position -= 1;
+                return Err(cursor.unexpected());
+            }
         });
+        previous_command = Some(command);
+        cursor.skip_separator();
+    }
+    if previous_command.is_none() {
+        return Err(cursor.error(SvgPathErrorKind::ExpectedMoveTo));
     }
     Ok(instructions)
 }
 
-pub fn simple_svg_to_path(path: &str) -> Path {
-    let mut p = PathBuilder::new();
-    for instruction in parse_svg_instructions(path).unwrap() {
+/// Parses SVG path data (the `d` attribute of a `<path>` element) into a lyon path.
+pub fn svg_to_path(path: &str) -> Result<Path, SvgPathError> {
+    use Coords::*;
+    let mut p = tess::path::Path::builder().with_svg();
+    for instruction in parse_svg_instructions(path)? {
         match instruction {
-            Instruction::MoveTo(to) => {
-                p.move_to(to.to_vec2());
-            },
-            Instruction::LineTo(to) => {
-                p.line_to(to.to_vec2());
-            },
-            Instruction::CubicCurveTo(c) => {
-                p.cubic_bezier_to(c.ctrl1.to_vec2(), c.ctrl2.to_vec2(), c.to.to_vec2());
-            },
-            Instruction::QuadraticCurveTo(c) => {
-                p.quadratic_bezier_to(c.ctrl.to_vec2(), c.to.to_vec2());
-            },
-            Instruction::ClosePath => {
-                p.close();
-            },
+            Instruction::MoveTo(Absolute, to) => { p.move_to(to); },
+            Instruction::MoveTo(Relative, to) => { p.relative_move_to(to.to_vector()); },
+            Instruction::LineTo(Absolute, to) => { p.line_to(to); },
+            Instruction::LineTo(Relative, to) => { p.relative_line_to(to.to_vector()); },
+            Instruction::HorizontalLineTo(Absolute, x) => { p.horizontal_line_to(x); },
+            Instruction::HorizontalLineTo(Relative, dx) => { p.relative_horizontal_line_to(dx); },
+            Instruction::VerticalLineTo(Absolute, y) => { p.vertical_line_to(y); },
+            Instruction::VerticalLineTo(Relative, dy) => { p.relative_vertical_line_to(dy); },
+            Instruction::CubicCurveTo(Absolute, c) => { p.cubic_bezier_to(c.ctrl1, c.ctrl2, c.to); },
+            Instruction::CubicCurveTo(Relative, c) => { p.relative_cubic_bezier_to(c.ctrl1.to_vector(), c.ctrl2.to_vector(), c.to.to_vector()); },
+            Instruction::SmoothCubicCurveTo(Absolute, c) => { p.smooth_cubic_bezier_to(c.ctrl2, c.to); },
+            Instruction::SmoothCubicCurveTo(Relative, c) => { p.smooth_relative_cubic_bezier_to(c.ctrl2.to_vector(), c.to.to_vector()); },
+            Instruction::QuadraticCurveTo(Absolute, c) => { p.quadratic_bezier_to(c.ctrl, c.to); },
+            Instruction::QuadraticCurveTo(Relative, c) => { p.relative_quadratic_bezier_to(c.ctrl.to_vector(), c.to.to_vector()); },
+            Instruction::SmoothQuadraticCurveTo(Absolute, to) => { p.smooth_quadratic_bezier_to(to); },
+            Instruction::SmoothQuadraticCurveTo(Relative, to) => { p.smooth_relative_quadratic_bezier_to(to.to_vector()); },
+            Instruction::ArcTo(Absolute, a) => { p.arc_to(a.radii, Angle::degrees(a.x_rotation_degrees), a.flags, a.to); },
+            Instruction::ArcTo(Relative, a) => { p.relative_arc_to(a.radii, Angle::degrees(a.x_rotation_degrees), a.flags, a.to.to_vector()); },
+            Instruction::ClosePath => { p.close(); },
         }
     }
-    p.build()
-}
\ No newline at end of file
+    // NOTE: SVG uses {x right, y down}, bevy uses {x right, y up}
+    // Flip the y coordinates once the whole path (including arcs) has been built
+    let flip_y = Transform::scale(1.0, -1.0);
+    Ok(Path(p.build().transformed(&flip_y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(path: &str) -> Vec<Instruction> {
+        parse_svg_instructions(path).unwrap()
+    }
+
+    fn parse_error(path: &str) -> SvgPathError {
+        parse_svg_instructions(path).err().expect("path should be rejected")
+    }
+
+    #[test]
+    fn splits_numbers_on_signs_and_decimal_points() {
+        let instructions = parse("M1-2.5.5.25");
+        assert_eq!(instructions.len(), 2);
+        assert!(matches!(instructions[0], Instruction::MoveTo(Coords::Absolute, to) if to == point(1., -2.5)));
+        assert!(matches!(instructions[1], Instruction::LineTo(Coords::Absolute, to) if to == point(0.5, 0.25)));
+    }
+
+    #[test]
+    fn parses_exponents() {
+        let instructions = parse("M1e2-2E-1");
+        assert!(matches!(instructions[0], Instruction::MoveTo(Coords::Absolute, to) if to == point(100., -0.2)));
+    }
+
+    #[test]
+    fn repeats_move_to_as_line_to() {
+        let instructions = parse("m 1 2 3,4 5 6 z");
+        assert_eq!(instructions.len(), 4);
+        assert!(matches!(instructions[0], Instruction::MoveTo(Coords::Relative, to) if to == point(1., 2.)));
+        assert!(matches!(instructions[1], Instruction::LineTo(Coords::Relative, to) if to == point(3., 4.)));
+        assert!(matches!(instructions[2], Instruction::LineTo(Coords::Relative, to) if to == point(5., 6.)));
+        assert!(matches!(instructions[3], Instruction::ClosePath));
+    }
+
+    #[test]
+    fn repeats_other_commands() {
+        let instructions = parse("M0 0 H1 2 v-3-4");
+        assert_eq!(instructions.len(), 5);
+        assert!(matches!(instructions[2], Instruction::HorizontalLineTo(Coords::Absolute, x) if x == 2.));
+        assert!(matches!(instructions[4], Instruction::VerticalLineTo(Coords::Relative, dy) if dy == -4.));
+    }
+
+    #[test]
+    fn parses_packed_arc_flags() {
+        let instructions = parse("M0 0a1 1 0 00 1 1A2,3 45 1,1 4,5");
+        assert_eq!(instructions.len(), 3);
+        let Instruction::ArcTo(Coords::Relative, ref arc) = instructions[1] else { panic!("expected a relative arc") };
+        assert_eq!(arc.radii, vector(1., 1.));
+        assert_eq!(arc.x_rotation_degrees, 0.);
+        assert!(!arc.flags.large_arc && !arc.flags.sweep);
+        assert_eq!(arc.to, point(1., 1.));
+        let Instruction::ArcTo(Coords::Absolute, ref arc) = instructions[2] else { panic!("expected an absolute arc") };
+        assert_eq!(arc.x_rotation_degrees, 45.);
+        assert!(arc.flags.large_arc && arc.flags.sweep);
+        assert_eq!(arc.to, point(4., 5.));
+    }
+
+    #[test]
+    fn rejects_bad_arc_flag() {
+        assert_eq!(parse_error("M0 0 a1 1 0 2 1 1 1"), SvgPathError { kind: SvgPathErrorKind::ExpectedFlag, position: 12 });
+    }
+
+    #[test]
+    fn requires_initial_move_to() {
+        assert_eq!(parse_error("L 1 2"), SvgPathError { kind: SvgPathErrorKind::ExpectedMoveTo, position: 0 });
+        assert_eq!(parse_error("  l 1 2"), SvgPathError { kind: SvgPathErrorKind::ExpectedMoveTo, position: 2 });
+        assert_eq!(parse_error(""), SvgPathError { kind: SvgPathErrorKind::ExpectedMoveTo, position: 0 });
+    }
+
+    #[test]
+    fn reports_unexpected_character() {
+        assert_eq!(parse_error("M 1 2 # 3"), SvgPathError { kind: SvgPathErrorKind::UnexpectedCharacter('#'), position: 6 });
+        assert_eq!(parse_error("M 1 2 X 3"), SvgPathError { kind: SvgPathErrorKind::UnexpectedCharacter('X'), position: 6 });
+        assert_eq!(parse_error("M 1 2 z 3"), SvgPathError { kind: SvgPathErrorKind::UnexpectedCharacter('3'), position: 8 });
+    }
+
+    #[test]
+    fn reports_missing_number() {
+        assert_eq!(parse_error("M 1 2 L 3 x"), SvgPathError { kind: SvgPathErrorKind::ExpectedNumber, position: 10 });
+        assert_eq!(parse_error("M 1 -.e"), SvgPathError { kind: SvgPathErrorKind::ExpectedNumber, position: 4 });
+    }
+
+    #[test]
+    fn reports_unexpected_end() {
+        assert_eq!(parse_error("M 1 2 L 3"), SvgPathError { kind: SvgPathErrorKind::UnexpectedEnd, position: 9 });
+        assert_eq!(parse_error("M 1 2 a 1 1 0 1"), SvgPathError { kind: SvgPathErrorKind::UnexpectedEnd, position: 15 });
+    }
+
+    #[test]
+    fn builds_path() {
+        assert!(svg_to_path("M 0 0 L 10 0 A 5 5 0 0 1 10 10 Z").is_ok());
+        assert_eq!(svg_to_path("M 0").err().map(|e| e.to_string()).as_deref(), Some("unexpected end of path data at position 3"));
+    }
+}
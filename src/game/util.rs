@@ -6,11 +6,13 @@ use bevy::utils::HashSet;
 pub trait RngUtil {
     fn random_unit_vec2(&mut self) -> Vec2;
     fn random_f32(&mut self) -> f32;
+    /// Random value in the inclusive range (min, max)
+    fn random_range(&mut self, range: (f32, f32)) -> f32;
     fn random_bool(&mut self) -> bool;
     fn random_choice<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T>;
 }
 
-impl RngUtil for rand::rngs::ThreadRng {
+impl<R: rand::Rng> RngUtil for R {
     fn random_unit_vec2(&mut self) -> Vec2 {
         let x = self.random_f32() * 2.0 - 1.0;
         let y = self.random_f32() * 2.0 - 1.0;
@@ -18,12 +20,14 @@ impl RngUtil for rand::rngs::ThreadRng {
     }
     
     fn random_f32(&mut self) -> f32 {
-        use rand::Rng;
         self.gen()
     }
 
+    fn random_range(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * self.random_f32()
+    }
+
     fn random_bool(&mut self) -> bool {
-        use rand::Rng;
         self.gen()
     }
     
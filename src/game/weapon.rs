@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use crate::AppState;
+use crate::theme::{Themed, ThemeColor};
+use super::FrameStage;
+use super::bullet::{BulletAssets, BulletCollidable, BulletController, BulletShape, BulletSource, BulletSpawn, spawn_bullet};
+use super::collidable::{Collidable, Collider};
+use super::hit::{DamageEvent, HitEvent};
+use super::invulnerable::{Invulnerable, TestInvulnerable};
+use super::manager::GameCleanup;
+
+// Player weapons
+//
+// The rocket carries a `WeaponRack` and can cycle between the weapons in it. Each weapon has its own
+// fire rate, projectile, lifetime and damage. Most weapons fire bullets, but the laser fires an
+// instant beam which hits everything along its length.
+
+const LASER_Z: f32 = 5.0;
+const LASER_RADIUS: f32 = 0.8;
+const LASER_LINE_WIDTH: f32 = 0.6;
+
+/// A fully charged shot is this much larger than normal
+const CHARGE_MAX_EXTRA_SCALE: f32 = 1.5;
+/// A fully charged shot passes through this many things
+const CHARGE_MAX_PIERCE: f32 = 4.0;
+/// A fully charged shot does this much extra damage
+const CHARGE_MAX_EXTRA_DAMAGE: f32 = 2.0;
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                laser_beam_system,
+
+                laser_collision_system
+                    .in_set(FrameStage::Collision)
+                    .after(FrameStage::Movement),
+            )
+            .run_if(in_state(AppState::Game))
+        );
+        app.add_systems(GameCleanup, laser_teardown_system);
+    }
+}
+
+// Weapons
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weapon {
+    SingleShot,
+    /// A fan of bullets
+    SpreadShot,
+    /// Fast, short range bullets
+    RapidPulse,
+    /// An instant beam which passes through everything in its path
+    Laser,
+    /// Slow, heavy bullets
+    ChargedShot,
+}
+
+enum Projectile {
+    Bullets {
+        shape: BulletShape,
+        speed: f32,
+        count: u32,
+        /// Angle between the outermost bullets
+        spread: f32,
+    },
+    Beam {
+        length: f32,
+    },
+}
+
+impl Weapon {
+    pub const VALUES: [Self; 5] = [ Self::SingleShot, Self::SpreadShot, Self::RapidPulse, Self::Laser, Self::ChargedShot ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::SingleShot => "SINGLE",
+            Weapon::SpreadShot => "SPREAD",
+            Weapon::RapidPulse => "PULSE",
+            Weapon::Laser => "LASER",
+            Weapon::ChargedShot => "CHARGE",
+        }
+    }
+
+    /// shots per second
+    pub fn fire_rate(self) -> f32 {
+        match self {
+            Weapon::SingleShot => 5.0,
+            Weapon::SpreadShot => 3.0,
+            Weapon::RapidPulse => 12.0,
+            Weapon::Laser => 1.5,
+            Weapon::ChargedShot => 1.0,
+        }
+    }
+
+    fn projectile(self) -> Projectile {
+        match self {
+            Weapon::SingleShot => Projectile::Bullets { shape: BulletShape::Standard, speed: 250.0, count: 1, spread: 0.0 },
+            Weapon::SpreadShot => Projectile::Bullets { shape: BulletShape::Standard, speed: 220.0, count: 5, spread: 0.6 },
+            Weapon::RapidPulse => Projectile::Bullets { shape: BulletShape::Pulse, speed: 300.0, count: 1, spread: 0.0 },
+            Weapon::Laser => Projectile::Beam { length: 120.0 },
+            Weapon::ChargedShot => Projectile::Bullets { shape: BulletShape::Slug, speed: 180.0, count: 1, spread: 0.0 },
+        }
+    }
+
+    fn lifetime_secs(self) -> f32 {
+        match self {
+            Weapon::SingleShot => 1.0,
+            Weapon::SpreadShot => 0.6,
+            Weapon::RapidPulse => 0.4,
+            Weapon::Laser => 0.15,
+            Weapon::ChargedShot => 1.5,
+        }
+    }
+
+    /// Seconds to fully charge a shot, for weapons which can be charged
+    pub fn charge_secs(self) -> Option<f32> {
+        match self {
+            Weapon::ChargedShot => Some(1.2),
+            _ => None,
+        }
+    }
+
+    /// A controller which fires this weapon
+    pub fn bullet_controller(self) -> BulletController {
+        let controller = BulletController::new(self.fire_rate());
+        match self.charge_secs() {
+            Some(secs) => controller.with_charge(secs),
+            None => controller,
+        }
+    }
+
+    fn damage(self) -> u32 {
+        match self {
+            Weapon::SingleShot => 1,
+            Weapon::SpreadShot => 1,
+            Weapon::RapidPulse => 1,
+            Weapon::Laser => 2,
+            Weapon::ChargedShot => 3,
+        }
+    }
+}
+
+/// The weapons carried by an entity, one of which is active
+#[derive(Component)]
+pub struct WeaponRack {
+    weapons: Vec<Weapon>,
+    active: usize,
+}
+
+impl WeaponRack {
+    pub fn new(weapons: Vec<Weapon>) -> Self {
+        assert!(!weapons.is_empty(), "A weapon rack needs at least one weapon");
+        Self { weapons, active: 0 }
+    }
+
+    /// Starts with `weapon` selected, if the rack carries it
+    pub fn with_active(mut self, weapon: Weapon) -> Self {
+        self.active = self.weapons.iter().position(|&w| w == weapon).unwrap_or(self.active);
+        self
+    }
+
+    pub fn active(&self) -> Weapon {
+        self.weapons[self.active]
+    }
+
+    /// Moves to the next (or previous, for negative steps) weapon and returns it
+    pub fn cycle(&mut self, step: i32) -> Weapon {
+        let len = self.weapons.len() as i32;
+        self.active = (self.active as i32 + step).rem_euclid(len) as usize;
+        self.active()
+    }
+}
+
+// Firing
+
+/// Where (and how fast) the shot leaves the weapon
+pub struct WeaponFire {
+    pub source: BulletSource,
+    pub owner: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heading_angle: f32,
+    /// How charged the shot is (0.0 - 1.0)
+    pub charge: f32,
+    /// The most bullets the shot may fire (see `BulletController::bullet_allowance`)
+    pub max_bullets: Option<u32>,
+}
+
+/// Returns the number of bullets fired
+pub fn fire_weapon(
+    commands: &mut Commands,
+    assets: &BulletAssets,
+    weapon: Weapon,
+    fire: WeaponFire
+) -> u32 {
+    match weapon.projectile() {
+        Projectile::Bullets { shape, speed, count, spread } => {
+            // Near the bullet limit a fan of bullets narrows, keeping the same gap between them
+            let full_count = count;
+            let count = fire.max_bullets.map_or(count, |max| count.min(max));
+            let spread = if full_count > 1 { spread * count.saturating_sub(1) as f32 / (full_count - 1) as f32 } else { 0. };
+            // Charged shots are bigger, and punch through things
+            let scale = 1. + fire.charge * CHARGE_MAX_EXTRA_SCALE;
+            let pierce = (fire.charge * CHARGE_MAX_PIERCE).round() as u32;
+            let damage = weapon.damage() + (fire.charge * CHARGE_MAX_EXTRA_DAMAGE).round() as u32;
+            for i in 0..count {
+                // Fan out evenly around the heading
+                let offset = if count > 1 { spread * (i as f32 / (count - 1) as f32 - 0.5) } else { 0. };
+                let heading_angle = fire.heading_angle + offset;
+                spawn_bullet(commands, assets, BulletSpawn {
+                    source: fire.source,
+                    owner: fire.owner,
+                    shape,
+                    scale,
+                    damage,
+                    pierce,
+                    position: fire.position,
+                    velocity: fire.velocity + Vec2::from_angle(heading_angle) * speed,
+                    heading_angle,
+                    despawn_after_secs: weapon.lifetime_secs(),
+                });
+            }
+            count
+        },
+        Projectile::Beam { length } => {
+            spawn_laser_beam(commands, weapon, &fire, length);
+            0
+        },
+    }
+}
+
+// Laser beams
+
+#[derive(Component)]
+struct LaserBeam {
+    source: BulletSource,
+    damage: u32,
+    /// Beams only hit things on the frame they are fired
+    fired: bool,
+    despawn_timer: Timer,
+}
+
+fn spawn_laser_beam(commands: &mut Commands, weapon: Weapon, fire: &WeaponFire, length: f32) {
+    let direction = Vec2::from_angle(fire.heading_angle);
+    let half = direction * length / 2.;
+    let transform = Transform::from_xyz(fire.position.x, fire.position.y, LASER_Z)
+        .with_rotation(Quat::from_rotation_z(fire.heading_angle));
+
+    commands.spawn((
+        LaserBeam {
+            source: fire.source,
+            damage: weapon.damage(),
+            fired: false,
+            despawn_timer: Timer::from_seconds(weapon.lifetime_secs(), TimerMode::Once),
+        },
+        // Collision detection
+        Collidable { collider: Collider::capsule(fire.position + half, half, LASER_RADIUS) },
+        // Rendering
+        ShapeBundle {
+            path: GeometryBuilder::build_as(&shapes::Line(Vec2::ZERO, Vec2::new(length, 0.))),
+            transform,
+            ..default()
+        },
+        // NOTE: Colour is applied by the theme
+        Stroke::new(Color::WHITE, LASER_LINE_WIDTH),
+        Themed(ThemeColor::Bullet),
+    ));
+}
+
+fn laser_beam_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut LaserBeam, &mut Stroke)>
+) {
+    for (entity, mut beam, mut stroke) in query.iter_mut() {
+        beam.despawn_timer.tick(time.delta());
+        if beam.despawn_timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        stroke.color.set_a(beam.despawn_timer.percent_left());
+    }
+}
+
+fn laser_collision_system(
+    mut beams: Query<(&mut LaserBeam, &Collidable)>,
+    targets: Query<(Entity, &BulletCollidable, &Collidable, Option<&Invulnerable>)>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (mut beam, beam_collidable) in beams.iter_mut() {
+        if beam.fired {
+            continue;
+        }
+        beam.fired = true;
+        // Hit everything along the beam
+        for (entity, bullet_collidable, collidable, invulnerable) in targets.iter() {
+            if invulnerable.is_invulnerable() || bullet_collidable.source != beam.source {
+                continue;
+            }
+            if beam_collidable.test_collision_with(collidable) {
+                hit_events.send(HitEvent(entity));
+                if beam.damage > 1 {
+                    damage_events.send(DamageEvent { entity, amount: beam.damage });
+                }
+            }
+        }
+    }
+}
+
+// Teardown
+
+fn laser_teardown_system(mut commands: Commands, query: Query<Entity, With<LaserBeam>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
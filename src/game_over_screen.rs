@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
 use crate::AppState;
+use crate::game::mode::GameMode;
+use crate::theme::{Theme, ThemeColor, ThemedText};
 
 // Plugins
 
@@ -18,7 +20,11 @@ impl Plugin for GameOverScreenPlugin {
 
 #[derive(Resource)]
 pub struct GameResults {
+    pub mode: GameMode,
     pub score: u32,
+    /// Whether the game ended because the level's win condition was met
+    pub cleared: bool,
+    pub par_score: Option<u32>,
 }
 
 // Components
@@ -29,6 +35,7 @@ struct GameOverRoot;
 fn game_over_setup_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     game_results: Res<GameResults>
 ) {
     let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
@@ -42,13 +49,13 @@ fn game_over_setup_system(
     let title_text_style = TextStyle {
         font: font_light.clone(),
         font_size: 90.0,
-        color: Color::WHITE,
+        color: theme.text,
     };
 
     let secondary_text_style = TextStyle {
         font: font_light,
         font_size: 50.0,
-        color: Color::GRAY,
+        color: theme.text_secondary,
     };
 
     let background = Color::rgba(0.0, 0.0, 0.0, 0.2);
@@ -72,22 +79,39 @@ fn game_over_setup_system(
         ))
         .with_children(|parent| {
             // Title
-            parent.spawn(
-                TextBundle::from_section("GAME OVER", title_text_style)
-                .with_style(margin_style.clone())
-            );
+            parent.spawn((
+                TextBundle::from_section(if game_results.cleared { "LEVEL CLEAR" } else { "GAME OVER" }, title_text_style)
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::Text]),
+            ));
+            // Mode
+            parent.spawn((
+                TextBundle::from_section(game_results.mode.name(), secondary_text_style.clone())
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
             // Score
-            parent.spawn(
+            parent.spawn((
                 TextBundle::from_sections([
                     TextSection::new("SCORE ", secondary_text_style.clone()),
-                    TextSection::new(game_results.score.to_string(), ts_with_color(&secondary_text_style, Color::GOLD)),
+                    TextSection::new(game_results.score.to_string(), ts_with_color(&secondary_text_style, theme.text_highlight)),
                 ])
-                .with_style(margin_style.clone())
-            );
-            parent.spawn(
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary, ThemeColor::TextHighlight]),
+            ));
+            // Par
+            if let Some(par_score) = game_results.par_score {
+                parent.spawn((
+                    TextBundle::from_section(format!("PAR {par_score}"), secondary_text_style.clone())
+                    .with_style(margin_style.clone()),
+                    ThemedText(vec![ThemeColor::TextSecondary]),
+                ));
+            }
+            parent.spawn((
                 TextBundle::from_section("Press [esc] to continue", secondary_text_style)
-                .with_style(margin_style.clone())
-            );
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
         });
 }
 
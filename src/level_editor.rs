@@ -0,0 +1,609 @@
+use bevy::prelude::*;
+use bevy::render::camera::OrthographicProjection;
+use bevy::window::PrimaryWindow;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::AppState;
+use crate::asset_paths;
+use crate::theme::{Theme, Themed, ThemeColor, ThemedText};
+use crate::game::FrameStage;
+use crate::game::assets::GameAssets;
+use crate::game::asteroid::{AsteroidMaterial, AsteroidShapeId, AsteroidSize, spawn_asteroid};
+use crate::game::collidable::Collidable;
+use crate::game::debug_overlay::{add_arrow, add_circle, add_collider};
+use crate::game::hazard::{HazardSpawn, spawn_hazard};
+use crate::game::level::{ChallengeLevel, Level, LevelAsteroid, LEVEL_ASTEROID_MAX_SPEED, parse_level, set_arena_size, write_level};
+use crate::game::manager::world_boundaries_update_system;
+use crate::game::movable::{Drag, MovableGlobalState};
+
+// Level editor
+//
+// Edits the challenge level (see `level`). Asteroids and hazards are spawned with the same
+// functions the game uses, and respawned whenever the level changes, so what is edited is exactly
+// what gets played. Nothing moves while editing, apart from a preview of the first second.
+//
+// [click] selects, [drag] moves, and dragging the arrow head of the selected asteroid sets its
+// velocity. The arrows show where each asteroid will be after one second.
+
+const EDITOR_PREVIEW_SECS: f32 = 1.0;
+const EDITOR_VELOCITY_ARROW_SECS: f32 = 1.0;
+/// How close the cursor needs to be to grab the end of a velocity arrow
+const EDITOR_HANDLE_RADIUS: f32 = 2.5;
+const EDITOR_ROTATE_STEP: f32 = std::f32::consts::PI / 12.;
+/// Gravity wells are picked by their inner ring, as the core is tiny
+const EDITOR_GRAVITY_WELL_PICK_SCALE: f32 = 2.0;
+const EDITOR_OVERLAY_Z: f32 = 40.0;
+const EDITOR_LINE_WIDTH: f32 = 0.3;
+
+pub struct LevelEditorPlugin;
+
+impl Plugin for LevelEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Editor), editor_setup_system);
+        app.add_systems(OnExit(AppState::Editor), editor_cleanup_system);
+        app.add_systems(
+            Update,
+            (
+                world_boundaries_update_system
+                    .in_set(FrameStage::Start),
+
+                // NOTE: The mouse picks from the spawned entities, so runs before the keyboard can
+                // change the level out from under them
+                editor_mouse_system
+                    .before(editor_keyboard_system),
+
+                editor_keyboard_system,
+
+                editor_preview_system
+                    .after(editor_keyboard_system),
+
+                editor_rebuild_system
+                    .after(editor_keyboard_system)
+                    .after(editor_mouse_system)
+                    .after(editor_preview_system),
+
+                editor_overlay_system
+                    .after(editor_rebuild_system),
+
+                editor_text_system
+                    .after(editor_rebuild_system),
+            )
+            .run_if(in_state(AppState::Editor))
+        );
+    }
+}
+
+// Resources
+
+#[derive(Resource)]
+struct LevelEditor {
+    level: Level,
+    selected: Option<EditorItem>,
+    drag: Option<EditorDrag>,
+    /// Runs down while the preview is playing
+    preview: Option<Timer>,
+    /// Set when the level changes, so the world is respawned
+    dirty: bool,
+    /// The result of the last save or load
+    status: String,
+}
+
+impl LevelEditor {
+    /// NOTE: None if the item is no longer in the level
+    fn position_of(&self, item: EditorItem) -> Option<Vec2> {
+        match item {
+            EditorItem::Asteroid(i) => self.level.asteroids.get(i).map(|a| a.position),
+            EditorItem::Hazard(i) => self.level.hazards.get(i).map(HazardSpawn::position),
+        }
+    }
+
+    fn set_position_of(&mut self, item: EditorItem, position: Vec2) {
+        let position = position.clamp(self.level.arena * -0.5, self.level.arena * 0.5);
+        if self.position_of(item).unwrap_or(position) == position {
+            return;
+        }
+        match item {
+            EditorItem::Asteroid(i) => self.level.asteroids[i].position = position,
+            EditorItem::Hazard(i) => match &mut self.level.hazards[i] {
+                HazardSpawn::GravityWell { position: p, .. } | HazardSpawn::Nebula { position: p, .. } => *p = position,
+            },
+        }
+        self.dirty = true;
+    }
+
+    fn selected_asteroid(&mut self) -> Option<&mut LevelAsteroid> {
+        match self.selected {
+            Some(EditorItem::Asteroid(i)) => {
+                self.dirty = true;
+                self.level.asteroids.get_mut(i)
+            },
+            _ => None,
+        }
+    }
+
+    fn add_asteroid(&mut self, position: Vec2) {
+        self.level.asteroids.push(LevelAsteroid {
+            size: AsteroidSize::Large,
+            shape: AsteroidShapeId::A,
+            material: AsteroidMaterial::Rock,
+            position,
+            velocity: Vec2::ZERO,
+            spin: 0.,
+            angle: 0.,
+        });
+        self.selected = Some(EditorItem::Asteroid(self.level.asteroids.len() - 1));
+        self.dirty = true;
+    }
+
+    fn add_hazard(&mut self, hazard: HazardSpawn) {
+        self.level.hazards.push(hazard);
+        self.selected = Some(EditorItem::Hazard(self.level.hazards.len() - 1));
+        self.dirty = true;
+    }
+
+    fn delete_selected(&mut self) {
+        match self.selected.take() {
+            Some(EditorItem::Asteroid(i)) => { self.level.asteroids.remove(i); },
+            Some(EditorItem::Hazard(i)) => { self.level.hazards.remove(i); },
+            None => return,
+        }
+        self.dirty = true;
+    }
+}
+
+// Components
+
+/// Marks the spawned entity for an asteroid or hazard in the level (by index)
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum EditorItem {
+    Asteroid(usize),
+    Hazard(usize),
+}
+
+#[derive(Clone, Copy)]
+enum EditorDrag {
+    /// Moving the selected item, which is held at this offset from the cursor
+    Move { offset: Vec2 },
+    /// Setting the velocity of the selected asteroid
+    Velocity,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum EditorOverlay {
+    /// The arena outline and velocity arrows
+    Guides,
+    /// The outline of the selected item
+    Selection,
+}
+
+#[derive(Component)]
+struct EditorText;
+
+// Setup
+
+fn editor_setup_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    challenge_level: Res<ChallengeLevel>,
+    mut movable_state: ResMut<MovableGlobalState>,
+    mut projections: Query<&mut OrthographicProjection>,
+) {
+    // NOTE: Starts from scratch if the level file failed to load
+    let level = challenge_level.level.clone().unwrap_or_else(Level::blank);
+    set_arena_size(&mut projections, level.arena);
+    movable_state.enabled = false;
+    commands.insert_resource(LevelEditor {
+        level,
+        selected: None,
+        drag: None,
+        preview: None,
+        dirty: true,
+        status: String::new(),
+    });
+
+    for (overlay, color) in [(EditorOverlay::Guides, ThemeColor::TextSecondary), (EditorOverlay::Selection, ThemeColor::TextHighlight)] {
+        commands.spawn((
+            overlay,
+            ShapeBundle {
+                transform: Transform::from_xyz(0., 0., EDITOR_OVERLAY_Z),
+                ..default()
+            },
+            // NOTE: Colour is applied by the theme
+            Stroke::new(Color::WHITE, EDITOR_LINE_WIDTH),
+            Themed(color),
+        ));
+    }
+
+    let font_light = asset_server.load(asset_paths::FONT_MONO_LIGHT);
+    commands.spawn((
+        EditorText,
+        // NOTE: Text is set by `editor_text_system`
+        TextBundle::from_sections([
+            TextSection::from_style(TextStyle {
+                font: font_light.clone(),
+                font_size: 20.0,
+                color: theme.text_secondary,
+            }),
+            TextSection::from_style(TextStyle {
+                font: font_light,
+                font_size: 20.0,
+                color: theme.text_highlight,
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(15.0),
+            left: Val::Px(15.0),
+            ..default()
+        }),
+        ThemedText(vec![ThemeColor::TextSecondary, ThemeColor::TextHighlight]),
+    ));
+}
+
+type EditorEntityFilter = Or<(With<EditorItem>, With<EditorOverlay>, With<EditorText>)>;
+
+fn editor_cleanup_system(
+    mut commands: Commands,
+    mut movable_state: ResMut<MovableGlobalState>,
+    query: Query<Entity, EditorEntityFilter>,
+) {
+    movable_state.enabled = true;
+    commands.remove_resource::<LevelEditor>();
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+}
+
+// Input
+
+fn cursor_world_position(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single()) else { return None };
+    window.cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+}
+
+fn velocity_arrow_end(asteroid: &LevelAsteroid) -> Vec2 {
+    asteroid.position + asteroid.velocity * EDITOR_VELOCITY_ARROW_SECS
+}
+
+fn hazard_pick_radius(hazard: &HazardSpawn) -> f32 {
+    match *hazard {
+        HazardSpawn::GravityWell { core_radius, .. } => core_radius * EDITOR_GRAVITY_WELL_PICK_SCALE,
+        HazardSpawn::Nebula { radius, .. } => radius,
+    }
+}
+
+/// The item under the cursor. Asteroids are drawn over hazards, so are picked first.
+fn pick_item(level: &Level, asteroids: &Query<(&EditorItem, &Collidable)>, cursor: Vec2) -> Option<EditorItem> {
+    let asteroid = asteroids.iter()
+        .find(|(&item, collidable)| item_in_level(level, item) && collidable.contains_point(cursor))
+        .map(|(&item, _)| item);
+    asteroid.or_else(|| {
+        level.hazards.iter()
+            .position(|hazard| hazard.position().distance(cursor) <= hazard_pick_radius(hazard))
+            .map(EditorItem::Hazard)
+    })
+}
+
+fn item_in_level(level: &Level, item: EditorItem) -> bool {
+    match item {
+        EditorItem::Asteroid(i) => i < level.asteroids.len(),
+        EditorItem::Hazard(i) => i < level.hazards.len(),
+    }
+}
+
+fn editor_mouse_system(
+    mouse: Res<Input<MouseButton>>,
+    mut editor: ResMut<LevelEditor>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    asteroids: Query<(&EditorItem, &Collidable)>,
+) {
+    if editor.preview.is_some() {
+        return;
+    }
+    let Some(cursor) = cursor_world_position(&windows, &cameras) else { return };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        // Grab the end of the selected asteroid's velocity arrow, or whatever is under the cursor
+        let velocity_handle = match editor.selected {
+            Some(EditorItem::Asteroid(i)) => editor.level.asteroids.get(i).map(velocity_arrow_end),
+            _ => None,
+        };
+        if velocity_handle.is_some_and(|handle| handle.distance(cursor) <= EDITOR_HANDLE_RADIUS) {
+            editor.drag = Some(EditorDrag::Velocity);
+        } else {
+            editor.selected = pick_item(&editor.level, &asteroids, cursor);
+            editor.drag = editor.selected
+                .and_then(|item| editor.position_of(item))
+                .map(|position| EditorDrag::Move { offset: position - cursor });
+        }
+    }
+    if !mouse.pressed(MouseButton::Left) {
+        editor.drag = None;
+    }
+
+    match (editor.drag, editor.selected) {
+        (Some(EditorDrag::Move { offset }), Some(item)) => {
+            editor.set_position_of(item, cursor + offset);
+        },
+        (Some(EditorDrag::Velocity), Some(EditorItem::Asteroid(i))) => {
+            let Some(asteroid) = editor.level.asteroids.get(i) else { return };
+            let velocity = ((cursor - asteroid.position) / EDITOR_VELOCITY_ARROW_SECS).clamp_length_max(LEVEL_ASTEROID_MAX_SPEED);
+            if asteroid.velocity != velocity {
+                editor.level.asteroids[i].velocity = velocity;
+                editor.dirty = true;
+            }
+        },
+        _ => {},
+    }
+}
+
+fn editor_keyboard_system(
+    mut kb: ResMut<Input<KeyCode>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut editor: ResMut<LevelEditor>,
+    mut challenge_level: ResMut<ChallengeLevel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    if kb.clear_just_released(KeyCode::Escape) {
+        next_app_state.set(AppState::Menu);
+        return;
+    }
+    if editor.preview.is_some() {
+        return;
+    }
+
+    // Preview
+    if kb.just_pressed(KeyCode::Space) {
+        editor.drag = None;
+        editor.preview = Some(Timer::from_seconds(EDITOR_PREVIEW_SECS, TimerMode::Once));
+        return;
+    }
+
+    // Save and load
+    if kb.just_pressed(KeyCode::S) {
+        let result = write_level(&editor.level)
+            .map_err(|err| err.to_string())
+            .and_then(|source| write_level_file(&source));
+        editor.status = match result {
+            Ok(()) => {
+                // Play the new version straight away
+                challenge_level.level = Some(editor.level.clone());
+                format!("Saved {}", asset_paths::LEVEL_CHALLENGE)
+            },
+            Err(err) => format!("Not saved: {err}"),
+        };
+    }
+    if kb.just_pressed(KeyCode::L) {
+        let result = read_level_file()
+            .and_then(|source| parse_level(&source).map_err(|err| err.to_string()));
+        editor.status = match result {
+            Ok(level) => {
+                editor.level = level;
+                editor.selected = None;
+                editor.dirty = true;
+                format!("Loaded {}", asset_paths::LEVEL_CHALLENGE)
+            },
+            Err(err) => format!("Not loaded: {err}"),
+        };
+    }
+
+    // Placing and deleting
+    if let Some(cursor) = cursor_world_position(&windows, &cameras).filter(|&p| editor.level.contains(p)) {
+        if kb.just_pressed(KeyCode::A) {
+            editor.add_asteroid(cursor);
+        }
+        if kb.just_pressed(KeyCode::G) {
+            editor.add_hazard(HazardSpawn::GravityWell { position: cursor, strength: 20000.0, core_radius: 4.0, range: 120.0 });
+        }
+        if kb.just_pressed(KeyCode::N) {
+            let drag = Drag { constant: 0.0, linear: 1.0, min_speed: 10.0 };
+            editor.add_hazard(HazardSpawn::Nebula { position: cursor, radius: 20.0, drag });
+        }
+    }
+    if kb.just_pressed(KeyCode::Delete) || kb.just_pressed(KeyCode::Back) {
+        editor.delete_selected();
+    }
+
+    // The selected asteroid
+    if kb.just_pressed(KeyCode::Q) || kb.just_pressed(KeyCode::E) {
+        let step = if kb.just_pressed(KeyCode::Q) { EDITOR_ROTATE_STEP } else { -EDITOR_ROTATE_STEP };
+        if let Some(asteroid) = editor.selected_asteroid() {
+            asteroid.angle = (asteroid.angle + step).rem_euclid(std::f32::consts::TAU);
+        }
+    }
+    for (key, size) in [(KeyCode::Key1, AsteroidSize::Small), (KeyCode::Key2, AsteroidSize::Medium), (KeyCode::Key3, AsteroidSize::Large)] {
+        if kb.just_pressed(key) {
+            if let Some(asteroid) = editor.selected_asteroid() {
+                asteroid.size = size;
+            }
+        }
+    }
+    if kb.just_pressed(KeyCode::Tab) {
+        if let Some(asteroid) = editor.selected_asteroid() {
+            asteroid.shape = next_value(&AsteroidShapeId::VALUES, asteroid.shape);
+        }
+    }
+    if kb.just_pressed(KeyCode::M) {
+        if let Some(asteroid) = editor.selected_asteroid() {
+            asteroid.material = next_value(&AsteroidMaterial::VALUES, asteroid.material);
+        }
+    }
+}
+
+fn next_value<T: Copy + PartialEq>(values: &[T], value: T) -> T {
+    let index = values.iter().position(|&v| v == value).unwrap_or(0);
+    values[(index + 1) % values.len()]
+}
+
+// Level files
+// NOTE: Read and written directly, as the asset server can only load
+
+#[cfg(not(target_arch = "wasm32"))]
+fn level_file_path() -> std::path::PathBuf {
+    bevy::asset::FileAssetIo::get_base_path()
+        .join("assets")
+        .join(asset_paths::LEVEL_CHALLENGE)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_level_file() -> Result<String, String> {
+    std::fs::read_to_string(level_file_path()).map_err(|err| err.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_level_file(source: &str) -> Result<(), String> {
+    std::fs::write(level_file_path(), source).map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_level_file() -> Result<String, String> {
+    Err("level files can't be loaded in the browser".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_level_file(_source: &str) -> Result<(), String> {
+    Err("level files can't be saved in the browser".to_string())
+}
+
+// Systems
+
+fn editor_preview_system(
+    time: Res<Time>,
+    mut editor: ResMut<LevelEditor>,
+    mut movable_state: ResMut<MovableGlobalState>,
+) {
+    let Some(timer) = editor.preview.as_mut() else { return };
+    movable_state.enabled = !timer.tick(time.delta()).finished();
+    if !movable_state.enabled {
+        // Put everything back where it started
+        editor.preview = None;
+        editor.dirty = true;
+    }
+}
+
+fn editor_rebuild_system(
+    mut commands: Commands,
+    mut editor: ResMut<LevelEditor>,
+    assets: Res<GameAssets>,
+    items: Query<Entity, With<EditorItem>>,
+) {
+    if !editor.dirty {
+        return;
+    }
+    editor.dirty = false;
+    for entity in items.iter() {
+        commands
+            .entity(entity)
+            .despawn_recursive();
+    }
+    for (i, asteroid) in editor.level.asteroids.iter().enumerate() {
+        let entity = spawn_asteroid(&mut commands, &assets.asteroid, asteroid.to_spawn());
+        commands.entity(entity).insert(EditorItem::Asteroid(i));
+    }
+    for (i, hazard) in editor.level.hazards.iter().enumerate() {
+        let entity = spawn_hazard(&mut commands, hazard);
+        commands.entity(entity).insert(EditorItem::Hazard(i));
+    }
+}
+
+fn editor_overlay_system(
+    editor: Res<LevelEditor>,
+    asteroids: Query<(&EditorItem, &Collidable)>,
+    mut overlays: Query<(&EditorOverlay, &mut Path)>,
+) {
+    let mut guides = PathBuilder::new();
+    let mut selection = PathBuilder::new();
+
+    // Arena
+    let half = editor.level.arena * 0.5;
+    guides.move_to(Vec2::new(-half.x, -half.y));
+    guides.line_to(Vec2::new(half.x, -half.y));
+    guides.line_to(Vec2::new(half.x, half.y));
+    guides.line_to(Vec2::new(-half.x, half.y));
+    guides.close();
+
+    // NOTE: Hidden during the preview, as everything has moved on
+    if editor.preview.is_none() {
+        for asteroid in editor.level.asteroids.iter() {
+            add_arrow(&mut guides, asteroid.position, velocity_arrow_end(asteroid));
+        }
+        match editor.selected {
+            Some(EditorItem::Asteroid(i)) => {
+                if let Some((_, collidable)) = asteroids.iter().find(|(&item, _)| item == EditorItem::Asteroid(i)) {
+                    add_collider(&mut selection, &collidable.collider);
+                }
+                if let Some(asteroid) = editor.level.asteroids.get(i) {
+                    add_circle(&mut selection, velocity_arrow_end(asteroid), EDITOR_HANDLE_RADIUS);
+                }
+            },
+            Some(EditorItem::Hazard(i)) => {
+                if let Some(hazard) = editor.level.hazards.get(i) {
+                    add_circle(&mut selection, hazard.position(), hazard_pick_radius(hazard));
+                }
+            },
+            None => {},
+        }
+    }
+
+    for (&overlay, mut path) in overlays.iter_mut() {
+        let builder = match overlay {
+            EditorOverlay::Guides => std::mem::replace(&mut guides, PathBuilder::new()),
+            EditorOverlay::Selection => std::mem::replace(&mut selection, PathBuilder::new()),
+        };
+        *path = builder.build();
+    }
+}
+
+fn editor_text_system(
+    editor: Res<LevelEditor>,
+    mut query: Query<&mut Text, With<EditorText>>
+) {
+    for mut text in query.iter_mut() {
+        if editor.is_changed() || text.is_added() {
+            write_editor_help(&mut text.sections[0].value, &editor);
+            text.sections[1].value.clone_from(&editor.status);
+        }
+    }
+}
+
+fn write_editor_help(output: &mut String, editor: &LevelEditor) {
+    use std::fmt::Write;
+    output.clear();
+    writeln!(output, "LEVEL EDITOR: {}", editor.level.name).unwrap();
+    writeln!(output, "[click] select [drag] move").unwrap();
+    writeln!(output, "[drag arrow] velocity").unwrap();
+    writeln!(output, "[a] asteroid [g] gravity well [n] nebula").unwrap();
+    writeln!(output, "[del] delete").unwrap();
+    writeln!(output, "[q/e] rotate [1-3] size [tab] shape [m] material").unwrap();
+    writeln!(output, "[space] preview {EDITOR_PREVIEW_SECS} sec").unwrap();
+    writeln!(output, "[s] save [l] load [esc] menu").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "asteroids: {} hazards: {}", editor.level.asteroids.len(), editor.level.hazards.len()).unwrap();
+    let selected_asteroid = match editor.selected {
+        Some(EditorItem::Asteroid(i)) => editor.level.asteroids.get(i).map(|a| (i, a)),
+        _ => None,
+    };
+    let selected_hazard = match editor.selected {
+        Some(EditorItem::Hazard(i)) => editor.level.hazards.get(i).map(|h| (i, h)),
+        _ => None,
+    };
+    if let Some((i, asteroid)) = selected_asteroid {
+        writeln!(output, "asteroid {}: {:?} {:?} {:?}, speed {:.1}", i + 1, asteroid.size, asteroid.shape, asteroid.material, asteroid.velocity.length()).unwrap();
+    }
+    if let Some((i, hazard)) = selected_hazard {
+        let kind = match hazard {
+            HazardSpawn::GravityWell { .. } => "gravity well",
+            HazardSpawn::Nebula { .. } => "nebula",
+        };
+        writeln!(output, "hazard {}: {kind}", i + 1).unwrap();
+    }
+}
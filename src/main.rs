@@ -1,10 +1,11 @@
-#![feature(extract_if)]
-
 mod asset_paths;
+mod theme;
+mod camera;
 mod game;
 mod splash_screen;
 mod pause_screen;
 mod game_over_screen;
+mod level_editor;
 
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
@@ -15,18 +16,19 @@ enum AppState {
     Menu,
     Game,
     Pause,
-    GameOver
+    GameOver,
+    Editor,
 }
 
 const ASTEROIDS_TITLE: &str = "Asteroids";
+/// The smallest area kept on screen, outside of levels with their own arena size
 const FIXED_WIDTH_HEIGHT: f32 = 200.0;
 
 fn main() {
     let title = ASTEROIDS_TITLE.into();
-    let (width, height) = (1024., 768.);
+    let (width, height): (f32, f32) = (1024., 768.);
     App::new()
         // bevy
-        .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
         .add_plugins(DefaultPlugins
             .set(WindowPlugin {
                 primary_window: Some(Window {
@@ -43,10 +45,13 @@ fn main() {
         .add_plugins(ShapePlugin)
         // Game
         .add_plugins((
+            theme::ThemePlugin,
+            camera::CameraEffectsPlugin,
             game::GamePluginGroup,
             splash_screen::SplashScreenPlugin,
             game_over_screen::GameOverScreenPlugin,
-            pause_screen::PauseScreenPlugin
+            pause_screen::PauseScreenPlugin,
+            level_editor::LevelEditorPlugin,
         ))
         .add_systems(Startup, startup_system)
         .run();
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{AppState, game::movable::MovableGlobalState};
+use crate::theme::{Theme, ThemeColor, ThemedText};
 
 // Plugins
 
@@ -32,6 +33,7 @@ struct PauseRoot;
 fn pause_setup_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     mut movable_state: ResMut<MovableGlobalState>,
 ) {
     let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
@@ -44,13 +46,13 @@ fn pause_setup_system(
     let title_text_style = TextStyle {
         font: font_light.clone(),
         font_size: 90.0,
-        color: Color::WHITE,
+        color: theme.text,
     };
 
     let secondary_text_style = TextStyle {
         font: font_light,
         font_size: 50.0,
-        color: Color::GRAY,
+        color: theme.text_secondary,
     };
 
     // Root node
@@ -71,14 +73,16 @@ fn pause_setup_system(
             },
         ))
         .with_children(|parent| {
-            parent.spawn(
+            parent.spawn((
                 TextBundle::from_section("PAUSE", title_text_style)
-                .with_style(margin_style.clone())
-            );
-            parent.spawn(
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::Text]),
+            ));
+            parent.spawn((
                 TextBundle::from_section("Press [esc] to continue", secondary_text_style)
-                .with_style(margin_style.clone())
-            );
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
         });
 
     // Disable movables
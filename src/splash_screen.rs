@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 
 use crate::AppState;
+use crate::camera::CameraEffects;
+use crate::theme::{Theme, ThemeColor, ThemedText};
+use crate::game::assets::{GameAssets, GameAssetsFailed};
+use crate::game::level::{ChallengeLevel, Level};
 use crate::game::manager::{GameManager, GameCleanup};
+use crate::game::mode::GameMode;
 
 // Plugins
 
@@ -9,6 +14,7 @@ pub struct SplashScreenPlugin;
 
 impl Plugin for SplashScreenPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<MenuGameMode>();
         app.add_systems(
             OnEnter(AppState::Menu),
             (
@@ -22,20 +28,40 @@ impl Plugin for SplashScreenPlugin {
         );
         app.add_systems(
             Update, 
-            menu_keyboard_system
-                .run_if(in_state(AppState::Menu))
+            (
+                menu_keyboard_system,
+                menu_start_text_system,
+                menu_screen_shake_text_system,
+                menu_game_mode_text_system,
+            )
+            .run_if(in_state(AppState::Menu))
         );
     }
 }
 
+// Resources
+
+/// The game mode picked on the menu (remembered between games)
+#[derive(Resource, Default)]
+struct MenuGameMode(GameMode);
+
 // Components
 
 #[derive(Component)]
 struct MenuRoot;
 
+#[derive(Component)]
+struct MenuStartText;
+
+#[derive(Component)]
+struct MenuScreenShakeText;
+
+#[derive(Component)]
+struct MenuGameModeText;
+
 // Menu
 
-fn menu_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn menu_setup_system(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
     let font_light = asset_server.load(crate::asset_paths::FONT_MONO_LIGHT);
     let font_bold = asset_server.load(crate::asset_paths::FONT_MONO_BOLD);
 
@@ -47,13 +73,25 @@ fn menu_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     let title_text_style = TextStyle {
         font: font_bold,
         font_size: 90.0,
-        color: Color::WHITE,
+        color: theme.text,
     };
 
     let secondary_text_style = TextStyle {
-        font: font_light,
+        font: font_light.clone(),
         font_size: 50.0,
-        color: Color::GRAY,
+        color: theme.text_secondary,
+    };
+
+    let mode_text_style = TextStyle {
+        font: font_light.clone(),
+        font_size: 35.0,
+        color: theme.text_highlight,
+    };
+
+    let hint_text_style = TextStyle {
+        font: font_light,
+        font_size: 25.0,
+        color: theme.text_secondary,
     };
 
     // Root node
@@ -74,14 +112,46 @@ fn menu_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
             }
         ))
         .with_children(|parent| {
-            parent.spawn(
+            parent.spawn((
                 TextBundle::from_section("ASTEROIDS", title_text_style)
-                .with_style(margin_style.clone())
-            );
-            parent.spawn(
-                TextBundle::from_section("Press [space] to begin", secondary_text_style)
-                .with_style(margin_style.clone())
-            );
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::Text]),
+            ));
+            parent.spawn((
+                MenuStartText,
+                // NOTE: Text is set by `menu_start_text_system`
+                TextBundle::from_section("", secondary_text_style)
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
+            parent.spawn((
+                MenuGameModeText,
+                // NOTE: Text is set by `menu_game_mode_text_system`
+                TextBundle::from_sections([
+                    TextSection::from_style(mode_text_style),
+                    TextSection::from_style(hint_text_style.clone()),
+                ])
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextHighlight, ThemeColor::TextSecondary]),
+            ));
+            parent.spawn((
+                TextBundle::from_section("Press [E] to edit the challenge level", hint_text_style.clone())
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
+            parent.spawn((
+                TextBundle::from_section("Press [F2] to change colours", hint_text_style.clone())
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
+            parent.spawn((
+                MenuScreenShakeText,
+                // NOTE: Text is set by `menu_screen_shake_text_system`
+                TextBundle::from_section("", hint_text_style)
+                .with_style(margin_style.clone()),
+                ThemedText(vec![ThemeColor::TextSecondary]),
+            ));
         });
 }
 
@@ -96,12 +166,89 @@ fn menu_cleanup_system(mut commands: Commands, fragments: Query<Entity, With<Men
 fn menu_keyboard_system(
     mut commands: Commands,
     mut kb: ResMut<Input<KeyCode>>,
-    mut next_app_state: ResMut<NextState<AppState>>
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut camera_effects: ResMut<CameraEffects>,
+    mut game_mode: ResMut<MenuGameMode>,
+    game_assets: Option<Res<GameAssets>>,
+    challenge_level: Res<ChallengeLevel>,
 ) {
+    if kb.clear_just_released(KeyCode::S) {
+        camera_effects.shake_enabled = !camera_effects.shake_enabled;
+    }
+    if kb.clear_just_released(KeyCode::Left) {
+        game_mode.0 = game_mode.0.cycle(-1);
+    }
+    if kb.clear_just_released(KeyCode::Right) {
+        game_mode.0 = game_mode.0.cycle(1);
+    }
+    // Wait for the game assets to finish loading
+    if game_assets.is_none() {
+        return;
+    }
     if kb.clear_just_released(KeyCode::Space) {
-        crate::game::manager::game_create(&mut commands);
+        let level = match game_mode.0.uses_level_file() {
+            true => challenge_level.level.clone(),
+            false => Some(Level::classic()),
+        };
+        // NOTE: The level file may still be loading, or have failed to load
+        let Some(level) = level else { return };
+        crate::game::manager::game_create(&mut commands, game_mode.0, level);
         next_app_state.set(AppState::Game);
     }
+    // NOTE: Edits a blank level if the level file failed to load
+    if kb.clear_just_released(KeyCode::E) && (challenge_level.level.is_some() || challenge_level.failed) {
+        next_app_state.set(AppState::Editor);
+    }
+}
+
+fn menu_start_text_system(
+    game_assets_failed: Option<Res<GameAssetsFailed>>,
+    mut query: Query<&mut Text, With<MenuStartText>>
+) {
+    for mut text in query.iter_mut() {
+        let value = match game_assets_failed {
+            Some(_) => "Shapes failed to load (see the log)",
+            None => "Press [space] to begin",
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_string();
+        }
+    }
+}
+
+fn menu_screen_shake_text_system(
+    camera_effects: Res<CameraEffects>,
+    mut query: Query<&mut Text, With<MenuScreenShakeText>>
+) {
+    for mut text in query.iter_mut() {
+        if camera_effects.is_changed() || text.is_added() {
+            let state = if camera_effects.shake_enabled { "on" } else { "off" };
+            text.sections[0].value = format!("Press [S] to toggle screen shake ({})", state);
+        }
+    }
+}
+
+fn menu_game_mode_text_system(
+    game_mode: Res<MenuGameMode>,
+    challenge_level: Res<ChallengeLevel>,
+    mut query: Query<&mut Text, With<MenuGameModeText>>
+) {
+    for mut text in query.iter_mut() {
+        if game_mode.is_changed() || challenge_level.is_changed() || text.is_added() {
+            let mode = game_mode.0;
+            text.sections[0].value = format!("< {} >\n", mode.name());
+            text.sections[1].value = mode.description().to_string();
+            if mode.uses_level_file() {
+                let level = match (&challenge_level.level, challenge_level.failed) {
+                    (Some(level), _) => level.name.clone(),
+                    (None, true) => "Level failed to load (see the log)".to_string(),
+                    (None, false) => "Loading level...".to_string(),
+                };
+                text.sections[1].value.push('\n');
+                text.sections[1].value.push_str(&level);
+            }
+        }
+    }
 }
 
 fn game_cleanup_system(
@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+
+// Colour themes
+//
+// Entities and UI text declare *what* they are (see `Themed` and `ThemedText`),
+// and the active `Theme` decides which colour that is. Switching the theme recolours
+// everything already on screen.
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Theme::PRESETS[0].clone());
+        app.insert_resource(ClearColor(Theme::PRESETS[0].background));
+        app.add_systems(Update, theme_keyboard_system);
+        app.add_systems(
+            PostUpdate,
+            (
+                theme_clear_color_system,
+                themed_stroke_system,
+                themed_text_system,
+                themed_background_system,
+            )
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ThemeColor {
+    Rocket,
+    Alien,
+    Asteroid,
+    Bullet,
+    Explosion,
+    Star,
+    Hazard,
+    Text,
+    TextSecondary,
+    TextHighlight,
+}
+
+#[derive(Resource, Clone)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Color,
+    pub rocket: Color,
+    pub alien: Color,
+    pub asteroid: Color,
+    pub bullet: Color,
+    pub explosion: Color,
+    pub star: Color,
+    pub hazard: Color,
+    pub text: Color,
+    pub text_secondary: Color,
+    pub text_highlight: Color,
+}
+
+impl Theme {
+    pub const CLASSIC: Theme = Theme {
+        name: "Classic",
+        background: Color::rgb(0.04, 0.04, 0.04),
+        rocket: Color::rgb(1.0, 1.0, 1.0),
+        alien: Color::rgb(1.0, 1.0, 1.0),
+        asteroid: Color::rgb(0.6, 0.6, 0.6),
+        bullet: Color::rgb(0.8, 0.8, 0.8),
+        explosion: Color::rgb(0.8, 0.8, 0.8),
+        star: Color::rgb(0.7, 0.7, 0.7),
+        hazard: Color::rgb(0.5, 0.5, 0.8),
+        text: Color::WHITE,
+        text_secondary: Color::GRAY,
+        text_highlight: Color::GOLD,
+    };
+
+    pub const VECTOR_CRT: Theme = Theme {
+        name: "Vector CRT",
+        background: Color::rgb(0.0, 0.03, 0.01),
+        rocket: Color::rgb(0.6, 1.0, 0.6),
+        alien: Color::rgb(0.5, 1.0, 0.5),
+        asteroid: Color::rgb(0.2, 0.75, 0.3),
+        bullet: Color::rgb(0.75, 1.0, 0.75),
+        explosion: Color::rgb(0.4, 0.9, 0.4),
+        star: Color::rgb(0.3, 0.7, 0.4),
+        hazard: Color::rgb(0.4, 0.85, 0.6),
+        text: Color::rgb(0.6, 1.0, 0.6),
+        text_secondary: Color::rgb(0.25, 0.6, 0.3),
+        text_highlight: Color::rgb(0.85, 1.0, 0.85),
+    };
+
+    pub const AMBER: Theme = Theme {
+        name: "Amber",
+        background: Color::rgb(0.05, 0.03, 0.0),
+        rocket: Color::rgb(1.0, 0.75, 0.2),
+        alien: Color::rgb(1.0, 0.75, 0.2),
+        asteroid: Color::rgb(0.8, 0.5, 0.1),
+        bullet: Color::rgb(1.0, 0.8, 0.4),
+        explosion: Color::rgb(0.9, 0.6, 0.15),
+        star: Color::rgb(0.9, 0.65, 0.25),
+        hazard: Color::rgb(1.0, 0.45, 0.1),
+        text: Color::rgb(1.0, 0.75, 0.2),
+        text_secondary: Color::rgb(0.6, 0.4, 0.1),
+        text_highlight: Color::rgb(1.0, 0.9, 0.6),
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        name: "High Contrast",
+        background: Color::BLACK,
+        rocket: Color::WHITE,
+        alien: Color::rgb(1.0, 0.3, 0.3),
+        asteroid: Color::WHITE,
+        bullet: Color::YELLOW,
+        explosion: Color::WHITE,
+        star: Color::WHITE,
+        hazard: Color::CYAN,
+        text: Color::WHITE,
+        text_secondary: Color::WHITE,
+        text_highlight: Color::YELLOW,
+    };
+
+    pub const PRESETS: [Theme; 4] = [
+        Self::CLASSIC,
+        Self::VECTOR_CRT,
+        Self::AMBER,
+        Self::HIGH_CONTRAST,
+    ];
+
+    pub fn color(&self, color: ThemeColor) -> Color {
+        match color {
+            ThemeColor::Rocket => self.rocket,
+            ThemeColor::Alien => self.alien,
+            ThemeColor::Asteroid => self.asteroid,
+            ThemeColor::Bullet => self.bullet,
+            ThemeColor::Explosion => self.explosion,
+            ThemeColor::Star => self.star,
+            ThemeColor::Hazard => self.hazard,
+            ThemeColor::Text => self.text,
+            ThemeColor::TextSecondary => self.text_secondary,
+            ThemeColor::TextHighlight => self.text_highlight,
+        }
+    }
+
+    fn next_preset(&self) -> Theme {
+        let index = Self::PRESETS.iter().position(|t| t.name == self.name).unwrap_or(0);
+        Self::PRESETS[(index + 1) % Self::PRESETS.len()].clone()
+    }
+}
+
+/// Colours the `Stroke` of this entity using the active theme.
+/// NOTE: The alpha channel of the stroke is left alone, so it can be animated independently.
+#[derive(Component)]
+pub struct Themed(pub ThemeColor);
+
+/// Colours each section of the `Text` of this entity using the active theme.
+#[derive(Component)]
+pub struct ThemedText(pub Vec<ThemeColor>);
+
+/// Colours the `BackgroundColor` of this UI node using the active theme (alpha is left alone).
+#[derive(Component)]
+pub struct ThemedBackground(pub ThemeColor);
+
+fn with_alpha_of(color: Color, original: Color) -> Color {
+    color.with_a(original.a())
+}
+
+// Systems
+
+fn theme_keyboard_system(
+    mut kb: ResMut<Input<KeyCode>>,
+    mut theme: ResMut<Theme>,
+) {
+    if kb.clear_just_released(KeyCode::F2) {
+        *theme = theme.next_preset();
+    }
+}
+
+fn theme_clear_color_system(theme: Res<Theme>, mut clear_color: ResMut<ClearColor>) {
+    if theme.is_changed() {
+        clear_color.0 = theme.background;
+    }
+}
+
+fn themed_stroke_system(
+    theme: Res<Theme>,
+    mut query: Query<(Ref<Themed>, &mut bevy_prototype_lyon::prelude::Stroke)>
+) {
+    // Colour newly spawned entities, or everything if the theme has changed
+    let recolour_all = theme.is_changed();
+    for (themed, mut stroke) in query.iter_mut() {
+        if recolour_all || themed.is_added() {
+            stroke.color = with_alpha_of(theme.color(themed.0), stroke.color);
+        }
+    }
+}
+
+fn themed_text_system(
+    theme: Res<Theme>,
+    mut query: Query<(Ref<ThemedText>, &mut Text)>
+) {
+    let recolour_all = theme.is_changed();
+    for (themed, mut text) in query.iter_mut() {
+        if recolour_all || themed.is_added() {
+            for (section, &color) in text.sections.iter_mut().zip(themed.0.iter()) {
+                section.style.color = with_alpha_of(theme.color(color), section.style.color);
+            }
+        }
+    }
+}
+
+fn themed_background_system(
+    theme: Res<Theme>,
+    mut query: Query<(Ref<ThemedBackground>, &mut BackgroundColor)>
+) {
+    let recolour_all = theme.is_changed();
+    for (themed, mut background) in query.iter_mut() {
+        if recolour_all || themed.is_added() {
+            background.0 = with_alpha_of(theme.color(themed.0), background.0);
+        }
+    }
+}